@@ -4,7 +4,7 @@ use crate::commands::{
     add::add,
     status::status,
     commit::commit,
-    checkout::checkout,
+    checkout::{ checkout, checkout_new_branch },
     branch::{ branch, branch_list },
     restore::restore,
     merge::merge,
@@ -18,10 +18,37 @@ use crate::commands::{
         add_remote,
         remove_remote,
         fetch_remote,
-    }
+        push_remote,
+    },
+    pull::pull,
+    clone::clone,
+    config::{
+        config_get,
+        config_set,
+        config_list,
+    },
+    log::log,
+    diff::diff,
+    merge_base::merge_base,
+    gc::gc,
+    fsck::fsck,
+    stash::{
+        stash_push,
+        stash_list,
+        stash_show,
+        stash_apply,
+        stash_pop,
+        stash_drop,
+    },
+    reflog::reflog,
+    reset::{ reset, reset_paths, ResetMode },
+    rm::rm,
+    mv::mv,
+    cherry_pick::{ cherry_pick, cherry_pick_continue, cherry_pick_abort },
 };
 use crate::core::io::read_file;
 use crate::core::hash::hash;
+use crate::core::migrate::migrate_if_needed;
 
 use std::path::Path;
 
@@ -51,6 +78,11 @@ enum Command {
     },
     Checkout {
         branch: String,
+        start: Option<String>,
+        #[arg(short = 'b')]
+        create: bool,
+        #[arg(short = 'm', long = "merge")]
+        merge: bool,
     },
     Branch {
         branch_name: Option<String>,
@@ -60,6 +92,12 @@ enum Command {
     },
     Restore {
         restore_path: String,
+        #[arg(short = 's', long = "source")]
+        source: Option<String>,
+        #[arg(short = 'S', long = "staged")]
+        staged: bool,
+        #[arg(short = 'W', long = "worktree")]
+        worktree: bool,
     },
     Merge {
         target_branch: String
@@ -79,11 +117,107 @@ enum Command {
         action: String,
         name: Option<String>,
         path: Option<String>,
+        #[arg(short = 'f', long = "force")]
+        force: bool,
+    },
+    Pull {
+        remote: Option<String>,
+        branch: Option<String>,
+    },
+    Clone {
+        source: String,
+        destination: Option<String>,
+    },
+    Config {
+        action: String,
+        key: Option<String>,
+        value: Option<String>,
+        #[arg(long = "global")]
+        global: bool,
+    },
+    Log {
+        revision: Option<String>,
+        #[arg(long = "oneline")]
+        oneline: bool,
+        #[arg(long = "graph")]
+        graph: bool,
+        #[arg(short = 'n')]
+        count: Option<usize>,
+        #[arg(last = true)]
+        paths: Vec<String>,
+    },
+    Diff {
+        commits: Vec<String>,
+        #[arg(long = "staged", alias = "cached")]
+        staged: bool,
+        #[arg(short = 'U', long = "unified", default_value_t = 3)]
+        context: usize,
+    },
+    MergeBase {
+        one: String,
+        two: String,
+        #[arg(long = "all")]
+        all: bool,
+    },
+    Gc {
+        #[arg(long = "prune", default_value_t = 14)]
+        prune_days: u64,
+    },
+    Fsck {},
+    Stash {
+        action: Option<String>,
+        #[arg(default_value_t = 0)]
+        index: usize,
+        #[arg(short = 'm', long = "message")]
+        message: Option<String>,
+        #[arg(short = 'u', long = "include-untracked")]
+        include_untracked: bool,
+    },
+    Reflog {
+        ref_name: Option<String>,
+    },
+    Reset {
+        target: Option<String>,
+        #[arg(long = "soft", conflicts_with_all = ["mixed", "hard"])]
+        soft: bool,
+        #[arg(long = "mixed", conflicts_with = "hard")]
+        mixed: bool,
+        #[arg(long = "hard")]
+        hard: bool,
+        #[arg(last = true)]
+        paths: Vec<String>,
+    },
+    Rm {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        #[arg(long = "cached")]
+        cached: bool,
+        #[arg(short = 'r')]
+        recursive: bool,
+        #[arg(short = 'f', long = "force")]
+        force: bool,
+    },
+    Mv {
+        source: String,
+        destination: String,
+        #[arg(short = 'f', long = "force")]
+        force: bool,
+    },
+    CherryPick {
+        #[arg(required_unless_present_any = ["continue_pick", "abort"])]
+        revisions: Vec<String>,
+        #[arg(long = "continue", conflicts_with_all = ["abort", "revisions"])]
+        continue_pick: bool,
+        #[arg(long = "abort", conflicts_with = "revisions")]
+        abort: bool,
     },
 }
 
 pub fn run_command() -> std::io::Result<()> {
     let tokens = Cli::parse();
+    if !matches!(tokens.command, None | Some(Command::Init { .. }) | Some(Command::Clone { .. })) {
+        migrate_if_needed()?;
+    }
     match tokens {
         Cli { command: Some(Command::Init { input_path })} => {
             init(input_path);
@@ -102,8 +236,14 @@ pub fn run_command() -> std::io::Result<()> {
         Cli { command: Some(Command::Commit { message })} => {
             commit(message)?;
         },
-        Cli { command: Some(Command::Checkout { branch })} => {
-            checkout(branch)?;
+        Cli { command: Some(Command::Checkout { branch, start, create, merge })} => {
+            if create {
+                checkout_new_branch(branch, start, merge)?;
+            } else if start.is_some() {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "A start point is only accepted with -b"));
+            } else {
+                checkout(branch, merge)?;
+            }
         },
         Cli { command: Some(Command::Branch { branch_name, source_oid, list })} => {
             if list {
@@ -113,8 +253,8 @@ pub fn run_command() -> std::io::Result<()> {
                 branch(b_name, source_oid)?;
             }
         },
-        Cli { command: Some(Command::Restore { restore_path })} => {
-            restore(restore_path)?;
+        Cli { command: Some(Command::Restore { restore_path, source, staged, worktree })} => {
+            restore(restore_path, source, staged, worktree)?;
         },
         Cli { command: Some(Command::Merge { target_branch })} => {
             merge(target_branch)?;
@@ -133,7 +273,7 @@ pub fn run_command() -> std::io::Result<()> {
         Cli { command: Some(Command::Resolve { file_path })} => {
             resolve(&file_path)?;
         },
-        Cli { command: Some(Command::Remote { action, name, path })} => {
+        Cli { command: Some(Command::Remote { action, name, path, force })} => {
             match action.as_str() {
                 "add" => {
                     add_remote(name.unwrap(), path.unwrap())?;
@@ -145,11 +285,100 @@ pub fn run_command() -> std::io::Result<()> {
                 "fetch" => {
                     fetch_remote(name.unwrap())?;
                 }
+                "push" => {
+                    // The third positional is the branch to push when the action is push
+                    push_remote(name.unwrap(), path, force)?;
+                }
                 _ => {
                     return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid remote action"));
                 }
             }
         },
+        Cli { command: Some(Command::Pull { remote, branch })} => {
+            pull(remote, branch)?;
+        },
+        Cli { command: Some(Command::Clone { source, destination })} => {
+            clone(source, destination)?;
+        },
+        Cli { command: Some(Command::Config { action, key, value, global })} => {
+            match action.as_str() {
+                "get" => {
+                    config_get(key.unwrap())?;
+                },
+                "set" => {
+                    config_set(key.unwrap(), value.unwrap(), global)?;
+                },
+                "list" => {
+                    config_list(global, true)?;
+                },
+                _ => {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid config action"));
+                }
+            }
+        },
+        Cli { command: Some(Command::Log { revision, oneline, graph, count, paths })} => {
+            log(revision, oneline, graph, count, paths, true)?;
+        },
+        Cli { command: Some(Command::Diff { commits, staged, context })} => {
+            diff(staged, commits, context, true)?;
+        },
+        Cli { command: Some(Command::MergeBase { one, two, all })} => {
+            merge_base(&one, &two, all, true)?;
+        },
+        Cli { command: Some(Command::Gc { prune_days })} => {
+            gc(prune_days, true)?;
+        },
+        Cli { command: Some(Command::Fsck { })} => {
+            fsck(true)?;
+        },
+        Cli { command: Some(Command::Stash { action, index, message, include_untracked })} => {
+            match action.as_deref().unwrap_or("push") {
+                "push" => stash_push(message, include_untracked)?,
+                "list" => { stash_list(true)?; },
+                "show" => { stash_show(index, true)?; },
+                "apply" => { stash_apply(index)?; },
+                "pop" => stash_pop(index)?,
+                "drop" => stash_drop(index)?,
+                _ => {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid stash action"));
+                }
+            }
+        },
+        Cli { command: Some(Command::Reflog { ref_name })} => {
+            reflog(ref_name, true)?;
+        },
+        Cli { command: Some(Command::Reset { target, soft, mixed: _, hard, paths })} => {
+            if !paths.is_empty() {
+                if soft || hard {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Paths can only be reset in the index, without --soft or --hard"));
+                }
+                reset_paths(target, paths)?;
+            } else {
+                let mode = if soft {
+                    ResetMode::Soft
+                } else if hard {
+                    ResetMode::Hard
+                } else {
+                    ResetMode::Mixed
+                };
+                reset(target, mode)?;
+            }
+        },
+        Cli { command: Some(Command::Rm { paths, cached, recursive, force })} => {
+            rm(paths, cached, recursive, force)?;
+        },
+        Cli { command: Some(Command::Mv { source, destination, force })} => {
+            mv(source, destination, force)?;
+        },
+        Cli { command: Some(Command::CherryPick { revisions, continue_pick, abort })} => {
+            if continue_pick {
+                cherry_pick_continue()?;
+            } else if abort {
+                cherry_pick_abort()?;
+            } else {
+                cherry_pick(revisions)?;
+            }
+        },
         Cli { command: None } => {}
     }
 
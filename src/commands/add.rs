@@ -5,7 +5,7 @@ use std::os::unix::fs::PermissionsExt;
 
 use crate::core::io::{ read_file, write_object };
 use crate::core::index::{ read_index, write_index, IndexEntry, EntryType };
-use crate::core::hash::hash;
+use crate::core::object::Object;
 use crate::core::repo::find_repo_root;
 use crate::core::ignore::should_ignore;
 
@@ -45,8 +45,7 @@ fn walk(path: &Path, entries: &mut Vec<IndexEntry>) -> std::io::Result<()> {
         }
 
         let file = read_file(&abs_path.to_string_lossy())?;
-        let blob = hash(&file);
-        write_object(&file, &blob)?;
+        let blob = write_object(&Object::Blob(file))?;
         update_or_insert(blob, rel_str, entries)?;
     }
     Ok(())
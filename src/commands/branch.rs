@@ -1,6 +1,6 @@
-use crate::core::io::{ read_file, write_file };
+use crate::core::io::read_file;
 use crate::core::repo::find_repo_root;
-use crate::core::refs::list_refs;
+use crate::core::refs::{ list_refs, resolve_head, resolve_revision, update_ref };
 
 pub fn branch(branch: String, source_oid: Option<String>) -> std::io::Result<()> {
 
@@ -18,27 +18,13 @@ pub fn branch(branch: String, source_oid: Option<String>) -> std::io::Result<()>
         return Ok(());
     }
 
-    if let Some(oid) = source_oid {
-        if let Some(parent) = refs_dir.join(&branch).parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        write_file(&oid.as_bytes().to_vec(), &refs_dir.join(&branch))?;
+    if let Some(source) = source_oid {
+        let oid = resolve_revision(&source)?;
+        update_ref(&branch, &oid, &format!("branch: Created from {}", source))?;
         println!("Branch {} created at {}", branch, oid);
     } else {
-        let proj_head = nag_head.join("HEAD");
-        let proj_head_contents = read_file(&proj_head.to_string_lossy())?;
-        let head_str = String::from_utf8_lossy(&proj_head_contents);
-
-        let target = head_str.trim();
-        let branch_path_fragment = target.strip_prefix("ref: ").unwrap_or(target);
-        let branch_path = nag_head.join(branch_path_fragment);
-        let branch_contents = read_file(&branch_path.to_string_lossy())?;
-        let oid = String::from_utf8_lossy(&branch_contents).trim().to_string();
-
-        if let Some(parent) = refs_dir.join(&branch).parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        write_file(&oid.as_bytes().to_vec(), &refs_dir.join(&branch))?;
+        let oid = resolve_head()?.1;
+        update_ref(&branch, &oid, "branch: Created from HEAD")?;
 
         println!("Branch {} created at {}", branch, oid);
     }
@@ -1,85 +1,264 @@
-use crate::commands::status::status;
-use crate::core::io::read_file;
+use crate::commands::branch::branch;
+use crate::core::io::{ read_file, read_object, has_object, write_object };
 use crate::core::repo::find_repo_root;
+use crate::core::refs::{
+    resolve_head,
+    resolve_revision,
+    set_head_detached,
+    set_head_ref,
+    read_ref,
+    get_ref_path,
+    read_merge_head,
+};
 use crate::core::tree::read_tree_to_index;
-use crate::core::io::write_file;
-use crate::core::index::write_index;
+use crate::core::io::{ write_file, remove_empty_parents };
+use crate::core::index::{ read_index, write_index, IndexEntry, EntryType };
+use crate::core::diff::{ get_all_diffs, DiffType, merge3, is_binary, MergeLabels };
+use crate::core::commit::read_commit;
+use crate::core::object::Object;
+use crate::core::reflog::reflog_path;
 
-use std::fs::{ read_dir, remove_file, remove_dir_all, create_dir_all };
+use std::collections::HashMap;
+use std::fs::remove_file;
 use std::path::Path;
 
-pub fn checkout(branch: String) -> std::io::Result<()> {
-    if status(false)?.len() > 0 {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("There are un-committed changes made. Please save your changes before checkout"),
-        ));
-    }
-
+pub fn checkout(branch: String, merge: bool) -> std::io::Result<()> {
     let root = find_repo_root()?;
     let nag_dir = root.join(".nag");
+    // A branch is checked out by name; any other revision (a commit, tag or
+    // remote-tracking branch) detaches HEAD at the commit it names
     let branch_path = nag_dir.join(format!("refs/heads/{}", branch));
-    if !branch_path.exists() {
+    let (branch_name, commit_oid) = if branch_path.is_file() {
+        let branch_contents = read_file(&branch_path.to_string_lossy())?;
+        (Some(branch.clone()), String::from_utf8_lossy(&branch_contents).trim().to_string())
+    } else if let Ok(oid) = resolve_revision(&branch) {
+        (None, oid)
+    } else {
         return Err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
-            format!("Branch '{}' not found", branch),
+            format!("Branch or revision '{}' not found", branch),
         ));
-    }
-    let branch_contents = read_file(&branch_path.to_string_lossy())?;
-    let branch_str = String::from_utf8_lossy(&branch_contents);
+    };
 
-    let commit_path = nag_dir.join("objects").join(branch_str.trim());
-    if !commit_path.exists() {
+    if !has_object(&nag_dir.join("objects"), &commit_oid) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
             format!("Commit '{}' not found", branch),
         ));
     }
-    let commit_contents = read_file(&commit_path.to_string_lossy())?;
+    let commit_contents = read_object(&commit_oid)?.into_payload("commit")?;
     let commit_str = String::from_utf8_lossy(&commit_contents);
 
     let tree_line = commit_str.lines().next().unwrap();
     let tree_oid = tree_line.strip_prefix("tree ").unwrap().trim();
-    let tree_path = nag_dir.join("objects").join(tree_oid.trim());
-    if !tree_path.exists() {
+    if !has_object(&nag_dir.join("objects"), tree_oid.trim()) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
             format!("Commit\'s tree '{}' not found", branch),
         ));
     }
 
-    for entry in read_dir(root)? {
-        let entry = entry?;
-        let path = entry.path();
+    let index = read_index()?;
+    let moving_from = match resolve_head() {
+        Ok((Some(current), _)) => current,
+        Ok((None, oid)) => oid,
+        Err(_) => "HEAD".to_string(),
+    };
+    let reflog_message = format!("checkout: moving from {} to {}", moving_from, branch);
+    if read_merge_head()?.is_some() || index.iter().any(|entry| entry.entry_type == EntryType::X) {
+        return Err(std::io::Error::other(
+            "Cannot checkout: there are unresolved conflicts. Resolve them and commit first",
+        ));
+    }
+
+    let current_oid = resolve_head()?.1;
+    let current_index = if current_oid.is_empty() {
+        Vec::new()
+    } else {
+        read_tree_to_index(&read_commit(&current_oid)?.tree)?
+    };
+    let target_index = read_tree_to_index(tree_oid)?;
+    let current_map: HashMap<&str, &str> = current_index.iter().map(|e| (e.path.as_str(), e.oids[0].as_str())).collect();
+    let target_map: HashMap<&str, &IndexEntry> = target_index.iter().map(|e| (e.path.as_str(), e)).collect();
 
-        if entry.file_name() == ".nag" {
-            continue;
+    // Local changes to paths both commits agree on are carried over untouched; the rest
+    // would be overwritten, so they stop the checkout unless they can be merged in
+    let diffs = get_all_diffs()?;
+    let mut local_paths: Vec<String> = [DiffType::Added, DiffType::Modified, DiffType::Deleted, DiffType::Staged, DiffType::StagedDelete]
+        .iter()
+        .flat_map(|kind| diffs.get(kind).cloned().unwrap_or_default())
+        .collect();
+    local_paths.sort();
+    local_paths.dedup();
+    let (carried, clashing): (Vec<&String>, Vec<&String>) = local_paths.iter()
+        .partition(|path| current_map.get(path.as_str()).copied() == target_map.get(path.as_str()).map(|e| e.oids[0].as_str()));
+    if !clashing.is_empty() && !merge {
+        return Err(std::io::Error::other(format!(
+            "Your un-committed changes to these files would be overwritten by checkout:\n\t{}\nCommit them first, or checkout with --merge to merge them into '{}'",
+            clashing.iter().map(|path| path.as_str()).collect::<Vec<_>>().join("\n\t"),
+            branch,
+        )));
+    }
+
+    // Every merge is worked out before the working directory is touched
+    let mut merged = Vec::new();
+    for path in &clashing {
+        let full_path = root.join(path);
+        let Some(target_entry) = target_map.get(path.as_str()).filter(|_| full_path.is_file()) else {
+            return Err(std::io::Error::other(
+                format!("Cannot merge local changes to '{}': it is deleted on one side", path),
+            ));
+        };
+        let ours = read_file(&full_path.to_string_lossy())?;
+        let base = match current_map.get(path.as_str()) {
+            Some(oid) => read_object(oid)?.into_payload("blob")?,
+            None => Vec::new(),
+        };
+        let theirs = read_object(&target_entry.oids[0])?.into_payload("blob")?;
+        if is_binary(&ours) || is_binary(&base) || is_binary(&theirs) {
+            return Err(std::io::Error::other(
+                format!("Cannot merge local changes to binary file '{}'", path),
+            ));
         }
 
-        if path.is_dir() {
-            remove_dir_all(&path)?;
+        let labels = MergeLabels { ours: "local", base: "HEAD", theirs: &branch };
+        let (text, conflict) = merge3(
+            &String::from_utf8_lossy(&base),
+            &String::from_utf8_lossy(&ours),
+            &String::from_utf8_lossy(&theirs),
+            &labels,
+        );
+        let entry = if conflict {
+            IndexEntry {
+                entry_type: EntryType::X,
+                path: path.to_string(),
+                mode: target_entry.mode.clone(),
+                oids: vec![write_object(&Object::Blob(ours))?, target_entry.oids[0].clone()],
+            }
         } else {
-            remove_file(&path)?;
+            (*target_entry).clone()
+        };
+        merged.push((text, entry));
+    }
+
+    let untouched = |entry: &&IndexEntry| local_paths.binary_search(&entry.path).is_err();
+    let from: Vec<IndexEntry> = current_index.iter().filter(untouched).cloned().collect();
+    let to: Vec<IndexEntry> = target_index.iter().filter(untouched).cloned().collect();
+    update_working_tree(&root, &from, &to)?;
+
+    // The target's index, keeping whatever is staged for carried paths
+    let mut new_index = to;
+    for path in &carried {
+        if let Some(entry) = index.iter().find(|entry| &entry.path == *path) {
+            new_index.push(entry.clone());
+        }
+    }
+    let mut conflicted = Vec::new();
+    for (text, entry) in merged {
+        write_file(&text.into_bytes(), &root.join(&entry.path))?;
+        if entry.entry_type == EntryType::X {
+            conflicted.push(entry.path.clone());
+        }
+        new_index.push(entry);
+    }
+    new_index.sort_by(|a, b| a.path.cmp(&b.path));
+
+    write_index(&new_index)?;
+    if let Some(branch_name) = branch_name {
+        set_head_ref(&branch_name, &reflog_message)?;
+    } else {
+        set_head_detached(&commit_oid, &reflog_message)?;
+        println!("Note: checking out '{}' leaves HEAD detached at {}.", branch, commit_oid);
+        println!("Commits made now belong to no branch; to keep them, create one with:");
+        println!("\tnag branch <new-branch-name> {}", commit_oid);
+    }
+
+    if !conflicted.is_empty() {
+        println!("Conflicts while merging local changes into:\n\t{}", conflicted.join("\n\t"));
+        println!("Fix them and run resolve on each file");
+    }
+
+    Ok(())
+}
+
+/// Creates a branch at `start` (HEAD by default) and checks it out. The branch is not
+/// kept if the checkout fails.
+pub fn checkout_new_branch(name: String, start: Option<String>, merge: bool) -> std::io::Result<()> {
+    if read_ref(&name).is_ok() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Branch '{}' already exists", name),
+        ));
+    }
+    branch(name.clone(), start)?;
+
+    // Nothing is committed yet, so there is only HEAD to move
+    if read_ref(&name)?.is_empty() {
+        return set_head_ref(&name, "");
+    }
+    if let Err(e) = checkout(name.clone(), merge) {
+        std::fs::remove_file(get_ref_path(&name)?)?;
+        // Its log goes too, or a branch created later under the name would inherit it
+        let log_path = reflog_path(&format!("refs/heads/{}", name))?;
+        if log_path.is_file() {
+            std::fs::remove_file(log_path)?;
         }
+        return Err(e);
     }
 
-    let index = read_tree_to_index(tree_oid)?;
-    for entry in &index {
-        let oid = entry.oids[0].clone();
-        let path = Path::new(&entry.path);
+    Ok(())
+}
+
+/// Moves the working directory from one tree's files to another's, touching only paths
+/// whose content differs. Untracked and ignored files are kept, and nothing is changed
+/// if one of them sits where the target tree needs to write.
+pub fn update_working_tree(root: &Path, current: &[IndexEntry], target: &[IndexEntry]) -> std::io::Result<()> {
+    let current_map: HashMap<&str, &str> = current.iter().map(|e| (e.path.as_str(), e.oids[0].as_str())).collect();
+    let target_map: HashMap<&str, &str> = target.iter().map(|e| (e.path.as_str(), e.oids[0].as_str())).collect();
 
-        if let Some(parent) = path.parent() { 
-            create_dir_all(parent)?;
+    let mut blocked = Vec::new();
+    for (path, oid) in &target_map {
+        if current_map.contains_key(path) {
+            continue;
         }
-        let object_path = nag_dir.join("objects").join(oid);
-        let obj_contents = read_file(&object_path.to_string_lossy())?;
-        write_file(&obj_contents, &path)?;
+        let full_path = root.join(path);
+        let clashes = if full_path.is_file() {
+            Object::Blob(std::fs::read(&full_path)?).oid() != *oid
+        } else {
+            full_path.exists()
+        };
+        // A parent directory that is an untracked file would have to be replaced as well
+        let parent_clashes = Path::new(path).ancestors().skip(1)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .any(|dir| root.join(dir).is_file() && !current_map.contains_key(dir.to_string_lossy().as_ref()));
+        if clashes || parent_clashes {
+            blocked.push(path.to_string());
+        }
+    }
+    if !blocked.is_empty() {
+        blocked.sort();
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Untracked files would be overwritten by checkout:\n\t{}", blocked.join("\n\t")),
+        ));
     }
 
-    write_index(&index)?;
-    let head_path = nag_dir.join("HEAD");
-    let new_head = format!("ref: refs/heads/{}", branch);
-    write_file(&new_head.as_bytes().to_vec(), &head_path)?;
+    for path in current_map.keys().filter(|path| !target_map.contains_key(*path)) {
+        let full_path = root.join(path);
+        if full_path.is_file() {
+            remove_file(&full_path)?;
+        }
+        // Directories emptied by the removal go too, but never ones still holding other files
+        remove_empty_parents(root, &full_path);
+    }
+
+    for entry in target {
+        if current_map.get(entry.path.as_str()) == Some(&entry.oids[0].as_str()) {
+            continue;
+        }
+        let obj_contents = read_object(&entry.oids[0])?.into_payload("blob")?;
+        write_file(&obj_contents, &root.join(&entry.path))?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,163 @@
+use crate::commands::merge::three_way_merge;
+use crate::commands::commit::commit_with_author;
+use crate::commands::status::status;
+use crate::commands::reset::{ reset, ResetMode };
+use crate::core::repo::find_repo_root;
+use crate::core::io::{ read_file, write_file };
+use crate::core::refs::{ resolve_head, resolve_revision, read_merge_head };
+use crate::core::commit::read_commit;
+use crate::core::tree::write_tree_from_index;
+use crate::core::index::{ read_index, EntryType };
+
+use std::path::PathBuf;
+
+/*
+A cherry-pick in progress keeps its state in .nag/sequencer: `head` holds the commit
+HEAD was on when it started (for --abort) and `todo` the commits still to be picked,
+one per line, the one that stopped with conflicts first.
+*/
+
+/// Applies the changes each commit made (relative to its parent) on top of HEAD, one new
+/// commit per pick. A conflict stops the sequence until `--continue` or `--abort`.
+pub fn cherry_pick(revisions: Vec<String>) -> std::io::Result<()> {
+    if sequencer_dir()?.is_dir() {
+        return Err(std::io::Error::other(
+            "A cherry-pick is already in progress. Use --continue or --abort",
+        ));
+    }
+    if read_merge_head()?.is_some() {
+        return Err(std::io::Error::other(
+            "Cannot cherry-pick: a merge is in progress. Resolve the conflicts and commit first",
+        ));
+    }
+    if !status(false)?.is_empty() {
+        return Err(std::io::Error::other("Cannot cherry-pick: working directory not clean"));
+    }
+    let head_oid = resolve_head()?.1;
+    if head_oid.is_empty() {
+        return Err(std::io::Error::other("Cannot cherry-pick before the first commit"));
+    }
+
+    let mut todo = Vec::new();
+    for revision in &revisions {
+        let oid = resolve_revision(revision)?;
+        if read_commit(&oid)?.parents.len() > 1 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Commit {} is a merge and cannot be cherry-picked", revision),
+            ));
+        }
+        todo.push(oid);
+    }
+
+    let dir = sequencer_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    write_file(&head_oid.into_bytes(), &dir.join("head"))?;
+    write_todo(&todo)?;
+
+    pick_remaining(todo)
+}
+
+/// Commits the resolved conflicts of the pick that stopped, then picks the rest.
+pub fn cherry_pick_continue() -> std::io::Result<()> {
+    let mut todo = read_todo()?;
+    if read_index()?.iter().any(|entry| entry.entry_type == EntryType::X) {
+        return Err(std::io::Error::other(
+            "There are unresolved conflicts. Run resolve on each conflicted file first",
+        ));
+    }
+
+    if let Some(current) = todo.first() {
+        // Nothing is committed if the resolution (or an earlier commit) left no changes
+        let head_tree = read_commit(&resolve_head()?.1)?.tree;
+        if write_tree_from_index(&read_index()?)? != head_tree {
+            commit_picked(current)?;
+        }
+        todo.remove(0);
+        write_todo(&todo)?;
+    }
+
+    pick_remaining(todo)
+}
+
+/// Gives up on the cherry-pick, putting HEAD, the index and the working directory back
+/// where they were before it started.
+pub fn cherry_pick_abort() -> std::io::Result<()> {
+    let dir = sequencer_dir()?;
+    if !dir.is_dir() {
+        return Err(no_cherry_pick());
+    }
+    let head_contents = read_file(&dir.join("head").to_string_lossy())?;
+    let original = String::from_utf8_lossy(&head_contents).trim().to_string();
+
+    reset(Some(original), ResetMode::Hard)?;
+    std::fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+fn pick_remaining(mut todo: Vec<String>) -> std::io::Result<()> {
+    while let Some(oid) = todo.first().cloned() {
+        let picked = read_commit(&oid)?;
+        let ancestor_tree = match picked.parents.first() {
+            Some(parent) => read_commit(parent)?.tree,
+            None => write_tree_from_index(&Vec::new())?,
+        };
+
+        let head_oid = resolve_head()?.1;
+        if let Err(e) = three_way_merge(&head_oid, &oid, &ancestor_tree) {
+            if !read_index()?.iter().any(|entry| entry.entry_type == EntryType::X) {
+                return Err(e);
+            }
+            return Err(std::io::Error::other(format!(
+                "Could not apply {} {}. Fix the conflicts, run resolve on each file, then cherry-pick --continue (or --abort)",
+                &oid[..7], picked.summary(),
+            )));
+        }
+
+        if write_tree_from_index(&read_index()?)? == read_commit(&head_oid)?.tree {
+            println!("Skipping {} {}: its changes are already in HEAD", &oid[..7], picked.summary());
+        } else {
+            commit_picked(&oid)?;
+            println!("Picked {} {}", &oid[..7], picked.summary());
+        }
+        todo.remove(0);
+        write_todo(&todo)?;
+    }
+
+    std::fs::remove_dir_all(sequencer_dir()?)?;
+    Ok(())
+}
+
+// Commits the index as the picked commit: its author and message, with a trailer naming
+// the commit it came from
+fn commit_picked(oid: &str) -> std::io::Result<()> {
+    let picked = read_commit(oid)?;
+    let message = format!("{}\n\n(cherry picked from commit {})", picked.message.trim_end(), oid);
+    commit_with_author(message, picked.author)
+}
+
+fn sequencer_dir() -> std::io::Result<PathBuf> {
+    Ok(find_repo_root()?.join(".nag").join("sequencer"))
+}
+
+fn read_todo() -> std::io::Result<Vec<String>> {
+    let todo_path = sequencer_dir()?.join("todo");
+    if !todo_path.is_file() {
+        return Err(no_cherry_pick());
+    }
+    let contents = read_file(&todo_path.to_string_lossy())?;
+    Ok(String::from_utf8_lossy(&contents).lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect())
+}
+
+fn write_todo(todo: &[String]) -> std::io::Result<()> {
+    let buf = todo.iter().map(|oid| format!("{}\n", oid)).collect::<String>();
+    write_file(&buf.into_bytes(), &sequencer_dir()?.join("todo"))
+}
+
+fn no_cherry_pick() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "No cherry-pick in progress",
+    )
+}
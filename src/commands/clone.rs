@@ -0,0 +1,102 @@
+use crate::commands::init::init;
+use crate::commands::remote::{ add_remote, fetch_remote };
+use crate::core::io::{ read_file, read_object, write_file };
+use crate::core::refs::{ list_refs, read_ref, update_ref, set_head_ref, set_head_detached };
+use crate::core::tree::read_tree_to_index;
+use crate::core::index::write_index;
+use crate::core::migrate::check_format;
+
+use std::path::Path;
+use std::fs::{ create_dir_all, read_dir };
+
+pub fn clone(source: String, destination: Option<String>) -> std::io::Result<()> {
+    let source_path = Path::new(&source).canonicalize()?;
+    let source_nag = source_path.join(".nag");
+    if !source_nag.is_dir() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("'{}' is not a NAG repository", source),
+        ));
+    }
+    check_format(&source_nag)?;
+
+    let dest = match destination {
+        Some(d) => d,
+        None => {
+            let Some(name) = source_path.file_name() else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Cannot derive a destination directory from the source path",
+                ));
+            };
+            name.to_string_lossy().to_string()
+        }
+    };
+    let dest_path = Path::new(&dest);
+    if dest_path.exists() && read_dir(dest_path)?.next().is_some() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Destination '{}' already exists and is not empty", dest),
+        ));
+    }
+    create_dir_all(dest_path)?;
+    let dest_path = dest_path.canonicalize()?;
+
+    init(Some(dest_path.to_string_lossy().to_string()));
+
+    // Every other command works relative to the current directory, so move into the new repository
+    std::env::set_current_dir(&dest_path)?;
+
+    add_remote("origin".to_string(), source_path.to_string_lossy().to_string())?;
+    fetch_remote("origin".to_string())?;
+
+    for remote_branch in list_refs("refs/remotes/origin")? {
+        let oid = read_ref(&format!("refs/remotes/origin/{}", remote_branch))?;
+        update_ref(&remote_branch, &oid, &format!("clone: from {}", source_path.display()))?;
+    }
+
+    let remote_head_contents = read_file(&source_nag.join("HEAD").to_string_lossy())?;
+    let remote_head_str = String::from_utf8_lossy(&remote_head_contents);
+    let remote_head = remote_head_str.trim();
+
+    let head_oid = if let Some(branch) = remote_head.strip_prefix("ref: refs/heads/") {
+        if read_ref(branch).is_err() {
+            update_ref(branch, "", "")?;
+        }
+        set_head_ref(branch, &format!("clone: from {}", source_path.display()))?;
+        read_ref(branch)?
+    } else {
+        set_head_detached(remote_head, &format!("clone: from {}", source_path.display()))?;
+        remote_head.to_string()
+    };
+
+    // Drop the bootstrap branch from init if the source never had one
+    let bootstrap = dest_path.join(".nag/refs/heads/main");
+    if read_ref("main")?.is_empty() && remote_head != "ref: refs/heads/main" {
+        std::fs::remove_file(bootstrap)?;
+    }
+
+    if head_oid.is_empty() {
+        println!("Cloned an empty repository into '{}'", dest_path.display());
+        return Ok(());
+    }
+
+    let commit_contents = read_object(&head_oid)?.into_payload("commit")?;
+    let commit_str = String::from_utf8_lossy(&commit_contents);
+    let tree_line = commit_str
+        .lines()
+        .find(|line| line.starts_with("tree "))
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "missing tree line"))?;
+    let tree_oid = tree_line["tree ".len()..].trim();
+
+    let index = read_tree_to_index(tree_oid)?;
+    for entry in &index {
+        let obj_contents = read_object(&entry.oids[0])?.into_payload("blob")?;
+        write_file(&obj_contents, &dest_path.join(&entry.path))?;
+    }
+    write_index(&index)?;
+
+    println!("Cloned '{}' into '{}'", source_path.display(), dest_path.display());
+
+    Ok(())
+}
@@ -1,13 +1,27 @@
-use crate::core::index::{ read_index, write_index };
+use crate::core::index::{ read_index, write_index, EntryType };
+use crate::core::refs::{ read_merge_head, clear_merge_head, update_head };
 use crate::core::tree::{ write_tree_from_index, read_tree_to_index };
 use crate::core::repo::find_repo_root;
-use crate::core::io::{ read_file, write_object, write_file };
-use crate::core::hash::hash;
+use crate::core::io::{ read_file, write_object };
+use crate::core::object::Object;
+use crate::core::config::signature;
+use crate::core::commit::Signature;
 
 pub fn commit(message: String) -> std::io::Result<()> {
+    commit_with_author(message, None)
+}
+
+/// Commits the index with `author` on the author line instead of the current user, who is
+/// still recorded as the committer. Used to replay someone else's commit.
+pub fn commit_with_author(message: String, author: Option<Signature>) -> std::io::Result<()> {
     let mut commit_str_buf = String::new();
 
     let index = read_index()?;
+    if index.iter().any(|entry| entry.entry_type == EntryType::X) {
+        return Err(std::io::Error::other(
+            "Cannot commit: there are unresolved conflicts. Run resolve on each conflicted file first",
+        ));
+    }
     let tree = write_tree_from_index(&index)?;
     commit_str_buf.push_str(&format!("tree {}\n", tree.trim()));
 
@@ -16,28 +30,50 @@ pub fn commit(message: String) -> std::io::Result<()> {
     let proj_head_contents = read_file(&proj_head.to_string_lossy())?;
     let head_str = String::from_utf8_lossy(&proj_head_contents);
 
+    // A detached HEAD holds the parent commit itself and is what the new commit advances
     let target = head_str.trim();
-    let branch_path_fragment = target.strip_prefix("ref: ").unwrap_or(target);
-    let branch_path = nag_head.join(branch_path_fragment);
-    let branch_contents = read_file(&branch_path.to_string_lossy())?;
+    let branch_contents = match target.strip_prefix("ref: ") {
+        Some(branch_path_fragment) => read_file(&nag_head.join(branch_path_fragment).to_string_lossy())?,
+        None => target.as_bytes().to_vec(),
+    };
     let branch_str = String::from_utf8_lossy(&branch_contents);
 
     if !branch_str.trim().is_empty() {
         commit_str_buf.push_str(&format!("parent {}\n", branch_str.trim()));
     }
 
-    // TODO Build author / user config and add in an author line
+    // A merge in progress contributes the merged commit as the second parent
+    let merge_head = read_merge_head()?;
+    if let Some(merge_oid) = &merge_head {
+        commit_str_buf.push_str(&format!("parent {}\n", merge_oid));
+    }
+
+    let signature = signature()?;
+    match author {
+        Some(author) => commit_str_buf.push_str(&format!("author {}\n", author)),
+        None => commit_str_buf.push_str(&format!("author {}\n", signature)),
+    }
+    commit_str_buf.push_str(&format!("committer {}\n", signature));
 
     commit_str_buf.push_str(&format!("\n{}\n", message.trim()));
 
-    let buffer_bytes = commit_str_buf.into_bytes();
-    let commit_hash = hash(&buffer_bytes);
-    write_object(&buffer_bytes, &commit_hash)?;
+    let commit_hash = write_object(&Object::Commit(commit_str_buf.into_bytes()))?;
 
-    write_file(&commit_hash.into_bytes(), &branch_path)?;
+    let kind = if merge_head.is_some() {
+        "commit (merge)"
+    } else if branch_str.trim().is_empty() {
+        "commit (initial)"
+    } else {
+        "commit"
+    };
+    update_head(&commit_hash, &format!("{}: {}", kind, message.trim()))?;
 
     let committed_index = read_tree_to_index(&tree)?;
     write_index(&committed_index)?;
 
+    if merge_head.is_some() {
+        clear_merge_head()?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,36 @@
+use crate::core::config::{ read_config, read_effective_config, get_config_value, set_config_value };
+
+pub fn config_get(key: String) -> std::io::Result<String> {
+    match get_config_value(&key)? {
+        Some(value) => {
+            println!("{}", value);
+            Ok(value)
+        },
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Config key '{}' is not set", key),
+        )),
+    }
+}
+
+pub fn config_set(key: String, value: String, global: bool) -> std::io::Result<()> {
+    set_config_value(&key, &value, global)?;
+    Ok(())
+}
+
+/// Lists the global config with `global`, otherwise every setting in effect for the
+/// repository: global values overridden by the repository's own.
+pub fn config_list(global: bool, print: bool) -> std::io::Result<String> {
+    let entries = if global { read_config(true)? } else { read_effective_config()? };
+
+    let mut output = String::new();
+    for (key, value) in &entries {
+        output.push_str(&format!("{}={}\n", key, value));
+    }
+
+    if print {
+        print!("{output}");
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,157 @@
+use crate::core::diff::{ unified_diff, is_binary };
+use crate::core::index::{ read_index, EntryType };
+use crate::core::commit::read_commit;
+use crate::core::refs::{ resolve_head, resolve_revision };
+use crate::core::repo::find_repo_root;
+use crate::core::io::{ read_file, read_object };
+use crate::core::tree::read_tree_to_index;
+
+use std::collections::{ BTreeSet, HashMap };
+
+enum Source {
+    Object(String),
+    Worktree(String),
+}
+
+pub fn diff(staged: bool, commits: Vec<String>, context: usize, print: bool) -> std::io::Result<String> {
+    let (old_side, new_side) = match commits.len() {
+        0 if staged => (head_side()?, index_side()?),
+        0 => (index_side()?, worktree_side()?),
+        2 => (commit_side(&commits[0])?, commit_side(&commits[1])?),
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Expected either no commits or exactly two commits to compare",
+            ));
+        }
+    };
+
+    let paths: BTreeSet<&String> = old_side.keys().chain(new_side.keys()).collect();
+
+    let mut output = String::new();
+    for path in paths {
+        let old = match old_side.get(path) {
+            Some((mode, source)) => Some((mode, read_source(source)?)),
+            None => None,
+        };
+        let new = match new_side.get(path) {
+            Some((mode, source)) => match read_source(source) {
+                Ok(bytes) => Some((mode, bytes)),
+                // A tracked file missing from the working tree shows up as a deletion
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            },
+            None => None,
+        };
+        output.push_str(&file_diff(path, old, new, context));
+    }
+
+    if print {
+        print!("{output}");
+    }
+
+    Ok(output)
+}
+
+fn file_diff(path: &str, old: Option<(&String, Vec<u8>)>, new: Option<(&String, Vec<u8>)>, context: usize) -> String {
+    let mut header = String::new();
+    header.push_str(&format!("diff --nag a/{} b/{}\n", path, path));
+
+    let (old_label, new_label) = match (&old, &new) {
+        (None, None) => return String::new(),
+        (None, Some((mode, _))) => {
+            header.push_str(&format!("new file mode {}\n", mode));
+            ("/dev/null".to_string(), format!("b/{}", path))
+        },
+        (Some((mode, _)), None) => {
+            header.push_str(&format!("deleted file mode {}\n", mode));
+            (format!("a/{}", path), "/dev/null".to_string())
+        },
+        (Some((old_mode, old_bytes)), Some((new_mode, new_bytes))) => {
+            if old_bytes == new_bytes && old_mode == new_mode {
+                return String::new();
+            }
+            if old_mode != new_mode {
+                header.push_str(&format!("old mode {}\nnew mode {}\n", old_mode, new_mode));
+            }
+            (format!("a/{}", path), format!("b/{}", path))
+        },
+    };
+
+    let empty = Vec::new();
+    let old_bytes = old.as_ref().map(|(_, b)| b).unwrap_or(&empty);
+    let new_bytes = new.as_ref().map(|(_, b)| b).unwrap_or(&empty);
+
+    let mut buf = String::new();
+    for line in header.lines() {
+        buf.push_str(&format!("\x1b[1m{}\x1b[0m\n", line));
+    }
+
+    if is_binary(old_bytes) || is_binary(new_bytes) {
+        buf.push_str(&format!("Binary files {} and {} differ\n", old_label, new_label));
+        return buf;
+    }
+    if old_bytes == new_bytes {
+        return buf;
+    }
+
+    buf.push_str(&format!("\x1b[1m--- {}\x1b[0m\n", old_label));
+    buf.push_str(&format!("\x1b[1m+++ {}\x1b[0m\n", new_label));
+
+    let hunks = unified_diff(&String::from_utf8_lossy(old_bytes), &String::from_utf8_lossy(new_bytes), context);
+    for line in hunks.lines() {
+        let colored = match line.chars().next() {
+            Some('@') => format!("\x1b[36m{}\x1b[0m\n", line),
+            Some('-') => format!("\x1b[31m{}\x1b[0m\n", line),
+            Some('+') => format!("\x1b[32m{}\x1b[0m\n", line),
+            _ => format!("{}\n", line),
+        };
+        buf.push_str(&colored);
+    }
+
+    buf
+}
+
+fn read_source(source: &Source) -> std::io::Result<Vec<u8>> {
+    let root = find_repo_root()?;
+    match source {
+        Source::Object(oid) => read_object(oid)?.into_payload("blob"),
+        Source::Worktree(path) => read_file(&root.join(path).to_string_lossy()),
+    }
+}
+
+fn index_side() -> std::io::Result<HashMap<String, (String, Source)>> {
+    Ok(read_index()?
+        .into_iter()
+        .filter(|entry| entry.entry_type == EntryType::C)
+        .map(|entry| (entry.path, (entry.mode, Source::Object(entry.oids[0].clone()))))
+        .collect())
+}
+
+fn worktree_side() -> std::io::Result<HashMap<String, (String, Source)>> {
+    Ok(read_index()?
+        .into_iter()
+        .filter(|entry| entry.entry_type == EntryType::C)
+        .map(|entry| (entry.path.clone(), (entry.mode, Source::Worktree(entry.path))))
+        .collect())
+}
+
+fn head_side() -> std::io::Result<HashMap<String, (String, Source)>> {
+    let head_oid = resolve_head()?.1;
+    if head_oid.is_empty() {
+        return Ok(HashMap::new());
+    }
+    tree_side(&read_commit(&head_oid)?.tree)
+}
+
+fn commit_side(revision: &str) -> std::io::Result<HashMap<String, (String, Source)>> {
+    let oid = resolve_revision(revision)?;
+    tree_side(&read_commit(&oid)?.tree)
+}
+
+fn tree_side(tree_oid: &str) -> std::io::Result<HashMap<String, (String, Source)>> {
+    Ok(read_tree_to_index(tree_oid)?
+        .into_iter()
+        .map(|entry| (entry.path, (entry.mode, Source::Object(entry.oids[0].clone()))))
+        .collect())
+}
@@ -0,0 +1,23 @@
+use crate::core::fsck::check_repository;
+
+pub fn fsck(print: bool) -> std::io::Result<String> {
+    let report = check_repository()?;
+    let output = report.errors.iter()
+        .chain(&report.dangling)
+        .map(|line| format!("{}\n", line))
+        .collect::<String>();
+
+    if print {
+        print!("{}", output);
+    }
+
+    // Dangling objects are normal leftovers; anything else fails so scripts notice
+    if !report.errors.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("fsck found {} problem(s)", report.errors.len()),
+        ));
+    }
+
+    Ok(output)
+}
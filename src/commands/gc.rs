@@ -0,0 +1,169 @@
+use crate::core::repo::find_repo_root;
+use crate::core::io::{ read_object_from, write_file, object_path, loose_oids };
+use crate::core::object::Object;
+use crate::core::index::read_index;
+use crate::core::refs::{ list_refs, read_ref, resolve_head, read_merge_head };
+use crate::core::reflog::{ reflog_oids, expire_reflogs };
+use crate::core::config::get_config_value;
+use crate::core::pack::{ PackInput, write_pack, list_packs, pack_oids, read_packed_object };
+
+use std::path::Path;
+use std::collections::HashSet;
+use std::fs::File;
+use std::time::{ Duration, SystemTime, UNIX_EPOCH };
+
+/// Days reflog entries are kept for when `gc.reflogExpire` is not set.
+pub const REFLOG_EXPIRE_DAYS: u64 = 90;
+
+/// Packs every reachable object into a single pack and drops unreachable objects that
+/// are older than `prune_days`. Younger unreachable objects are kept loose, so work that
+/// has not been referenced yet (such as freshly added blobs) survives a gc. Reflog entries
+/// older than `gc.reflogExpire` days are expired first and stop keeping their commits.
+pub fn gc(prune_days: u64, print: bool) -> std::io::Result<String> {
+    let objects_dir = find_repo_root()?.join(".nag").join("objects");
+    let grace = Duration::from_secs(prune_days * 24 * 60 * 60);
+
+    let expire_days = match get_config_value("gc.reflogExpire")? {
+        Some(days) => days.parse::<u64>().map_err(|_| std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("gc.reflogExpire must be a number of days, not '{}'", days),
+        ))?,
+        None => REFLOG_EXPIRE_DAYS,
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_err(std::io::Error::other)?.as_secs();
+    let expired = expire_reflogs(now.saturating_sub(expire_days * 24 * 60 * 60) as i64)?;
+
+    let mut seen = HashSet::new();
+    let mut inputs = Vec::new();
+    let mut stack: Vec<(String, String)> = gc_roots()?.into_iter().map(|oid| (oid, String::new())).collect();
+    while let Some((oid, hint)) = stack.pop() {
+        if !seen.insert(oid.clone()) {
+            continue;
+        }
+        let object = read_object_from(&objects_dir, &oid)?;
+        let payload = String::from_utf8_lossy(object.data()).to_string();
+        match object {
+            Object::Commit(_) => {
+                for line in payload.lines().take_while(|line| !line.is_empty()) {
+                    if let Some(tree_oid) = line.strip_prefix("tree ") {
+                        stack.push((tree_oid.trim().to_string(), String::new()));
+                    } else if let Some(parent_oid) = line.strip_prefix("parent ") {
+                        stack.push((parent_oid.trim().to_string(), String::new()));
+                    }
+                }
+            },
+            Object::Tree(_) => {
+                for line in payload.lines() {
+                    let parts: Vec<&str> = line.split('\t').collect();
+                    if parts.len() >= 4 {
+                        stack.push((parts[3].to_string(), parts[2].to_string()));
+                    }
+                }
+            },
+            Object::Tag(_) => {
+                if let Some(target) = payload.lines().next().and_then(|l| l.strip_prefix("object ")) {
+                    stack.push((target.trim().to_string(), String::new()));
+                }
+            },
+            Object::Blob(_) => {},
+        }
+        inputs.push(PackInput { object, hint });
+    }
+
+    let loose = loose_oids(&objects_dir)?;
+    let loose_set: HashSet<String> = loose.iter().map(|(oid, _)| oid.clone()).collect();
+    let old_packs = list_packs(&objects_dir)?;
+    let packed_count = inputs.len();
+
+    // Reachable objects are safely in the new pack before anything is removed
+    let new_pack = if inputs.is_empty() { None } else { Some(write_pack(&objects_dir, inputs)?) };
+
+    let mut pruned = 0;
+    for idx_path in &old_packs {
+        if Some(idx_path) == new_pack.as_ref() {
+            continue;
+        }
+        let pack_path = idx_path.with_extension("pack");
+        let pack_mtime = std::fs::metadata(&pack_path)?.modified()?;
+        for oid in pack_oids(idx_path)? {
+            if seen.contains(&oid) || loose_set.contains(&oid) {
+                continue;
+            }
+            if is_expired(pack_mtime, grace) {
+                pruned += 1;
+                continue;
+            }
+            // Unreachable but recent objects leave the pack as loose files that keep the pack's age
+            let Some(object) = read_packed_object(&objects_dir, &oid)? else {
+                continue;
+            };
+            let loose_path = object_path(&objects_dir, &oid);
+            write_file(&object.compress()?, &loose_path)?;
+            File::options().write(true).open(&loose_path)?.set_modified(pack_mtime)?;
+        }
+    }
+    for idx_path in &old_packs {
+        if Some(idx_path) != new_pack.as_ref() {
+            std::fs::remove_file(idx_path)?;
+            std::fs::remove_file(idx_path.with_extension("pack"))?;
+        }
+    }
+
+    for (oid, path) in &loose {
+        if seen.contains(oid) {
+            std::fs::remove_file(path)?;
+        } else if is_expired(std::fs::metadata(path)?.modified()?, grace) {
+            std::fs::remove_file(path)?;
+            pruned += 1;
+        }
+    }
+    remove_empty_fanout_dirs(&objects_dir)?;
+
+    let mut output = String::new();
+    if expired > 0 {
+        output.push_str(&format!("Expired {} reflog entries\n", expired));
+    }
+    output.push_str(&format!("Packed {} objects, pruned {} unreachable objects\n", packed_count, pruned));
+
+    if print {
+        print!("{}", output);
+    }
+
+    Ok(output)
+}
+
+// Everything gc must keep: refs, reflogs, a detached HEAD, an in-progress merge and the index
+fn gc_roots() -> std::io::Result<Vec<String>> {
+    let mut roots = Vec::new();
+    for name in list_refs("refs")? {
+        roots.push(read_ref(&format!("refs/{}", name))?);
+    }
+    // An unborn branch has no ref file yet, so only a detached HEAD adds a root
+    if let Ok((None, head_oid)) = resolve_head() {
+        roots.push(head_oid);
+    }
+    if let Some(merge_oid) = read_merge_head()? {
+        roots.push(merge_oid);
+    }
+    roots.extend(reflog_oids()?.into_iter().map(|(_, oid)| oid));
+    for entry in read_index()? {
+        roots.extend(entry.oids.into_iter().filter(|oid| oid != "empty"));
+    }
+    roots.retain(|oid| !oid.is_empty());
+    Ok(roots)
+}
+
+fn is_expired(modified: SystemTime, grace: Duration) -> bool {
+    SystemTime::now().duration_since(modified).unwrap_or(Duration::ZERO) >= grace
+}
+
+fn remove_empty_fanout_dirs(objects_dir: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(objects_dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        if path.is_dir() && name.len() == 2 && std::fs::read_dir(&path)?.next().is_none() {
+            std::fs::remove_dir(&path)?;
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use std::path::Path;
 use std::fs::{create_dir, create_dir_all, write};
+use crate::core::object::Object;
+use crate::core::migrate::{ FORMAT_KEY, FORMAT_VERSION };
 
 pub fn init(input_path: Option<String>) {
     let path = match &input_path {
@@ -31,9 +33,12 @@ pub fn init(input_path: Option<String>) {
 
     let _ = create_dir(&canon_path);
     let _ = create_dir(&obj_path);
-    let _ = write(obj_path.join("empty"), b"");
+    if let Ok(empty_blob) = Object::Blob(Vec::new()).compress() {
+        let _ = write(obj_path.join("empty"), empty_blob);
+    }
     let _ = create_dir_all(&head_dir_path);
     let _ = write(main_bootstrap_path, b"");
     let _ = write(head_file_path, b"ref: refs/heads/main\n");
     let _ = write(index_path, b"");
+    let _ = write(canon_path.join("config"), format!("{} = {}\n", FORMAT_KEY, FORMAT_VERSION));
 }
@@ -0,0 +1,274 @@
+use crate::core::commit::{ read_commit, Commit };
+use crate::core::refs::{ resolve_head, resolve_revision };
+use crate::core::tree::read_tree_to_index;
+
+use std::collections::{ HashMap, HashSet };
+
+pub fn log(revision: Option<String>, oneline: bool, graph: bool, count: Option<usize>, paths: Vec<String>, print: bool) -> std::io::Result<String> {
+    let start_oid = match revision {
+        Some(name) => resolve_revision(&name)?,
+        None => resolve_head()?.1,
+    };
+    if start_oid.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Current branch does not have any commits yet",
+        ));
+    }
+
+    let commits = collect_commits(&start_oid)?;
+    let ordered = topo_order(&start_oid, &commits);
+
+    let paths: Vec<String> = paths.iter().map(|p| normalize_path(p)).collect();
+    let shown: HashSet<String> = if paths.is_empty() {
+        ordered.iter().cloned().collect()
+    } else {
+        let mut tree_cache: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut touched = HashSet::new();
+        for oid in &ordered {
+            if touches_paths(&commits[oid], &commits, &paths, &mut tree_cache)? {
+                touched.insert(oid.clone());
+            }
+        }
+        touched
+    };
+
+    let mut display: Vec<&String> = ordered.iter().filter(|oid| shown.contains(*oid)).collect();
+    if let Some(n) = count {
+        display.truncate(n);
+    }
+
+    let parents = rewrite_parents(&ordered, &commits, &shown);
+
+    let mut output = String::new();
+    let mut lanes: Vec<String> = Vec::new();
+    for oid in display {
+        let commit = &commits[oid];
+        let entry = format_entry(commit, oneline);
+
+        if !graph {
+            output.push_str(&entry);
+            if !oneline {
+                output.push('\n');
+            }
+            continue;
+        }
+
+        if !lanes.contains(oid) {
+            lanes.push(oid.clone());
+        }
+        while let Some(dup) = lanes.iter().rposition(|lane| lane == oid).filter(|j| Some(*j) != lanes.iter().position(|lane| lane == oid)) {
+            output.push_str(&collapse_line(dup, lanes.len()));
+            lanes.remove(dup);
+        }
+        let col = lanes.iter().position(|lane| lane == oid).unwrap();
+        let commit_parents = &parents[oid];
+
+        let mut entry_lines = entry.lines();
+        let star_row = lanes.iter().enumerate()
+            .map(|(i, _)| if i == col { "*" } else { "|" })
+            .collect::<Vec<&str>>()
+            .join(" ");
+        output.push_str(&format!("{} {}\n", star_row, entry_lines.next().unwrap_or("")));
+
+        let body_row = lanes.iter().enumerate()
+            .map(|(i, _)| if i == col && commit_parents.is_empty() { " " } else { "|" })
+            .collect::<Vec<&str>>()
+            .join(" ");
+        for line in entry_lines {
+            output.push_str(format!("{} {}", body_row, line).trim_end());
+            output.push('\n');
+        }
+        if !oneline {
+            output.push_str(body_row.trim_end());
+            output.push('\n');
+        }
+
+        if commit_parents.is_empty() {
+            lanes.remove(col);
+            continue;
+        }
+        let trailing = lanes.len() - col - 1;
+        lanes[col] = commit_parents[0].clone();
+        let mut new_lanes = 0;
+        for parent in commit_parents.iter().skip(1) {
+            if !lanes.contains(parent) {
+                new_lanes += 1;
+                lanes.insert(col + new_lanes, parent.clone());
+            }
+        }
+        if new_lanes > 0 {
+            output.push_str(&fan_out_line(col, new_lanes, trailing));
+        }
+    }
+
+    if print {
+        print!("{output}");
+    }
+
+    Ok(output)
+}
+
+fn collect_commits(start_oid: &str) -> std::io::Result<HashMap<String, Commit>> {
+    let mut commits = HashMap::new();
+    let mut queue = vec![start_oid.to_string()];
+
+    while let Some(oid) = queue.pop() {
+        if commits.contains_key(&oid) {
+            continue;
+        }
+        let commit = read_commit(&oid)?;
+        queue.extend(commit.parents.iter().cloned());
+        commits.insert(oid, commit);
+    }
+
+    Ok(commits)
+}
+
+// Newest-first ordering in which no commit is listed before any of its children
+fn topo_order(start_oid: &str, commits: &HashMap<String, Commit>) -> Vec<String> {
+    let mut child_counts: HashMap<&str, usize> = HashMap::new();
+    for commit in commits.values() {
+        for parent in &commit.parents {
+            *child_counts.entry(parent.as_str()).or_default() += 1;
+        }
+    }
+
+    let mut ordered = Vec::new();
+    let mut ready: Vec<(String, usize)> = vec![(start_oid.to_string(), 0)];
+    let mut discovered = 1;
+
+    while !ready.is_empty() {
+        let (best, _) = ready.iter().enumerate()
+            .max_by(|(_, a), (_, b)| {
+                commits[&a.0].time().cmp(&commits[&b.0].time()).then(b.1.cmp(&a.1))
+            })
+            .unwrap();
+        let (oid, _) = ready.remove(best);
+
+        for parent in &commits[&oid].parents {
+            let remaining = child_counts.get_mut(parent.as_str()).unwrap();
+            *remaining -= 1;
+            if *remaining == 0 {
+                ready.push((parent.clone(), discovered));
+                discovered += 1;
+            }
+        }
+        ordered.push(oid);
+    }
+
+    ordered
+}
+
+// Maps every commit to its nearest ancestors that are actually displayed
+fn rewrite_parents(ordered: &[String], commits: &HashMap<String, Commit>, shown: &HashSet<String>) -> HashMap<String, Vec<String>> {
+    let mut rewritten: HashMap<String, Vec<String>> = HashMap::new();
+
+    for oid in ordered.iter().rev() {
+        let mut new_parents: Vec<String> = Vec::new();
+        for parent in &commits[oid].parents {
+            let candidates = if shown.contains(parent) {
+                vec![parent.clone()]
+            } else {
+                rewritten[parent].clone()
+            };
+            for candidate in candidates {
+                if !new_parents.contains(&candidate) {
+                    new_parents.push(candidate);
+                }
+            }
+        }
+        rewritten.insert(oid.clone(), new_parents);
+    }
+
+    rewritten
+}
+
+fn normalize_path(path: &str) -> String {
+    let trimmed = path.strip_prefix("./").unwrap_or(path);
+    trimmed.trim_end_matches('/').replace('\\', "/")
+}
+
+fn touches_paths(
+    commit: &Commit,
+    commits: &HashMap<String, Commit>,
+    paths: &[String],
+    tree_cache: &mut HashMap<String, HashMap<String, String>>,
+) -> std::io::Result<bool> {
+    let own = filtered_tree(&commit.tree, paths, tree_cache)?;
+
+    if commit.parents.is_empty() {
+        return Ok(!own.is_empty());
+    }
+
+    // A commit identical to any parent for these paths did not change them
+    for parent in &commit.parents {
+        let parent_tree = commits[parent].tree.clone();
+        if filtered_tree(&parent_tree, paths, tree_cache)? == own {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn filtered_tree(
+    tree_oid: &str,
+    paths: &[String],
+    tree_cache: &mut HashMap<String, HashMap<String, String>>,
+) -> std::io::Result<HashMap<String, String>> {
+    if let Some(cached) = tree_cache.get(tree_oid) {
+        return Ok(cached.clone());
+    }
+
+    let filtered: HashMap<String, String> = read_tree_to_index(tree_oid)?
+        .into_iter()
+        .filter(|entry| paths.iter().any(|p| p.is_empty() || entry.path == *p || entry.path.starts_with(&format!("{}/", p))))
+        .map(|entry| (entry.path, entry.oids[0].clone()))
+        .collect();
+
+    tree_cache.insert(tree_oid.to_string(), filtered.clone());
+    Ok(filtered)
+}
+
+fn format_entry(commit: &Commit, oneline: bool) -> String {
+    if oneline {
+        let short = &commit.oid[..commit.oid.len().min(7)];
+        return format!("\x1b[33m{}\x1b[0m {}\n", short, commit.summary());
+    }
+
+    let mut buf = String::new();
+    buf.push_str(&format!("\x1b[33mcommit {}\x1b[0m\n", commit.oid));
+    if commit.parents.len() > 1 {
+        let shorts: Vec<&str> = commit.parents.iter().map(|p| &p[..p.len().min(7)]).collect();
+        buf.push_str(&format!("Merge: {}\n", shorts.join(" ")));
+    }
+    if let Some(author) = &commit.author {
+        buf.push_str(&format!("Author: {} <{}>\n", author.name, author.email));
+        buf.push_str(&format!("Date:   {}\n", author.date_string()));
+    }
+    buf.push('\n');
+    for line in commit.message.lines() {
+        buf.push_str(&format!("    {}\n", line));
+    }
+    buf
+}
+
+fn collapse_line(lane: usize, lane_count: usize) -> String {
+    let mut line = vec!["|"; lane].join(" ");
+    line.push('/');
+    for _ in lane + 1..lane_count {
+        line.push_str(" /");
+    }
+    line.push('\n');
+    line
+}
+
+fn fan_out_line(col: usize, new_lanes: usize, trailing: usize) -> String {
+    let mut line = vec!["|"; col + 1].join(" ");
+    line.push('\\');
+    for _ in 1..new_lanes + trailing {
+        line.push_str(" \\");
+    }
+    line.push('\n');
+    line
+}
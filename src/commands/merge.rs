@@ -1,19 +1,30 @@
 use crate::core::refs::{
     resolve_head,
-    read_ref,
+    resolve_revision,
     update_ref,
-    set_head_ref,
+    read_merge_head,
+    write_merge_head,
 };
-use crate::core::io::{ read_file, write_file };
+use crate::core::io::{ read_object, write_file, write_object, remove_empty_parents };
+use crate::core::object::Object;
+use crate::core::diff::{ merge3, is_binary, MergeLabels };
 use crate::core::repo::find_repo_root;
-use crate::core::tree::read_tree_to_index;
-use crate::core::index::{ write_index, IndexEntry, EntryType };
+use crate::core::tree::{ read_tree_to_index, write_tree_from_index };
+use crate::core::ancestry::CommitGraph;
+use crate::core::index::{ read_index, write_index, IndexEntry, EntryType };
 use crate::commands::status::status;
+use crate::commands::commit::commit;
 
 use std::path::Path;
 use std::collections::{ HashMap, HashSet };
 
 pub fn merge(target_branch: String) -> std::io::Result<()> {
+    if read_merge_head()?.is_some() {
+        return Err(std::io::Error::other(
+            "Cannot merge: a merge is already in progress. Resolve the conflicts and commit first",
+        ));
+    }
+
     if status(false)?.len() > 0 {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
@@ -30,28 +41,44 @@ pub fn merge(target_branch: String) -> std::io::Result<()> {
         ));
     };
 
-    let target_commit_oid = read_ref(&target_branch)?;
+    let target_commit_oid = resolve_revision(&target_branch)?;
 
     if oid == target_commit_oid {
         println!("Already up-to-date");
         return Ok(());
     }
 
-    let nag_dir = find_repo_root()?.join(".nag");
-    let object_dir = nag_dir.join("objects");
-
-    let ancestor = find_ancestor_type(&object_dir, &oid, &target_commit_oid)?;
+    let mut graph = CommitGraph::new();
+    let ancestor = if oid.is_empty() {
+        // Nothing committed on this branch yet, so anything is a fast-forward
+        Ancestor::Direct
+    } else {
+        find_ancestor_type(&mut graph, &oid, &target_commit_oid)?
+    };
 
     match ancestor {
         Ancestor::Direct => {
-            fast_forward(&branch, &target_commit_oid)?;
+            fast_forward(&branch, &target_commit_oid, &target_branch)?;
             println!("Fast-forwarded '{}' to '{}' (new commit: {})", branch, target_branch, target_commit_oid);
         },
         Ancestor::DirectReverse => {
             println!("Already up-to-date");
         },
-        Ancestor::Shared(ancestor_oid) => {
-            let output = three_way_merge(&oid, &target_commit_oid, &ancestor_oid)?;
+        Ancestor::Shared(ancestor_oids) => {
+            let ancestor_tree = virtual_ancestor_tree(&mut graph, &ancestor_oids)?;
+            let output = match three_way_merge(&oid, &target_commit_oid, &ancestor_tree) {
+                Ok(output) => output,
+                Err(e) => {
+                    // Recorded only for conflicts, so they can be finished with a later commit;
+                    // any other failure leaves no merge in progress
+                    if read_index()?.iter().any(|entry| entry.entry_type == EntryType::X) {
+                        write_merge_head(&target_commit_oid)?;
+                    }
+                    return Err(e);
+                },
+            };
+            write_merge_head(&target_commit_oid)?;
+            commit(format!("Merge '{}' into '{}'", target_branch, branch))?;
             println!("{}", output);
         },
         Ancestor::NotFound => {
@@ -65,38 +92,110 @@ pub fn merge(target_branch: String) -> std::io::Result<()> {
     Ok(())
 }
 
-fn fast_forward(branch: &String, target_commit_oid: &String) -> std::io::Result<()> {
-    update_ref(&branch, &target_commit_oid)?;
-    set_head_ref(&branch)?;
+fn fast_forward(branch: &str, target_commit_oid: &str, target_branch: &str) -> std::io::Result<()> {
+    let repo_root = find_repo_root()?;
+    let target_tree = extract_tree_oid(target_commit_oid)?;
+    let target_index = read_tree_to_index(&target_tree)?;
+    let target_paths: HashSet<&String> = target_index.iter().map(|entry| &entry.path).collect();
+
+    // The working directory is clean here, so tracked files can be swapped out safely
+    for entry in read_index()? {
+        let full_path = repo_root.join(&entry.path);
+        if !target_paths.contains(&entry.path) && full_path.is_file() {
+            std::fs::remove_file(full_path)?;
+        }
+    }
+    for entry in &target_index {
+        let contents = read_object(&entry.oids[0])?.into_payload("blob")?;
+        write_file(&contents, &repo_root.join(&entry.path))?;
+    }
+    write_index(&target_index)?;
+
+    update_ref(branch, target_commit_oid, &format!("merge {}: Fast-forward", target_branch))?;
 
     Ok(())
 }
 
 fn extract_tree_oid(commit_str: &str) -> std::io::Result<String> {
-    let nag_dir = find_repo_root()?.join(".nag");
-    let tree_oid = commit_str.lines().next().unwrap();
-    let tree_path = nag_dir.join("objects").join(tree_oid.trim());
-    if !tree_path.exists() {
+    let commit_oid = commit_str.lines().next().unwrap().trim();
+    let Ok(commit) = read_object(commit_oid) else {
         return Err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
-            format!("Commit\'s tree '{}' not found", tree_oid),
+            format!("Commit\'s tree '{}' not found", commit_oid),
         ));
-    }
-    let tree_contents = read_file(&tree_path.to_string_lossy())?;
+    };
+    let tree_contents = commit.into_payload("commit")?;
     let tree_str = String::from_utf8_lossy(&tree_contents);
     let first_line = tree_str.lines().next().unwrap();
     let tree_oid = first_line.strip_prefix("tree ").unwrap().trim();
     Ok(tree_oid.trim().to_string())
 }
 
-fn three_way_merge(base_oid: &str, target_oid: &str, ancestor_oid: &str) -> std::io::Result<String> {
+/// Merges the changes between `ancestor_tree` and commit `target_oid` into commit `base_oid`,
+/// writing the result to the index and working directory. Conflicts are left marked in both
+/// and reported as an error.
+pub fn three_way_merge(base_oid: &str, target_oid: &str, ancestor_tree: &str) -> std::io::Result<String> {
     let base_tree = extract_tree_oid(base_oid)?;
     let target_tree = extract_tree_oid(target_oid)?;
-    let ancestor_tree = extract_tree_oid(ancestor_oid)?;
 
-    let base_index = read_tree_to_index(&base_tree)?.into_iter().map(|entry| (entry.path.clone(), entry)).collect::<HashMap<String, IndexEntry>>();
-    let target_index = read_tree_to_index(&target_tree)?.into_iter().map(|entry| (entry.path.clone(), entry)).collect::<HashMap<String, IndexEntry>>();
-    let ancestor_index = read_tree_to_index(&ancestor_tree)?.into_iter().map(|entry| (entry.path.clone(), entry)).collect::<HashMap<String, IndexEntry>>();
+    let (final_index, conflict_files) = merge_trees(&base_tree, &target_tree, ancestor_tree, target_oid)?;
+
+    let repo_root = find_repo_root()?;
+    // Files the merge deleted go from the working directory as well
+    let kept: HashSet<&String> = final_index.iter().map(|entry| &entry.path).collect();
+    for entry in read_tree_to_index(&base_tree)? {
+        if !kept.contains(&entry.path) {
+            let full_path = repo_root.join(&entry.path);
+            if full_path.is_file() {
+                std::fs::remove_file(&full_path)?;
+            }
+            remove_empty_parents(&repo_root, &full_path);
+        }
+    }
+    for entry in &final_index {
+        if let Some(contents) = conflict_files.get(&entry.path) {
+            write_file(contents, &repo_root.join(&entry.path))?;
+        } else if entry.oids.len() > 1 {
+            build_conflict_file(&entry.oids[0], &entry.oids[1], &entry.path)?;
+        } else {
+            let full_path = repo_root.join(&entry.path);
+            let contents = read_object(&entry.oids[0])?.into_payload("blob")?;
+
+            if let Some(parent) = full_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            write_file(&contents, &full_path)?;
+        }
+    }
+
+    write_index(&final_index)?;
+
+    let mut summary_buf = String::new();
+    summary_buf.push_str("Merge results:\n");
+    for entry in &final_index {
+        if entry.entry_type == EntryType::C {
+            summary_buf.push_str(&format!("\tclean: {}\n", entry.path));
+        } else {
+            summary_buf.push_str(&format!("\tconflict: {}\n", entry.path));
+        }
+    }
+    let has_conflicts = final_index.iter().any(|e| e.entry_type == EntryType::X);
+
+    if has_conflicts {
+        Err(std::io::Error::new(std::io::ErrorKind::Other, "Merge conflicts"))
+    } else {
+        Ok(summary_buf)
+    }
+}
+
+type MergedTree = (Vec<IndexEntry>, HashMap<String, Vec<u8>>);
+
+// Merges three trees path by path without touching the index or working tree.
+// Conflicted entries keep both oids, and text conflicts come back with their marked-up contents.
+fn merge_trees(base_tree: &str, target_tree: &str, ancestor_tree: &str, target_label: &str) -> std::io::Result<MergedTree> {
+    let base_index = read_tree_to_index(base_tree)?.into_iter().map(|entry| (entry.path.clone(), entry)).collect::<HashMap<String, IndexEntry>>();
+    let target_index = read_tree_to_index(target_tree)?.into_iter().map(|entry| (entry.path.clone(), entry)).collect::<HashMap<String, IndexEntry>>();
+    let ancestor_index = read_tree_to_index(ancestor_tree)?.into_iter().map(|entry| (entry.path.clone(), entry)).collect::<HashMap<String, IndexEntry>>();
 
     let mut map: HashMap<String, (Option<IndexEntry>, Option<IndexEntry>, Option<IndexEntry>)> = HashMap::new();
     for (path, entry) in ancestor_index {
@@ -112,8 +211,9 @@ fn three_way_merge(base_oid: &str, target_oid: &str, ancestor_oid: &str) -> std:
     }
 
     let mut final_index: Vec<IndexEntry> = Vec::new();
+    let mut conflict_files: HashMap<String, Vec<u8>> = HashMap::new();
 
-    for (_path, (a_entry, b_entry, t_entry)) in map {
+    for (path, (a_entry, b_entry, t_entry)) in map {
         let is_dir =
             a_entry.clone().map(|e| e.mode == "040000").unwrap_or(false) ||
             b_entry.clone().map(|e| e.mode == "040000").unwrap_or(false) ||
@@ -136,8 +236,19 @@ fn three_way_merge(base_oid: &str, target_oid: &str, ancestor_oid: &str) -> std:
                 } else if aoid != boid && boid == toid {
                     final_index.push(quick_entry(&b, &vec![boid]));
                 } else if aoid != boid && aoid != toid && boid != toid {
-                    // Conflict, so keep both
-                    final_index.push(quick_entry(&b, &vec![boid, toid]));
+                    match merge_contents(Some(&aoid), &boid, &toid, target_label)? {
+                        ContentMerge::Clean(merged_oid) => {
+                            final_index.push(quick_entry(&b, &vec![merged_oid]));
+                        },
+                        ContentMerge::Conflicted(contents) => {
+                            conflict_files.insert(path, contents);
+                            final_index.push(quick_entry(&b, &vec![boid, toid]));
+                        },
+                        ContentMerge::Unmergeable => {
+                            // Conflict, so keep both
+                            final_index.push(quick_entry(&b, &vec![boid, toid]));
+                        },
+                    }
                 }
             },
             (Some(a), Some(b), None) => {
@@ -173,8 +284,19 @@ fn three_way_merge(base_oid: &str, target_oid: &str, ancestor_oid: &str) -> std:
                     // Somehow, both branches independently made the same change
                     final_index.push(quick_entry(&b, &vec![boid]));
                 } else {
-                    // Conflict, so keep both
-                    final_index.push(quick_entry(&b, &vec![boid, toid]));
+                    // Both sides added the file, so merge against an empty ancestor
+                    match merge_contents(None, &boid, &toid, target_label)? {
+                        ContentMerge::Clean(merged_oid) => {
+                            final_index.push(quick_entry(&b, &vec![merged_oid]));
+                        },
+                        ContentMerge::Conflicted(contents) => {
+                            conflict_files.insert(path, contents);
+                            final_index.push(quick_entry(&b, &vec![boid, toid]));
+                        },
+                        ContentMerge::Unmergeable => {
+                            final_index.push(quick_entry(&b, &vec![boid, toid]));
+                        },
+                    }
                 }
             },
             _ => {
@@ -183,40 +305,51 @@ fn three_way_merge(base_oid: &str, target_oid: &str, ancestor_oid: &str) -> std:
         }
     }
 
-    let repo_root = find_repo_root()?;
-    for entry in &final_index {
-        if entry.oids.len() > 1 {
-            build_conflict_file(&entry.oids[0], &entry.oids[1], &entry.path)?;
-        } else {
-            let full_path = repo_root.join(&entry.path);
-            let obj_path = repo_root.join(".nag/objects").join(&entry.oids[0]);
-            let contents = read_file(obj_path.to_str().unwrap())?;
 
-            if let Some(parent) = full_path.parent() {
-                std::fs::create_dir_all(parent)?;
-            }
-            write_file(&contents, &full_path)?;
-        }
-    }
+    Ok((final_index, conflict_files))
+}
 
-    write_index(&final_index)?;
+// Collapses several merge bases into one tree by merging them pairwise, the way
+// a recursive merge builds a virtual common ancestor for criss-cross histories.
+fn virtual_ancestor_tree(graph: &mut CommitGraph, ancestor_oids: &[String]) -> std::io::Result<String> {
+    let mut folded = vec![ancestor_oids[0].clone()];
+    let mut tree = extract_tree_oid(&ancestor_oids[0])?;
 
-    let mut summary_buf = String::new();
-    summary_buf.push_str("Merge results:\n");
-    for entry in &final_index {
-        if entry.entry_type == EntryType::C {
-            summary_buf.push_str(&format!("\tclean: {}\n", entry.path));
+    for next in &ancestor_oids[1..] {
+        let mut inner_bases = Vec::new();
+        for oid in &folded {
+            inner_bases.extend(graph.merge_bases(oid, next)?);
+        }
+        let inner_bases = graph.independent(inner_bases)?;
+        let inner_tree = if inner_bases.is_empty() {
+            write_tree_from_index(&Vec::new())?
         } else {
-            summary_buf.push_str(&format!("\tconflict: {}\n", entry.path));
+            virtual_ancestor_tree(graph, &inner_bases)?
+        };
+
+        let next_tree = extract_tree_oid(next)?;
+        let (entries, conflict_files) = merge_trees(&tree, &next_tree, &inner_tree, next)?;
+
+        // Conflicts are baked into the virtual ancestor as-is, markers and all
+        let mut resolved = Vec::new();
+        for entry in entries {
+            let oid = if let Some(contents) = conflict_files.get(&entry.path) {
+                write_object(&Object::Blob(contents.clone()))?
+            } else {
+                entry.oids.iter().find(|oid| *oid != "empty").cloned().unwrap_or_default()
+            };
+            resolved.push(IndexEntry {
+                entry_type: EntryType::C,
+                path: entry.path,
+                mode: entry.mode,
+                oids: vec![oid],
+            });
         }
+        tree = write_tree_from_index(&resolved)?;
+        folded.push(next.clone());
     }
-    let has_conflicts = final_index.iter().any(|e| e.entry_type == EntryType::X);
 
-    if has_conflicts {
-        Err(std::io::Error::new(std::io::ErrorKind::Other, "Merge conflicts"))
-    } else {
-        Ok(summary_buf)
-    }
+    Ok(tree)
 }
 
 fn quick_entry(existing_entry: &IndexEntry, oids: &Vec<String>) -> IndexEntry {
@@ -228,13 +361,48 @@ fn quick_entry(existing_entry: &IndexEntry, oids: &Vec<String>) -> IndexEntry {
     }
 }
 
-fn build_conflict_file(base_oid: &str, target_oid: &str, conflict_file: &str) -> std::io::Result<()> {
-    let object_dir = find_repo_root()?.join(".nag").join("objects");
-    let base_object_path = object_dir.join(base_oid);
-    let target_object_path = object_dir.join(target_oid);
+enum ContentMerge {
+    Clean(String),
+    Conflicted(Vec<u8>),
+    Unmergeable,
+}
 
-    let base_object = read_file(&base_object_path.to_string_lossy())?;
-    let target_object = read_file(&target_object_path.to_string_lossy())?;
+// Line-merges both sides of a file against their common ancestor blob
+fn merge_contents(ancestor_oid: Option<&str>, base_oid: &str, target_oid: &str, target_commit: &str) -> std::io::Result<ContentMerge> {
+    let ancestor_object = match ancestor_oid {
+        Some(oid) => read_object(oid)?.into_payload("blob")?,
+        None => Vec::new(),
+    };
+    let base_object = read_object(base_oid)?.into_payload("blob")?;
+    let target_object = read_object(target_oid)?.into_payload("blob")?;
+
+    if is_binary(&ancestor_object) || is_binary(&base_object) || is_binary(&target_object) {
+        return Ok(ContentMerge::Unmergeable);
+    }
+
+    let labels = MergeLabels {
+        ours: "HEAD",
+        base: "ancestor",
+        theirs: &target_commit[..target_commit.len().min(7)],
+    };
+    let (merged, has_conflicts) = merge3(
+        &String::from_utf8_lossy(&ancestor_object),
+        &String::from_utf8_lossy(&base_object),
+        &String::from_utf8_lossy(&target_object),
+        &labels,
+    );
+
+    if has_conflicts {
+        return Ok(ContentMerge::Conflicted(merged.into_bytes()));
+    }
+
+    let merged_oid = write_object(&Object::Blob(merged.into_bytes()))?;
+    Ok(ContentMerge::Clean(merged_oid))
+}
+
+fn build_conflict_file(base_oid: &str, target_oid: &str, conflict_file: &str) -> std::io::Result<()> {
+    let base_object = read_object(base_oid)?.into_payload("blob")?;
+    let target_object = read_object(target_oid)?.into_payload("blob")?;
     let base_object_str = String::from_utf8_lossy(&base_object);
     let target_object_str = String::from_utf8_lossy(&target_object);
 
@@ -260,69 +428,27 @@ fn build_conflict_file(base_oid: &str, target_oid: &str, conflict_file: &str) ->
 enum Ancestor {
     Direct,
     DirectReverse,
-    Shared(String),
+    Shared(Vec<String>),
     NotFound,
 }
 
-fn find_ancestor_type(object_dir: &Path, base_oid: &str, target_oid: &str) -> std::io::Result<Ancestor> {
-    let mut target_parent_oids = HashSet::new();
-    collect_all_ancestors(object_dir, target_oid, &mut target_parent_oids)?;
+fn find_ancestor_type(graph: &mut CommitGraph, base_oid: &str, target_oid: &str) -> std::io::Result<Ancestor> {
+    let bases = graph.merge_bases(base_oid, target_oid)?;
 
-    let mut base_parent_oids = HashSet::new();
-    collect_all_ancestors(object_dir, base_oid, &mut base_parent_oids)?;
-
-    // Shared
-    if let Some(shared) = base_parent_oids.intersection(&target_parent_oids).next() {
-        return Ok(Ancestor::Shared(shared.clone()));
+    if bases.is_empty() {
+        return Ok(Ancestor::NotFound);
     }
 
     // Direct
-    if target_parent_oids.contains(base_oid) {
+    if bases.iter().any(|oid| oid == base_oid) {
         return Ok(Ancestor::Direct);
     }
 
     // DirectReverse
-    if base_parent_oids.contains(target_oid) {
+    if bases.iter().any(|oid| oid == target_oid) {
         return Ok(Ancestor::DirectReverse);
     }
 
-    Ok(Ancestor::NotFound)
-}
-
-fn collect_all_ancestors(object_dir: &Path, target_oid: &str, parent_oids: &mut HashSet<String>) -> std::io::Result<()> {
-    let new_parent_oids = collect_oids(object_dir, target_oid)?;
-
-    if new_parent_oids.is_empty() {
-        return Ok(())
-    }
-
-    for parent_oid in new_parent_oids.clone() {
-        collect_all_ancestors(object_dir, &parent_oid, parent_oids)?;
-    }
-
-    parent_oids.extend(new_parent_oids);
-
-    Ok(())
-}
-
-fn collect_oids(object_dir: &Path, oid: &str) -> std::io::Result<HashSet<String>> {
-    let target_object_path = object_dir.join(oid);
-    if !target_object_path.exists() {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            format!("Commit object {} not found", oid),
-        ));
-    }
-    let commit_bytes = read_file(&target_object_path.to_string_lossy())?;
-    let commit_str = String::from_utf8_lossy(&commit_bytes);
-    let trimmed = commit_str.trim();
-
-    let line_prefix = "parent ";
-    let new_parent_oids: HashSet<String> = trimmed
-        .lines()
-        .filter(|line| line.starts_with(line_prefix))
-        .map(|line| line[line_prefix.len()..].to_string())
-        .collect::<HashSet<String>>();
-
-    Ok(new_parent_oids)
+    // Shared
+    Ok(Ancestor::Shared(bases))
 }
@@ -0,0 +1,24 @@
+use crate::core::ancestry::merge_bases;
+use crate::core::refs::resolve_revision;
+
+pub fn merge_base(one: &str, two: &str, all: bool, print: bool) -> std::io::Result<String> {
+    let one_oid = resolve_revision(one)?;
+    let two_oid = resolve_revision(two)?;
+
+    let bases = merge_bases(&one_oid, &two_oid)?;
+    if bases.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No common ancestor between '{}' and '{}'", one, two),
+        ));
+    }
+
+    let shown = if all { &bases[..] } else { &bases[..1] };
+    let output = shown.iter().map(|oid| format!("{}\n", oid)).collect::<String>();
+
+    if print {
+        print!("{}", output);
+    }
+
+    Ok(output)
+}
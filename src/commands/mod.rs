@@ -9,3 +9,17 @@ pub mod merge;
 pub mod tag;
 pub mod resolve;
 pub mod remote;
+pub mod pull;
+pub mod clone;
+pub mod config;
+pub mod log;
+pub mod diff;
+pub mod merge_base;
+pub mod gc;
+pub mod fsck;
+pub mod stash;
+pub mod reflog;
+pub mod reset;
+pub mod rm;
+pub mod mv;
+pub mod cherry_pick;
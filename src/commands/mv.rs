@@ -0,0 +1,70 @@
+use crate::core::repo::{ find_repo_root, repo_relative_path };
+use crate::core::index::{ read_index, write_index, EntryType };
+use crate::core::io::remove_empty_parents;
+
+/// Renames a tracked file or directory on disk and in the index together. A destination
+/// that is an existing directory receives the source inside it; any other existing
+/// destination is only replaced with `force`.
+pub fn mv(source: String, destination: String, force: bool) -> std::io::Result<()> {
+    let root = find_repo_root()?;
+    let mut index = read_index()?;
+
+    let source = repo_relative_path(&source)?;
+    let mut destination = repo_relative_path(&destination)?;
+    let under = |path: &str, dir: &str| path == dir || path.starts_with(&format!("{}/", dir));
+
+    if source.is_empty() || !index.iter().any(|entry| under(&entry.path, &source)) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("'{}' is not tracked", source),
+        ));
+    }
+    if !root.join(&source).exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("'{}' does not exist in the working directory", source),
+        ));
+    }
+    if root.join(&destination).is_dir() {
+        let name = source.rsplit('/').next().unwrap_or(&source);
+        destination = if destination.is_empty() { name.to_string() } else { format!("{}/{}", destination, name) };
+    }
+    if under(&destination, &source) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Cannot move '{}' into itself", source),
+        ));
+    }
+    if index.iter().any(|entry| under(&entry.path, &source) && entry.entry_type == EntryType::X) {
+        return Err(std::io::Error::other(
+            format!("Cannot move '{}': it has unresolved conflicts. Resolve them first", source),
+        ));
+    }
+
+    let destination_path = root.join(&destination);
+    if destination_path.exists() {
+        if !force || destination_path.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("Destination '{}' already exists", destination),
+            ));
+        }
+        std::fs::remove_file(&destination_path)?;
+    }
+
+    if let Some(parent) = destination_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::rename(root.join(&source), &destination_path)?;
+    remove_empty_parents(&root, &root.join(&source));
+
+    index.retain(|entry| !under(&entry.path, &destination));
+    for entry in index.iter_mut().filter(|entry| under(&entry.path, &source)) {
+        entry.path = format!("{}{}", destination, &entry.path[source.len()..]);
+    }
+    index.sort_by(|a, b| a.path.cmp(&b.path));
+    write_index(&index)?;
+
+    println!("Renamed '{}' to '{}'", source, destination);
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::commands::remote::fetch_remote;
+use crate::commands::merge::merge;
+use crate::core::refs::{ resolve_head, read_ref };
+
+pub fn pull(remote: Option<String>, branch: Option<String>) -> std::io::Result<()> {
+    let remote_name = remote.unwrap_or("origin".to_string());
+
+    let branch_name = match branch {
+        Some(b) => b,
+        None => {
+            let (Some(current), _) = resolve_head()? else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Cannot pull into detached HEAD without a branch name",
+                ));
+            };
+            current
+        }
+    };
+
+    fetch_remote(remote_name.clone())?;
+
+    let tracking_ref = format!("refs/remotes/{}/{}", remote_name, branch_name);
+    if read_ref(&tracking_ref).is_err() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Remote '{}' has no branch '{}'", remote_name, branch_name),
+        ));
+    }
+
+    merge(tracking_ref)?;
+
+    Ok(())
+}
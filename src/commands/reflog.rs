@@ -0,0 +1,18 @@
+use crate::core::refs::log_ref_name;
+use crate::core::reflog::read_reflog;
+
+/// Lists a ref's log (HEAD's by default) newest first, one `<oid> <ref>@{n}: <message>` line per move.
+pub fn reflog(ref_name: Option<String>, print: bool) -> std::io::Result<String> {
+    let name = ref_name.unwrap_or("HEAD".to_string());
+    let entries = read_reflog(&log_ref_name(&name)?)?;
+
+    let output = entries.iter().rev().enumerate()
+        .map(|(n, entry)| format!("{} {}@{{{}}}: {}\n", &entry.new[..7.min(entry.new.len())], name, n, entry.message))
+        .collect::<String>();
+
+    if print {
+        print!("{}", output);
+    }
+
+    Ok(output)
+}
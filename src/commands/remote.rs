@@ -1,9 +1,14 @@
-use crate::core::io::{ read_file, write_object, write_file };
+use crate::core::io::{ read_file, read_object_from, write_file, object_path, has_object };
 use crate::core::repo::{ find_repo_root };
-use crate::core::tree::{ read_tree_to_index };
+use crate::core::refs::{ read_ref, update_ref, resolve_head };
+use crate::core::ancestry::is_ancestor;
+use crate::core::migrate::check_format;
+use crate::core::config::read_config_file;
+use crate::core::reflog::append_reflog_in;
 
 use std::path::{ Path, PathBuf };
 use std::fs::read_dir;
+use std::collections::HashSet;
 
 pub fn add_remote(name: String, path: String) -> std::io::Result<()> {
     let nag_path = get_remote_nag_dir(&path)?;
@@ -40,84 +45,255 @@ pub fn fetch_remote(remote_name: String) -> std::io::Result<()> {
     let remote = rem_str.trim().to_string();
     let remote_nag_dir = get_remote_nag_dir(&remote)?;
 
+    let local_root = find_repo_root()?;
+    let local_objects_dir = local_root.join(".nag").join("objects");
+    let remote_objects_dir = remote_nag_dir.join("objects");
+
+    let mut fetched: Vec<(String, String)> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
     let remote_heads_dir = remote_nag_dir.join("refs/heads");
     for entry in read_dir(remote_heads_dir)? {
         let entry = entry?;
         let branch_name = entry.file_name().to_string_lossy().to_string();
         let commit_oid_bytes = read_file(&entry.path().to_string_lossy().to_string())?;
         let commit_oid = String::from_utf8_lossy(&commit_oid_bytes).trim().to_string();
+        if commit_oid.is_empty() {
+            // Branch exists on the remote but has no commits yet
+            continue;
+        }
 
-        let remote_objects_dir = remote_nag_dir.join("objects");
-        let root_commit_obj_path = remote_objects_dir.join(&commit_oid);
-        let root_commit_obj_bytes = read_file(&root_commit_obj_path.to_string_lossy().to_string())?;
-        let root_commit_obj = String::from_utf8_lossy(&root_commit_obj_bytes).to_string();
-        let root_tree_oid_line = root_commit_obj.lines().next().unwrap();
-        let root_tree_oid = root_tree_oid_line.strip_prefix("tree ").unwrap_or(root_tree_oid_line);
+        copy_commit_objects(&remote_objects_dir, &local_objects_dir, &commit_oid, &mut seen)?;
+        fetched.push((branch_name, commit_oid));
+    }
 
-        let local_root = find_repo_root()?;
-        let local_objects_dir = local_root.join(".nag").join("objects");
+    // Nothing is published until every fetched tip is fully connected locally
+    let tips: Vec<String> = fetched.iter().map(|(_, commit_oid)| commit_oid.clone()).collect();
+    check_connectivity(&local_objects_dir, &tips)?;
 
-        let mut queue: Vec<String> = vec![root_tree_oid.to_string()];
-        while !queue.is_empty() {
-            let oid = queue.pop().unwrap();
-            if local_objects_dir.join(&oid).exists() {
-                continue;
-            }
-            let remote_obj_path = remote_objects_dir.join(&oid);
-            let remote_obj = read_file(&remote_obj_path.to_string_lossy().to_string())?;
-            write_object(&remote_obj, &oid)?;
-
-            let possible_index = read_tree_to_index(&oid);
-            if let Ok(index) = possible_index {
-                for entry in index.iter() {
-                    queue.push(entry.oids[0].clone());
-                }
-            }
+    for (branch_name, commit_oid) in fetched {
+        let tracking_ref = format!("refs/remotes/{}/{}", remote_name, branch_name);
+
+        // Only write if the ref doesn't exist or has a different value
+        if read_ref(&tracking_ref).ok().as_ref() != Some(&commit_oid) {
+            update_ref(&tracking_ref, &commit_oid, &format!("fetch: from {}", remote))?;
         }
+    }
 
-        // Only write the head commit if it doesn't already exist
-        if !local_objects_dir.join(&commit_oid).exists() {
-            write_object(&root_commit_obj_bytes, &commit_oid)?;
-        }
-
-        let root_commit_parent = root_commit_obj
-            .lines()
-            .find(|l| l.starts_with("parent "))
-            .and_then(|line| line.strip_prefix("parent "))
-            .map(|s| s.trim().to_string());
-
-        // Then walk the parents if they exist
-        if let Some(parent) = root_commit_parent {
-            let mut commit_cursor = parent;
-            loop {
-                let commit_path = remote_objects_dir.join(&commit_cursor);
-                let commit_bytes = read_file(&commit_path.to_string_lossy())?;
-                write_object(&commit_bytes, &commit_cursor)?;
-                let commit_str = String::from_utf8_lossy(&commit_bytes);
-                if let Some(parent_line) = commit_str.lines().find(|l| l.starts_with("parent ")) {
-                    commit_cursor = parent_line.strip_prefix("parent ").unwrap().trim().to_string();
-                } else {
-                    break;
-                }
-                if local_objects_dir.join(&commit_cursor).exists() {
-                    break;
-                }
-            }
+    Ok(())
+}
+
+pub fn push_remote(remote_name: String, branch: Option<String>, force: bool) -> std::io::Result<()> {
+    let local_nag_dir = find_repo_root()?.join(".nag");
+    let rem_path = local_nag_dir.join("remotes").join(&remote_name);
+    if !rem_path.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Remote '{}' not found", remote_name),
+        ));
+    }
+    let rem_contents = read_file(&rem_path.to_string_lossy())?;
+    let rem_str = String::from_utf8_lossy(&rem_contents);
+    let remote = rem_str.trim().to_string();
+    let remote_nag_dir = get_remote_nag_dir(&remote)?;
+
+    let branch = match branch {
+        Some(b) => b,
+        None => {
+            let (Some(current), _) = resolve_head()? else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Cannot push from detached HEAD without a branch name",
+                ));
+            };
+            current
         }
+    };
+
+    let local_oid = read_ref(&branch)?;
+    if local_oid.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Branch '{}' has no commits to push", branch),
+        ));
+    }
 
-        let local_remote_ref_dir = local_root.join(".nag/refs/remotes").join(&remote_name);
-        let tracking_ref_path = local_remote_ref_dir.join(&branch_name);
+    let remote_ref_path = remote_nag_dir.join("refs/heads").join(&branch);
+    let remote_oid = if remote_ref_path.exists() {
+        let remote_oid_bytes = read_file(&remote_ref_path.to_string_lossy())?;
+        String::from_utf8_lossy(&remote_oid_bytes).trim().to_string()
+    } else {
+        String::new()
+    };
 
-        // Only write if the ref doesn't exist or has a different value
-        let should_write = if tracking_ref_path.exists() {
-            let existing = read_file(&tracking_ref_path.to_string_lossy()).ok();
-            existing.as_ref().map(|v| v.as_slice()) != Some(commit_oid.as_bytes())
+    let local_objects_dir = local_nag_dir.join("objects");
+    let remote_objects_dir = remote_nag_dir.join("objects");
+
+    if remote_oid == local_oid {
+        println!("Everything up-to-date");
+        return Ok(());
+    }
+
+    // The remote's index and files stay where they are, so its checked-out branch is left
+    // alone unless the remote opts in with receive.denyCurrentBranch = ignore
+    let remote_ref = format!("refs/heads/{}", branch);
+    let remote_head = read_file(&remote_nag_dir.join("HEAD").to_string_lossy()).unwrap_or_default();
+    let is_remote_head = String::from_utf8_lossy(&remote_head).trim() == format!("ref: {}", remote_ref);
+    if is_remote_head && !force {
+        let allowed = read_config_file(&remote_nag_dir.join("config"))?.iter()
+            .any(|(key, value)| key == "receive.denyCurrentBranch" && (value == "ignore" || value == "false"));
+        if !allowed {
+            return Err(std::io::Error::other(
+                format!("Refusing to update branch '{}', which is checked out in remote '{}'. Push another branch, set receive.denyCurrentBranch = ignore in the remote, or push with --force", branch, remote_name),
+            ));
+        }
+    }
+
+    if !remote_oid.is_empty() && !force {
+        let is_known = has_object(&local_objects_dir, &remote_oid);
+        if !is_known || !is_ancestor(&remote_oid, &local_oid)? {
+            return Err(std::io::Error::other(
+                format!("Rejected non-fast-forward push to '{}/{}'. Fetch and merge first, or push with --force", remote_name, branch),
+            ));
+        }
+    }
+
+    copy_commit_objects(&local_objects_dir, &remote_objects_dir, &local_oid, &mut HashSet::new())?;
+
+    write_file(&local_oid.as_bytes().to_vec(), &remote_ref_path)?;
+    // The remote logs the move like any other ref update, on its HEAD too when that is the branch
+    let message = format!("push: from {}", local_nag_dir.parent().unwrap_or(&local_nag_dir).display());
+    append_reflog_in(&remote_nag_dir, &remote_ref, &remote_oid, &local_oid, &message)?;
+    if is_remote_head {
+        append_reflog_in(&remote_nag_dir, "HEAD", &remote_oid, &local_oid, &message)?;
+    }
+    update_ref(&format!("refs/remotes/{}/{}", remote_name, branch), &local_oid, "update by push")?;
+
+    println!("Pushed '{}' to '{}' ({})", branch, remote_name, local_oid);
+
+    Ok(())
+}
+
+// Copies a commit and everything reachable from it (every parent, tree and blob)
+// into another objects directory. Objects the destination already holds are read from
+// there and walked too, so history an earlier fetch left incomplete is filled in.
+// `seen` is shared between tips so common history is only walked once.
+fn copy_commit_objects(src_objects_dir: &Path, dst_objects_dir: &Path, commit_oid: &str, seen: &mut HashSet<String>) -> std::io::Result<()> {
+    let mut commit_queue: Vec<String> = vec![commit_oid.to_string()];
+
+    while let Some(oid) = commit_queue.pop() {
+        if !seen.insert(oid.clone()) {
+            continue;
+        }
+        let commit_exists = has_object(dst_objects_dir, &oid);
+        let commit = if commit_exists {
+            read_object_from(dst_objects_dir, &oid)?
         } else {
-            true
+            read_object_from(src_objects_dir, &oid)?
         };
+        let commit_data = commit.clone().into_payload("commit")?;
+        let commit_str = String::from_utf8_lossy(&commit_data).to_string();
+
+        for line in commit_str.lines() {
+            if let Some(tree_oid) = line.strip_prefix("tree ") {
+                copy_tree_objects(src_objects_dir, dst_objects_dir, tree_oid.trim(), seen)?;
+            } else if let Some(parent_oid) = line.strip_prefix("parent ") {
+                commit_queue.push(parent_oid.trim().to_string());
+            } else if line.is_empty() {
+                break;
+            }
+        }
+
+        // The commit goes in last so an interrupted copy never leaves a commit without its tree
+        if !commit_exists {
+            write_file(&commit.compress()?, &object_path(dst_objects_dir, &oid))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn copy_tree_objects(src_objects_dir: &Path, dst_objects_dir: &Path, tree_oid: &str, seen: &mut HashSet<String>) -> std::io::Result<()> {
+    if !seen.insert(tree_oid.to_string()) {
+        return Ok(());
+    }
+    // Objects are read decoded since the source may hold them in a pack
+    let tree_exists = has_object(dst_objects_dir, tree_oid);
+    let tree = if tree_exists {
+        read_object_from(dst_objects_dir, tree_oid)?
+    } else {
+        read_object_from(src_objects_dir, tree_oid)?
+    };
+    let tree_data = tree.clone().into_payload("tree")?;
+    let tree_str = String::from_utf8_lossy(&tree_data).to_string();
+
+    for line in tree_str.lines() {
+        let parts: Vec<&str> = line.split('\t').collect();
+        if parts.len() < 4 {
+            continue;
+        }
+        if parts[1] == "040000" {
+            copy_tree_objects(src_objects_dir, dst_objects_dir, parts[3], seen)?;
+        } else if !has_object(dst_objects_dir, parts[3]) {
+            let blob = read_object_from(src_objects_dir, parts[3])?;
+            write_file(&blob.compress()?, &object_path(dst_objects_dir, parts[3]))?;
+        }
+    }
 
-        if should_write {
-            write_file(&commit_oid.as_bytes().to_vec(), &tracking_ref_path)?;
+    if !tree_exists {
+        write_file(&tree.compress()?, &object_path(dst_objects_dir, tree_oid))?;
+    }
+
+    Ok(())
+}
+
+// Walks everything reachable from the fetched tips and fails on the first missing object
+fn check_connectivity(objects_dir: &Path, tips: &[String]) -> std::io::Result<()> {
+    let mut commit_queue: Vec<String> = tips.to_vec();
+    let mut tree_queue: Vec<String> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+
+    let missing = |oid: &str| std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("Object {} is missing after fetch", oid),
+    );
+
+    while let Some(oid) = commit_queue.pop() {
+        if !seen.insert(oid.clone()) {
+            continue;
+        }
+        if !has_object(objects_dir, &oid) {
+            return Err(missing(&oid));
+        }
+        let commit_data = read_object_from(objects_dir, &oid)?.into_payload("commit")?;
+        for line in String::from_utf8_lossy(&commit_data).lines() {
+            if let Some(tree_oid) = line.strip_prefix("tree ") {
+                tree_queue.push(tree_oid.trim().to_string());
+            } else if let Some(parent_oid) = line.strip_prefix("parent ") {
+                commit_queue.push(parent_oid.trim().to_string());
+            } else if line.is_empty() {
+                break;
+            }
+        }
+    }
+
+    while let Some(oid) = tree_queue.pop() {
+        if !seen.insert(oid.clone()) {
+            continue;
+        }
+        if !has_object(objects_dir, &oid) {
+            return Err(missing(&oid));
+        }
+        let tree_data = read_object_from(objects_dir, &oid)?.into_payload("tree")?;
+        for line in String::from_utf8_lossy(&tree_data).lines() {
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() < 4 {
+                continue;
+            }
+            if parts[1] == "040000" {
+                tree_queue.push(parts[3].to_string());
+            } else if !has_object(objects_dir, parts[3]) {
+                return Err(missing(parts[3]));
+            }
         }
     }
 
@@ -127,6 +303,7 @@ pub fn fetch_remote(remote_name: String) -> std::io::Result<()> {
 fn get_remote_nag_dir(path: &String) -> std::io::Result<PathBuf> {
     let nag_path = Path::new(&path).join(".nag");
     if nag_path.is_dir() {
+        check_format(&nag_path)?;
         return Ok(nag_path)
     }
     Err(std::io::Error::new(
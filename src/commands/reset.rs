@@ -0,0 +1,102 @@
+use crate::commands::checkout::update_working_tree;
+use crate::core::repo::find_repo_root;
+use crate::core::io::read_file;
+use crate::core::object::Object;
+use crate::core::refs::{ resolve_revision, update_head, read_merge_head, clear_merge_head };
+use crate::core::commit::read_commit;
+use crate::core::tree::read_tree_to_index;
+use crate::core::index::{ read_index, write_index, IndexEntry, EntryType };
+
+use std::collections::BTreeSet;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum ResetMode { Soft, Mixed, Hard }
+
+/// Moves the current branch (or a detached HEAD) to `target`, HEAD by default.
+/// `Soft` leaves the index and working directory alone, `Mixed` also rebuilds the
+/// index from the target's tree, and `Hard` rewrites the tracked files to match it too.
+pub fn reset(target: Option<String>, mode: ResetMode) -> std::io::Result<()> {
+    let target = target.unwrap_or("HEAD".to_string());
+    let oid = resolve_revision(&target)?;
+    let commit = read_commit(&oid)?;
+
+    if mode == ResetMode::Soft {
+        if read_merge_head()?.is_some() {
+            return Err(std::io::Error::other("Cannot do a soft reset in the middle of a merge"));
+        }
+        if read_index()?.iter().any(|entry| entry.entry_type == EntryType::X) {
+            return Err(std::io::Error::other(
+                "Cannot do a soft reset with unresolved conflicts. Resolve them first",
+            ));
+        }
+    }
+
+    let target_index = read_tree_to_index(&commit.tree)?;
+    if mode == ResetMode::Hard {
+        let root = find_repo_root()?;
+        // What is on disk now, so that edited files are rewritten as well as changed ones
+        let paths: BTreeSet<String> = read_index()?.into_iter().map(|entry| entry.path)
+            .chain(target_index.iter().map(|entry| entry.path.clone()))
+            .collect();
+        let mut on_disk = Vec::new();
+        for path in paths {
+            let full_path = root.join(&path);
+            if full_path.is_file() {
+                on_disk.push(IndexEntry {
+                    entry_type: EntryType::C,
+                    path,
+                    mode: "100644".to_string(),
+                    oids: vec![Object::Blob(read_file(&full_path.to_string_lossy())?).oid()],
+                });
+            }
+        }
+        update_working_tree(&root, &on_disk, &target_index)?;
+    }
+    if mode != ResetMode::Soft {
+        write_index(&target_index)?;
+        clear_merge_head()?;
+    }
+
+    update_head(&oid, &format!("reset: moving to {}", target))?;
+
+    if mode == ResetMode::Hard {
+        println!("HEAD is now at {} {}", &oid[..7], commit.summary());
+    }
+
+    Ok(())
+}
+
+/// Sets the index entries under `paths` back to their state in `target` (HEAD by default),
+/// unstaging them without touching HEAD or the working directory.
+pub fn reset_paths(target: Option<String>, paths: Vec<String>) -> std::io::Result<()> {
+    let target = target.unwrap_or("HEAD".to_string());
+    let commit = read_commit(&resolve_revision(&target)?)?;
+    let target_index = read_tree_to_index(&commit.tree)?;
+    let mut index = read_index()?;
+
+    let matches = |path: &str| paths.iter().any(|spec| under_path(path, spec));
+
+    let unmatched: Vec<&str> = paths.iter()
+        .filter(|spec| !index.iter().chain(&target_index).any(|entry| under_path(&entry.path, spec)))
+        .map(|spec| spec.as_str())
+        .collect();
+    if !unmatched.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Paths did not match any file known to nag: {}", unmatched.join(", ")),
+        ));
+    }
+
+    index.retain(|entry| !matches(&entry.path));
+    index.extend(target_index.into_iter().filter(|entry| matches(&entry.path)));
+    index.sort_by(|a, b| a.path.cmp(&b.path));
+    write_index(&index)?;
+
+    Ok(())
+}
+
+// A file is under a path if it is that file or inside that directory
+fn under_path(path: &str, spec: &str) -> bool {
+    let spec = spec.trim_end_matches('/');
+    path == spec || path.starts_with(&format!("{}/", spec))
+}
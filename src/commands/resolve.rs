@@ -1,5 +1,5 @@
-use crate::core::hash::hash;
 use crate::core::io::{ read_file, write_object };
+use crate::core::object::Object;
 use crate::core::index::{ read_index, write_index, EntryType };
 
 pub fn resolve(path: &str) -> std::io::Result<()> {
@@ -9,8 +9,7 @@ pub fn resolve(path: &str) -> std::io::Result<()> {
     if let Some(entry) = entry {
 
         let file_bytes = read_file(&path.to_string())?;
-        let blob = hash(&file_bytes);
-        write_object(&file_bytes, &blob)?;
+        let blob = write_object(&Object::Blob(file_bytes))?;
 
         entry.oids = vec![blob.clone()];
         entry.entry_type = EntryType::C;
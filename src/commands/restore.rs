@@ -1,69 +1,108 @@
 use crate::core::repo::find_repo_root;
-use crate::core::io::{ read_file, write_file };
+use crate::core::io::{ read_file, read_object, write_file, has_object, remove_empty_parents };
+use crate::core::object::Object;
+use crate::core::refs::{ resolve_head, resolve_revision };
+use crate::core::commit::read_commit;
 use crate::core::tree::read_tree_to_index;
-use crate::core::index::IndexEntry;
+use crate::core::index::{ read_index, write_index, IndexEntry, EntryType };
 
-use std::collections::HashMap;
-use std::fs::create_dir_all;
+use std::fs::remove_file;
 
-pub fn restore(restore_path: String) -> std::io::Result<()> {
+/// Restores `restore_path` (a file or directory) from `source`, HEAD by default. The working
+/// directory is restored unless only `staged` is given, and `staged` resets the index
+/// entries as well. Tracked files the source does not have are deleted, but only from the
+/// working directory when they are committed in HEAD and unchanged since they were staged.
+pub fn restore(restore_path: String, source: Option<String>, staged: bool, worktree: bool) -> std::io::Result<()> {
     let root = find_repo_root()?;
     let nag_dir = root.join(".nag");
+    let worktree = worktree || !staged;
 
-    let proj_head = nag_dir.join("HEAD");
-    let proj_head_contents = read_file(&proj_head.to_string_lossy())?;
-    let head_str = String::from_utf8_lossy(&proj_head_contents);
+    let source = source.unwrap_or("HEAD".to_string());
+    let commit = read_commit(&resolve_revision(&source)?)?;
 
-    let target = head_str.trim();
-    let branch_path_fragment = target.strip_prefix("ref: ").unwrap_or(target);
-    let branch_path = nag_dir.join(branch_path_fragment);
-    let branch_contents = read_file(&branch_path.to_string_lossy())?;
-    let branch_str = String::from_utf8_lossy(&branch_contents).trim().to_string();
-
-    let commit_path = nag_dir.join("objects").join(branch_str.trim());
-    let commit_contents = read_file(&commit_path.to_string_lossy())?;
-    let commit_str = String::from_utf8_lossy(&commit_contents);
-
-    let tree_line = commit_str.lines().next().unwrap();
-    let tree_oid = tree_line.strip_prefix("tree ").unwrap().trim();
-
-    let tree_map: HashMap<String, IndexEntry> = read_tree_to_index(&tree_oid)?
+    let restore_path = restore_path.trim_end_matches('/');
+    let matches = |path: &str| path == restore_path || path.starts_with(&format!("{}/", restore_path));
+    let source_entries: Vec<IndexEntry> = read_tree_to_index(&commit.tree)?
         .into_iter()
-        .map(|entry| {
-            return (entry.path.clone(), entry);
-        })
+        .filter(|entry| matches(&entry.path))
+        .collect();
+    let mut index = read_index()?;
+    let removed: Vec<String> = index.iter()
+        .filter(|entry| matches(&entry.path) && !source_entries.iter().any(|s| s.path == entry.path))
+        .map(|entry| entry.path.clone())
         .collect();
 
-    let mut restored_count = 0;
-    let objects_dir = nag_dir.join("objects");
-    println!("Restored:");
-    for (path, entry) in &tree_map {
-        if path == &restore_path || path.starts_with(&format!("{}/", restore_path)) {
-            if entry.mode == "040000" {
-                let dir_path = root.join(path);
-                create_dir_all(&dir_path)?;
-                continue;
+    // Deleting anything else would lose work that exists nowhere but the working directory
+    let head_oid = resolve_head().map(|head| head.1).unwrap_or_default();
+    let head_paths: Vec<String> = if head_oid.is_empty() {
+        Vec::new()
+    } else {
+        read_tree_to_index(&read_commit(&head_oid)?.tree)?.into_iter().map(|entry| entry.path).collect()
+    };
+    let mut deleted = Vec::new();
+    if worktree {
+        for entry in index.iter().filter(|entry| removed.contains(&entry.path)) {
+            let full_path = root.join(&entry.path);
+            let unchanged = !full_path.is_file()
+                || Object::Blob(read_file(&full_path.to_string_lossy())?).oid() == entry.oids[0];
+            if entry.entry_type == EntryType::C && unchanged && head_paths.contains(&entry.path) {
+                deleted.push(entry.path.clone());
             }
+        }
+    }
 
-            let object_path = objects_dir.join(&entry.oids[0]);
-            if !object_path.exists() {
+    if source_entries.is_empty() && removed.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No matches restored: '{}' is not in '{}' or the index", restore_path, source),
+        ));
+    }
+    if source_entries.is_empty() && deleted.is_empty() && !staged {
+        return Err(std::io::Error::other(format!(
+            "Nothing restored: '{}' is not in '{}', and its files are not committed or have changes (use --staged to unstage them)",
+            restore_path, source,
+        )));
+    }
+
+    let objects_dir = nag_dir.join("objects");
+    if worktree {
+        for entry in &source_entries {
+            if !has_object(&objects_dir, &entry.oids[0]) {
                 return Err(std::io::Error::new(
                     std::io::ErrorKind::NotFound,
-                    format!("Missing blob object for {}", path),
+                    format!("Missing blob object for {}", entry.path),
                 ));
             }
-            let object_contents = read_file(&object_path.to_string_lossy())?;
-            write_file(&object_contents, &root.join(path))?;
-            restored_count += 1;
-            println!("\t{}", path);
+            let object_contents = read_object(&entry.oids[0])?.into_payload("blob")?;
+            write_file(&object_contents, &root.join(&entry.path))?;
+        }
+        for path in &deleted {
+            let full_path = root.join(path);
+            if full_path.is_file() {
+                remove_file(&full_path)?;
+            }
+            // Directories emptied by the removal go too
+            remove_empty_parents(&root, &full_path);
         }
     }
 
-    if restored_count == 0 {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            format!("No matches restored"),
-        ));
+    println!("Restored:");
+    for entry in &source_entries {
+        println!("\t{}", entry.path);
+    }
+    for path in &removed {
+        if deleted.contains(path) {
+            println!("\t{} (deleted)", path);
+        } else if staged {
+            println!("\t{} (unstaged)", path);
+        }
+    }
+
+    if staged {
+        index.retain(|entry| !matches(&entry.path));
+        index.extend(source_entries);
+        index.sort_by(|a, b| a.path.cmp(&b.path));
+        write_index(&index)?;
     }
 
     Ok(())
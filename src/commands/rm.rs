@@ -0,0 +1,93 @@
+use crate::core::repo::{ find_repo_root, repo_relative_path };
+use crate::core::io::{ read_file, remove_empty_parents };
+use crate::core::object::Object;
+use crate::core::refs::resolve_head;
+use crate::core::commit::read_commit;
+use crate::core::tree::read_tree_to_index;
+use crate::core::index::{ read_index, write_index, EntryType };
+
+use std::collections::{ HashMap, HashSet };
+
+/// Stops tracking `paths` and deletes them from the working directory, or only from the
+/// index with `cached`. Directories need `recursive`. Files whose changes are not
+/// committed are refused unless `force` is given, and nothing is removed if any path fails.
+pub fn rm(paths: Vec<String>, cached: bool, recursive: bool, force: bool) -> std::io::Result<()> {
+    let root = find_repo_root()?;
+    let mut index = read_index()?;
+
+    let head_oid = resolve_head().map(|head| head.1).unwrap_or_default();
+    let head_map: HashMap<String, String> = if head_oid.is_empty() {
+        HashMap::new()
+    } else {
+        read_tree_to_index(&read_commit(&head_oid)?.tree)?
+            .into_iter()
+            .map(|entry| (entry.path, entry.oids[0].clone()))
+            .collect()
+    };
+
+    let mut removed: HashSet<String> = HashSet::new();
+    for spec in &paths {
+        let spec = repo_relative_path(spec)?;
+        let matched: Vec<&String> = index.iter()
+            .map(|entry| &entry.path)
+            .filter(|path| **path == spec || spec.is_empty() || path.starts_with(&format!("{}/", spec)))
+            .collect();
+        if matched.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Path '{}' did not match any tracked file", spec),
+            ));
+        }
+        if !recursive && !matched.iter().any(|path| **path == spec) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Not removing '{}' recursively without -r", spec),
+            ));
+        }
+        removed.extend(matched.into_iter().cloned());
+    }
+
+    if !force {
+        let mut refused = Vec::new();
+        for entry in index.iter().filter(|entry| removed.contains(&entry.path)) {
+            // Removing a conflicted file is how a conflict is resolved as a deletion
+            if entry.entry_type == EntryType::X {
+                continue;
+            }
+            let full_path = root.join(&entry.path);
+            let modified = full_path.is_file()
+                && Object::Blob(read_file(&full_path.to_string_lossy())?).oid() != entry.oids[0];
+            let staged = head_map.get(&entry.path) != Some(&entry.oids[0]);
+            // With --cached the file stays on disk, so only a staged version found nowhere else is lost
+            if (cached && modified && staged) || (!cached && (modified || staged)) {
+                refused.push(entry.path.clone());
+            }
+        }
+        if !refused.is_empty() {
+            refused.sort();
+            return Err(std::io::Error::other(format!(
+                "These files have changes that are not committed (use -f to remove them anyway{}):\n\t{}",
+                if cached { "" } else { ", or --cached to keep them on disk" },
+                refused.join("\n\t"),
+            )));
+        }
+    }
+
+    index.retain(|entry| !removed.contains(&entry.path));
+    write_index(&index)?;
+
+    let mut removed: Vec<String> = removed.into_iter().collect();
+    removed.sort();
+    for path in &removed {
+        if !cached {
+            let full_path = root.join(path);
+            if full_path.is_file() {
+                std::fs::remove_file(&full_path)?;
+            }
+            remove_empty_parents(&root, &full_path);
+        }
+        println!("rm '{}'", path);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,318 @@
+use crate::commands::checkout::update_working_tree;
+use crate::core::repo::find_repo_root;
+use crate::core::io::{ read_file, read_object, write_file, write_object };
+use crate::core::object::Object;
+use crate::core::index::{ read_index, write_index, IndexEntry, EntryType };
+use crate::core::tree::{ read_tree_to_index, write_tree_from_index };
+use crate::core::refs::{ resolve_head, update_ref, get_ref_path };
+use crate::core::commit::read_commit;
+use crate::core::config::signature;
+use crate::core::diff::{ get_all_diffs, DiffType, merge3, is_binary, MergeLabels };
+use crate::core::reflog::{ read_reflog, write_reflog, ReflogEntry };
+
+use std::collections::{ HashMap, BTreeSet };
+
+const STASH_REF: &str = "refs/stash";
+
+/*
+A stash is a commit whose tree is the working directory's tracked files, with the
+commit it was made on as first parent and a commit of the index as second parent.
+With --include-untracked a third parent, without parents of its own, holds the
+untracked files. refs/stash points at the newest stash and its log is the stack,
+so stash@{0} is the last line of .nag/logs/refs/stash.
+*/
+
+/// Saves the index and working directory changes as a stash and resets both to HEAD.
+pub fn stash_push(message: Option<String>, include_untracked: bool) -> std::io::Result<()> {
+    let root = find_repo_root()?;
+    let index = read_index()?;
+    if index.iter().any(|entry| entry.entry_type == EntryType::X) {
+        return Err(std::io::Error::other(
+            "Cannot stash: there are unresolved conflicts. Resolve them first",
+        ));
+    }
+    let (branch, head_oid) = resolve_head()?;
+    if head_oid.is_empty() {
+        return Err(std::io::Error::other("Cannot stash before the first commit"));
+    }
+
+    let diffs = get_all_diffs()?;
+    let changed = |kinds: &[DiffType]| kinds.iter().any(|kind| diffs.get(kind).is_some_and(|paths| !paths.is_empty()));
+    let untracked = if include_untracked { diffs.get(&DiffType::Untracked).cloned().unwrap_or_default() } else { Vec::new() };
+    if !changed(&[DiffType::Added, DiffType::Modified, DiffType::Deleted, DiffType::Staged, DiffType::StagedDelete]) && untracked.is_empty() {
+        println!("No local changes to save");
+        return Ok(());
+    }
+
+    let head_commit = read_commit(&head_oid)?;
+    let branch_label = branch.unwrap_or("(no branch)".to_string());
+    let description = match message {
+        Some(msg) => format!("On {}: {}", branch_label, msg.trim()),
+        None => format!("WIP on {}: {} {}", branch_label, &head_oid[..7], head_commit.summary()),
+    };
+
+    let index_tree = write_tree_from_index(&index)?;
+    let index_commit = write_stash_commit(&index_tree, std::slice::from_ref(&head_oid), &format!("index on {}: {} {}", branch_label, &head_oid[..7], head_commit.summary()))?;
+
+    // The working directory's version of every tracked file, leaving out deleted ones
+    let mut worktree = Vec::new();
+    for entry in &index {
+        let full_path = root.join(&entry.path);
+        if full_path.is_file() {
+            let oid = write_object(&Object::Blob(read_file(&full_path.to_string_lossy())?))?;
+            worktree.push(IndexEntry { oids: vec![oid], ..entry.clone() });
+        }
+    }
+    let worktree_tree = write_tree_from_index(&worktree)?;
+
+    let mut parents = vec![head_oid.clone(), index_commit];
+    let mut untracked_entries = Vec::new();
+    if !untracked.is_empty() {
+        for path in &untracked {
+            let oid = write_object(&Object::Blob(read_file(&root.join(path).to_string_lossy())?))?;
+            untracked_entries.push(IndexEntry {
+                entry_type: EntryType::C,
+                path: path.clone(),
+                mode: "100644".to_string(),
+                oids: vec![oid],
+            });
+        }
+        let untracked_tree = write_tree_from_index(&untracked_entries)?;
+        parents.push(write_stash_commit(&untracked_tree, &[], &format!("untracked files on {}", branch_label))?);
+    }
+
+    let stash_oid = write_stash_commit(&worktree_tree, &parents, &description)?;
+    update_ref(STASH_REF, &stash_oid, &description)?;
+
+    // Back to HEAD: stashed files are rewritten or removed, anything else stays
+    let head_index = read_tree_to_index(&head_commit.tree)?;
+    worktree.extend(untracked_entries);
+    update_working_tree(&root, &worktree, &head_index)?;
+    write_index(&head_index)?;
+
+    println!("Saved working directory and index state {}", description);
+    Ok(())
+}
+
+pub fn stash_list(print: bool) -> std::io::Result<String> {
+    let output = read_reflog(STASH_REF)?.iter().rev().enumerate()
+        .map(|(n, entry)| format!("stash@{{{}}}: {}\n", n, entry.message))
+        .collect::<String>();
+
+    if print {
+        print!("{}", output);
+    }
+
+    Ok(output)
+}
+
+/// Lists the files a stash changes relative to the commit it was made on.
+pub fn stash_show(n: usize, print: bool) -> std::io::Result<String> {
+    let stash = read_commit(&stash_entry(n)?.new)?;
+    let base = entry_map(&read_commit(&stash.parents[0])?.tree)?;
+    let stashed = entry_map(&stash.tree)?;
+
+    let paths: BTreeSet<&String> = base.keys().chain(stashed.keys()).collect();
+    let mut output = String::new();
+    for path in paths {
+        match (base.get(path), stashed.get(path)) {
+            (None, Some(_)) => output.push_str(&format!("A\t{}\n", path)),
+            (Some(_), None) => output.push_str(&format!("D\t{}\n", path)),
+            (Some(old), Some(new)) if old != new => output.push_str(&format!("M\t{}\n", path)),
+            _ => {},
+        }
+    }
+    if let Some(untracked_oid) = stash.parents.get(2) {
+        for path in entry_map(&read_commit(untracked_oid)?.tree)?.keys().collect::<BTreeSet<_>>() {
+            output.push_str(&format!("?\t{}\n", path));
+        }
+    }
+
+    if print {
+        print!("{}", output);
+    }
+
+    Ok(output)
+}
+
+/// Replays stash@{n} onto the working directory. Files changed since the stash was made
+/// are merged line by line; conflicts are left marked for `resolve`. Returns whether
+/// the stash applied cleanly.
+pub fn stash_apply(n: usize) -> std::io::Result<bool> {
+    let root = find_repo_root()?;
+    let mut index = read_index()?;
+    if index.iter().any(|entry| entry.entry_type == EntryType::X) {
+        return Err(std::io::Error::other(
+            "Cannot apply a stash: there are unresolved conflicts. Resolve them first",
+        ));
+    }
+
+    let stash = read_commit(&stash_entry(n)?.new)?;
+    let base = entry_map(&read_commit(&stash.parents[0])?.tree)?;
+    let stashed = entry_map(&stash.tree)?;
+    let untracked = match stash.parents.get(2) {
+        Some(oid) => entry_map(&read_commit(oid)?.tree)?,
+        None => HashMap::new(),
+    };
+
+    let mut blocked: Vec<String> = untracked.keys().filter(|path| root.join(path).exists()).cloned().collect();
+    if !blocked.is_empty() {
+        blocked.sort();
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Cannot restore untracked files from stash, they already exist:\n\t{}", blocked.join("\n\t")),
+        ));
+    }
+
+    // Work out every file's new contents before writing any of them
+    let paths: BTreeSet<&String> = base.keys().chain(stashed.keys()).collect();
+    let mut updates: Vec<(String, Option<Vec<u8>>, Option<IndexEntry>)> = Vec::new();
+    let mut conflicted = Vec::new();
+    for path in paths {
+        let (base_oid, stash_oid) = (base.get(path), stashed.get(path));
+        if base_oid == stash_oid {
+            continue;
+        }
+        let full_path = root.join(path);
+        let current = if full_path.is_file() { Some(read_file(&full_path.to_string_lossy())?) } else { None };
+        let current_oid = current.as_ref().map(|bytes| Object::Blob(bytes.clone()).oid());
+        if current_oid.as_ref() == stash_oid {
+            continue;
+        }
+        let stash_bytes = match stash_oid {
+            Some(oid) => Some(read_object(oid)?.into_payload("blob")?),
+            None => None,
+        };
+        // Files the stash made new are tracked again
+        let new_entry = match (base_oid, stash_oid) {
+            (None, Some(oid)) if !index.iter().any(|entry| &entry.path == path) => Some(IndexEntry {
+                entry_type: EntryType::C,
+                path: path.clone(),
+                mode: "100644".to_string(),
+                oids: vec![oid.clone()],
+            }),
+            _ => None,
+        };
+        if current_oid.as_ref() == base_oid {
+            updates.push((path.clone(), stash_bytes, new_entry));
+            continue;
+        }
+
+        let (Some(current), Some(stash_bytes)) = (current, stash_bytes) else {
+            return Err(std::io::Error::other(
+                format!("Cannot apply stash: '{}' is deleted on one side and changed on the other", path),
+            ));
+        };
+        let base_bytes = match base_oid {
+            Some(oid) => read_object(oid)?.into_payload("blob")?,
+            None => Vec::new(),
+        };
+        if is_binary(&current) || is_binary(&stash_bytes) || is_binary(&base_bytes) {
+            return Err(std::io::Error::other(
+                format!("Cannot apply stash: binary file '{}' has changed since it was stashed", path),
+            ));
+        }
+        let labels = MergeLabels { ours: "Updated upstream", base: "Stash base", theirs: "Stashed changes" };
+        let (text, conflict) = merge3(
+            &String::from_utf8_lossy(&base_bytes),
+            &String::from_utf8_lossy(&current),
+            &String::from_utf8_lossy(&stash_bytes),
+            &labels,
+        );
+        let entry = if conflict {
+            conflicted.push(path.clone());
+            Some(IndexEntry {
+                entry_type: EntryType::X,
+                path: path.clone(),
+                mode: "100644".to_string(),
+                oids: vec![write_object(&Object::Blob(current))?, stash_oid.unwrap().clone()],
+            })
+        } else {
+            new_entry
+        };
+        updates.push((path.clone(), Some(text.into_bytes()), entry));
+    }
+
+    for (path, contents, entry) in updates {
+        let full_path = root.join(&path);
+        match contents {
+            Some(bytes) => write_file(&bytes, &full_path)?,
+            None if full_path.is_file() => std::fs::remove_file(&full_path)?,
+            None => {},
+        }
+        if let Some(entry) = entry {
+            index.retain(|existing| existing.path != entry.path);
+            index.push(entry);
+        }
+    }
+    for (path, oid) in &untracked {
+        write_file(&read_object(oid)?.into_payload("blob")?, &root.join(path))?;
+    }
+    index.sort_by(|a, b| a.path.cmp(&b.path));
+    write_index(&index)?;
+
+    if !conflicted.is_empty() {
+        println!("Conflicts while applying stash@{{{}}}:\n\t{}", n, conflicted.join("\n\t"));
+        println!("Fix them and run resolve on each file");
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Applies stash@{n} and drops it, unless applying it left conflicts.
+pub fn stash_pop(n: usize) -> std::io::Result<()> {
+    if stash_apply(n)? {
+        stash_drop(n)?;
+    } else {
+        println!("The stash entry is kept in case you need it again");
+    }
+    Ok(())
+}
+
+pub fn stash_drop(n: usize) -> std::io::Result<()> {
+    let dropped = stash_entry(n)?;
+    let mut entries = read_reflog(STASH_REF)?;
+    let position = entries.len() - 1 - n;
+    entries.remove(position);
+
+    // The entry after the dropped one now follows whatever came before it
+    if let Some(next) = entries.get_mut(position) {
+        next.old = dropped.old.clone();
+    }
+    write_reflog(STASH_REF, &entries)?;
+    // The log was rewritten above, so the ref is moved without adding to it
+    let stash_path = get_ref_path(STASH_REF)?;
+    match entries.last() {
+        Some(top) => write_file(&top.new.as_bytes().to_vec(), &stash_path)?,
+        None if stash_path.is_file() => std::fs::remove_file(stash_path)?,
+        None => {},
+    }
+
+    println!("Dropped stash@{{{}}} ({})", n, dropped.new);
+    Ok(())
+}
+
+fn stash_entry(n: usize) -> std::io::Result<ReflogEntry> {
+    let entries = read_reflog(STASH_REF)?;
+    if n >= entries.len() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            if entries.is_empty() { "No stash entries found".to_string() } else { format!("stash@{{{}}} does not exist", n) },
+        ));
+    }
+    Ok(entries[entries.len() - 1 - n].clone())
+}
+
+fn entry_map(tree_oid: &str) -> std::io::Result<HashMap<String, String>> {
+    Ok(read_tree_to_index(tree_oid)?.into_iter().map(|entry| (entry.path, entry.oids[0].clone())).collect())
+}
+
+fn write_stash_commit(tree: &str, parents: &[String], message: &str) -> std::io::Result<String> {
+    let signature = signature()?;
+    let mut commit_str = format!("tree {}\n", tree);
+    for parent in parents {
+        commit_str.push_str(&format!("parent {}\n", parent));
+    }
+    commit_str.push_str(&format!("author {}\ncommitter {}\n\n{}\n", signature, signature, message));
+    write_object(&Object::Commit(commit_str.into_bytes()))
+}
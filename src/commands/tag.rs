@@ -3,9 +3,10 @@ use crate::core::refs::{
     read_ref,
     update_ref,
     resolve_head,
+    resolve_revision,
 };
 use crate::core::repo::find_repo_root;
-use crate::core::hash::hash;
+use crate::core::object::Object;
 use crate::core::io::write_object;
 
 use std::fs::remove_file;
@@ -14,15 +15,8 @@ pub fn tag(tag_name: Option<String>, commit: Option<String>, message: Option<Str
     if let Some(name) = tag_name {
         let oid = if commit.is_none() {
             resolve_head()?.1
-        } else if let Some(commit_oid) = commit {
-            let commit_path = find_repo_root()?.join(".nag/objects").join(&commit_oid);
-            if !commit_path.exists() {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    format!("Commit not found"),
-                ));
-            }
-            commit_oid
+        } else if let Some(revision) = commit {
+            resolve_revision(&revision)?
         } else {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::Other,
@@ -33,12 +27,10 @@ pub fn tag(tag_name: Option<String>, commit: Option<String>, message: Option<Str
             let mut annotated = String::new();
             annotated.push_str(&format!("object {}\n\n", oid));
             annotated.push_str(&msg);
-            let bytes = annotated.as_bytes().to_vec();
-            let annotated_tag_oid = hash(&bytes);
-            write_object(&bytes, &annotated_tag_oid)?;
-            update_ref(&format!("refs/tags/{}", name), &annotated_tag_oid)?;
+            let annotated_tag_oid = write_object(&Object::Tag(annotated.into_bytes()))?;
+            update_ref(&format!("refs/tags/{}", name), &annotated_tag_oid, "")?;
         } else {
-            update_ref(&format!("refs/tags/{}", name), &oid)?;
+            update_ref(&format!("refs/tags/{}", name), &oid, "")?;
         }
     }
 
@@ -0,0 +1,221 @@
+use crate::core::commit::read_commit;
+use crate::core::repo::find_repo_root;
+use crate::core::io::{ read_file, write_file };
+
+use std::collections::{ BinaryHeap, HashMap, HashSet };
+use std::path::PathBuf;
+
+const FROM_ONE: u8 = 1;
+const FROM_TWO: u8 = 2;
+const STALE: u8 = 4;
+const RESULT: u8 = 8;
+
+/// Lazily loaded parent links plus generation numbers, where a root commit has
+/// generation 1 and every other commit sits one above its highest parent. Commits never
+/// change, so generations are cached in `.nag/generations` and only new commits are walked.
+#[derive(Default)]
+pub struct CommitGraph {
+    parents: HashMap<String, Vec<String>>,
+    generations: HashMap<String, u64>,
+    cache_loaded: bool,
+}
+
+impl CommitGraph {
+    pub fn new() -> CommitGraph {
+        CommitGraph::default()
+    }
+
+    pub fn parents(&mut self, oid: &str) -> std::io::Result<Vec<String>> {
+        if let Some(parents) = self.parents.get(oid) {
+            return Ok(parents.clone());
+        }
+        let commit = read_commit(oid)?;
+        self.parents.insert(oid.to_string(), commit.parents.clone());
+        Ok(commit.parents)
+    }
+
+    pub fn generation(&mut self, oid: &str) -> std::io::Result<u64> {
+        if !self.cache_loaded {
+            self.load_generations()?;
+        }
+        if let Some(generation) = self.generations.get(oid) {
+            return Ok(*generation);
+        }
+
+        // Post-order walk so deep histories never recurse on the call stack
+        let mut computed = Vec::new();
+        let mut stack: Vec<(String, bool)> = vec![(oid.to_string(), false)];
+        while let Some((current, expanded)) = stack.pop() {
+            if self.generations.contains_key(&current) {
+                continue;
+            }
+            let parents = self.parents(&current)?;
+            if expanded {
+                let highest = parents.iter().filter_map(|p| self.generations.get(p)).max().copied().unwrap_or(0);
+                computed.push((current.clone(), highest + 1));
+                self.generations.insert(current, highest + 1);
+            } else {
+                stack.push((current, true));
+                for parent in parents {
+                    if !self.generations.contains_key(&parent) {
+                        stack.push((parent, false));
+                    }
+                }
+            }
+        }
+
+        self.save_generations(&computed)?;
+        Ok(self.generations[oid])
+    }
+
+    fn load_generations(&mut self) -> std::io::Result<()> {
+        self.cache_loaded = true;
+        let Some(path) = generations_path() else {
+            return Ok(());
+        };
+        if !path.is_file() {
+            return Ok(());
+        }
+        for line in String::from_utf8_lossy(&read_file(&path.to_string_lossy())?).lines() {
+            if let Some((oid, generation)) = line.split_once(' ')
+                && let Ok(generation) = generation.trim().parse() {
+                self.generations.insert(oid.to_string(), generation);
+            }
+        }
+        Ok(())
+    }
+
+    fn save_generations(&self, computed: &[(String, u64)]) -> std::io::Result<()> {
+        let Some(path) = generations_path() else {
+            return Ok(());
+        };
+        if computed.is_empty() {
+            return Ok(());
+        }
+        let mut buf = if path.is_file() { read_file(&path.to_string_lossy())? } else { Vec::new() };
+        for (oid, generation) in computed {
+            buf.extend_from_slice(format!("{} {}\n", oid, generation).as_bytes());
+        }
+        write_file(&buf, &path)
+    }
+
+    /// Whether `ancestor` is reachable from `descendant` (a commit counts as its own ancestor).
+    pub fn is_ancestor(&mut self, ancestor: &str, descendant: &str) -> std::io::Result<bool> {
+        if ancestor == descendant {
+            return Ok(true);
+        }
+        let floor = self.generation(ancestor)?;
+
+        let mut seen = HashSet::new();
+        let mut queue = vec![descendant.to_string()];
+        while let Some(current) = queue.pop() {
+            if current == ancestor {
+                return Ok(true);
+            }
+            // Nothing at or below the ancestor's generation can lead back up to it
+            if !seen.insert(current.clone()) || self.generation(&current)? <= floor {
+                continue;
+            }
+            queue.extend(self.parents(&current)?);
+        }
+        Ok(false)
+    }
+
+    /// All best common ancestors of two commits, highest generation first.
+    pub fn merge_bases(&mut self, one: &str, two: &str) -> std::io::Result<Vec<String>> {
+        if one == two {
+            return Ok(vec![one.to_string()]);
+        }
+
+        let mut flags: HashMap<String, u8> = HashMap::new();
+        let mut queue: BinaryHeap<(u64, String)> = BinaryHeap::new();
+        flags.insert(one.to_string(), FROM_ONE);
+        flags.insert(two.to_string(), FROM_TWO);
+        queue.push((self.generation(one)?, one.to_string()));
+        queue.push((self.generation(two)?, two.to_string()));
+        // Entries still in the queue that are not stale; the walk ends when none are left
+        let mut live = 2;
+
+        let mut candidates = Vec::new();
+        // Popping by generation guarantees every descendant is painted before its ancestors,
+        // so each commit is queued once and its flags are complete when it is popped
+        while live > 0 {
+            let Some((_, current)) = queue.pop() else { break };
+            let mut current_flags = flags[&current] & (FROM_ONE | FROM_TWO | STALE);
+            if current_flags & STALE == 0 {
+                live -= 1;
+            }
+
+            if current_flags & (FROM_ONE | FROM_TWO) == (FROM_ONE | FROM_TWO) {
+                if flags[&current] & RESULT == 0 && current_flags & STALE == 0 {
+                    flags.insert(current.clone(), flags[&current] | RESULT);
+                    candidates.push(current.clone());
+                }
+                current_flags |= STALE;
+            }
+
+            for parent in self.parents(&current)? {
+                let queued = flags.get(&parent).copied();
+                let parent_flags = queued.unwrap_or(0);
+                if queued.is_some() && parent_flags & current_flags == current_flags {
+                    continue;
+                }
+                let new_flags = parent_flags | current_flags;
+                flags.insert(parent.clone(), new_flags);
+                if queued.is_none() {
+                    if new_flags & STALE == 0 {
+                        live += 1;
+                    }
+                    queue.push((self.generation(&parent)?, parent));
+                } else if parent_flags & STALE == 0 && new_flags & STALE != 0 {
+                    live -= 1;
+                }
+            }
+        }
+
+        self.independent(candidates)
+    }
+
+    /// Drops every commit that is an ancestor of another one in the list.
+    pub fn independent(&mut self, commits: Vec<String>) -> std::io::Result<Vec<String>> {
+        let mut unique: Vec<String> = Vec::new();
+        for oid in commits {
+            if !unique.contains(&oid) {
+                unique.push(oid);
+            }
+        }
+
+        let mut kept = Vec::new();
+        for oid in &unique {
+            let mut redundant = false;
+            for other in &unique {
+                if other != oid && self.is_ancestor(oid, other)? {
+                    redundant = true;
+                    break;
+                }
+            }
+            if !redundant {
+                kept.push(oid.clone());
+            }
+        }
+
+        let mut ranked = Vec::new();
+        for oid in kept {
+            ranked.push((self.generation(&oid)?, oid));
+        }
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        Ok(ranked.into_iter().map(|(_, oid)| oid).collect())
+    }
+}
+
+fn generations_path() -> Option<PathBuf> {
+    find_repo_root().ok().map(|root| root.join(".nag").join("generations"))
+}
+
+pub fn merge_bases(one: &str, two: &str) -> std::io::Result<Vec<String>> {
+    CommitGraph::new().merge_bases(one, two)
+}
+
+pub fn is_ancestor(ancestor: &str, descendant: &str) -> std::io::Result<bool> {
+    CommitGraph::new().is_ancestor(ancestor, descendant)
+}
@@ -0,0 +1,211 @@
+use crate::core::io::read_object;
+
+use std::fmt;
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Signature {
+    pub name: String,
+    pub email: String,
+    pub timestamp: i64,
+    pub timezone: String,
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Commit {
+    pub oid: String,
+    pub tree: String,
+    pub parents: Vec<String>,
+    pub author: Option<Signature>,
+    pub committer: Option<Signature>,
+    pub message: String,
+}
+
+impl Signature {
+    // Parses `Name <email> timestamp offset`
+    pub fn parse(line: &str) -> Option<Signature> {
+        let (ident, rest) = line.rsplit_once('>')?;
+        let (name, email) = ident.split_once('<')?;
+        let mut when = rest.split_whitespace();
+        let timestamp = when.next()?.parse::<i64>().ok()?;
+        let timezone = when.next().unwrap_or("+0000").to_string();
+
+        Some(Signature {
+            name: name.trim().to_string(),
+            email: email.trim().to_string(),
+            timestamp,
+            timezone,
+        })
+    }
+
+    pub fn date_string(&self) -> String {
+        format_timestamp(self.timestamp, &self.timezone)
+    }
+}
+
+// Writes the signature back as it appears on author and committer lines
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} <{}> {} {}", self.name, self.email, self.timestamp, self.timezone)
+    }
+}
+
+impl Commit {
+    pub fn summary(&self) -> &str {
+        self.message.lines().next().unwrap_or("")
+    }
+
+    pub fn time(&self) -> i64 {
+        self.committer.as_ref().or(self.author.as_ref()).map(|sig| sig.timestamp).unwrap_or(0)
+    }
+}
+
+pub fn parse_commit(oid: &str, bytes: &[u8]) -> std::io::Result<Commit> {
+    let commit_str = String::from_utf8_lossy(bytes);
+
+    let mut tree = None;
+    let mut parents = Vec::new();
+    let mut author = None;
+    let mut committer = None;
+
+    let (header, message) = commit_str.split_once("\n\n").unwrap_or((&commit_str, ""));
+    for line in header.lines() {
+        if let Some(value) = line.strip_prefix("tree ") {
+            tree = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("parent ") {
+            parents.push(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("author ") {
+            author = Signature::parse(value);
+        } else if let Some(value) = line.strip_prefix("committer ") {
+            committer = Signature::parse(value);
+        }
+    }
+
+    let Some(tree) = tree else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Object {} is not a commit", oid),
+        ));
+    };
+
+    Ok(Commit {
+        oid: oid.to_string(),
+        tree,
+        parents,
+        author,
+        committer,
+        message: message.trim_end().to_string(),
+    })
+}
+
+pub fn read_commit(oid: &str) -> std::io::Result<Commit> {
+    let Ok(object) = read_object(oid) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Commit object {} not found", oid),
+        ));
+    };
+    let commit_bytes = object.into_payload("commit")?;
+    parse_commit(oid, &commit_bytes)
+}
+
+// Renders a Unix timestamp as `YYYY-MM-DD HH:MM:SS +ZZZZ` in the given offset
+pub fn format_timestamp(timestamp: i64, timezone: &str) -> String {
+    let offset_secs = parse_timezone(timezone).unwrap_or(0);
+    let local = timestamp + offset_secs;
+
+    let days = local.div_euclid(86_400);
+    let secs_of_day = local.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} {}",
+        year, month, day,
+        secs_of_day / 3600, (secs_of_day % 3600) / 60, secs_of_day % 60,
+        timezone,
+    )
+}
+
+pub fn parse_timezone(timezone: &str) -> Option<i64> {
+    if timezone.len() != 5 {
+        return None;
+    }
+    let sign = match &timezone[..1] {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let hours = timezone[1..3].parse::<i64>().ok()?;
+    let minutes = timezone[3..5].parse::<i64>().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian (year, month, day)
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// A proleptic Gregorian (year, month, day) to days since 1970-01-01
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Parses the dates accepted in `<ref>@{<date>}` into a Unix timestamp, relative to `now`:
+/// `now`, `yesterday`, `<n> <unit>s ago` (also written `<n>.<unit>s.ago`, units from
+/// seconds to years), and `YYYY-MM-DD[ HH:MM[:SS]][ +ZZZZ]`, which is taken as UTC
+/// unless an offset is given.
+pub fn parse_date(date: &str, now: i64) -> Option<i64> {
+    let words: Vec<&str> = date.split(['.', ' ']).filter(|w| !w.is_empty()).collect();
+    match words.as_slice() {
+        ["now"] => return Some(now),
+        ["yesterday"] => return Some(now - 86_400),
+        [count, unit, "ago"] => {
+            let count = count.parse::<i64>().ok()?;
+            let seconds = match unit.strip_suffix('s').unwrap_or(unit) {
+                "second" => 1,
+                "minute" => 60,
+                "hour" => 3600,
+                "day" => 86_400,
+                "week" => 7 * 86_400,
+                "month" => 30 * 86_400,
+                "year" => 365 * 86_400,
+                _ => return None,
+            };
+            return Some(now - count * seconds);
+        },
+        _ => {},
+    }
+
+    let mut parts = date.split([' ', 'T']).filter(|p| !p.is_empty());
+    let mut ymd = parts.next()?.split('-').map(|n| n.parse::<i64>().ok());
+    let (year, month, day) = (ymd.next()??, ymd.next()??, ymd.next()??);
+    if ymd.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let mut seconds = days_from_civil(year, month, day) * 86_400;
+    for part in parts {
+        if part.starts_with(['+', '-']) {
+            seconds -= parse_timezone(part)?;
+            continue;
+        }
+        let mut hms = part.split(':').map(|n| n.parse::<i64>().ok());
+        let (hour, minute) = (hms.next()??, hms.next()??);
+        let second = hms.next().unwrap_or(Some(0))?;
+        seconds += hour * 3600 + minute * 60 + second;
+    }
+    Some(seconds)
+}
@@ -0,0 +1,143 @@
+use crate::core::repo::find_repo_root;
+use crate::core::io::{ read_file, write_file };
+
+use std::path::{ Path, PathBuf };
+use std::time::{ SystemTime, UNIX_EPOCH };
+
+pub fn config_path(global: bool) -> std::io::Result<PathBuf> {
+    if global {
+        let home = std::env::var("HOME").map_err(|_| std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "HOME is not set, cannot locate the global config",
+        ))?;
+        return Ok(PathBuf::from(home).join(".nagconfig"));
+    }
+    Ok(find_repo_root()?.join(".nag").join("config"))
+}
+
+pub fn read_config(global: bool) -> std::io::Result<Vec<(String, String)>> {
+    read_config_file(&config_path(global)?)
+}
+
+/// Reads the entries of any config file, such as a remote repository's.
+pub fn read_config_file(path: &Path) -> std::io::Result<Vec<(String, String)>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = read_file(&path.to_string_lossy())?;
+    let contents_str = String::from_utf8_lossy(&contents);
+
+    let mut entries = Vec::new();
+    for line in contents_str.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = trimmed.split_once('=') {
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    Ok(entries)
+}
+
+fn write_config(entries: &[(String, String)], global: bool) -> std::io::Result<()> {
+    let mut buf = String::new();
+    for (key, value) in entries {
+        buf.push_str(&format!("{} = {}\n", key, value));
+    }
+    write_file(&buf.as_bytes().to_vec(), &config_path(global)?)?;
+    Ok(())
+}
+
+/// Looks a key up in the repository config first, then the global one.
+pub fn get_config_value(key: &str) -> std::io::Result<Option<String>> {
+    Ok(read_effective_config()?.into_iter().find(|(k, _)| k == key).map(|(_, value)| value))
+}
+
+/// The config in effect: global entries with the repository's overriding them, then any
+/// keys only the repository sets. Either file may be missing.
+pub fn read_effective_config() -> std::io::Result<Vec<(String, String)>> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    read_effective_config_with(home.as_deref())
+}
+
+/// `read_effective_config` with the global config read from `home` rather than `$HOME`,
+/// or skipped when there is none.
+pub fn read_effective_config_with(home: Option<&Path>) -> std::io::Result<Vec<(String, String)>> {
+    let mut entries = match home {
+        Some(home) => read_config_file(&home.join(".nagconfig"))?,
+        None => Vec::new(),
+    };
+    let local = if find_repo_root().is_ok() { read_config(false)? } else { Vec::new() };
+    for (key, value) in local {
+        match entries.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => entries.push((key, value)),
+        }
+    }
+    Ok(entries)
+}
+
+pub fn set_config_value(key: &str, value: &str, global: bool) -> std::io::Result<()> {
+    if !key.contains('.') || key.starts_with('.') || key.ends_with('.') || key.contains(char::is_whitespace) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid config key '{}', expected <section>.<name>", key),
+        ));
+    }
+
+    let mut entries = read_config(global)?;
+    if let Some(entry) = entries.iter_mut().find(|(k, _)| k == key) {
+        entry.1 = value.to_string();
+    } else {
+        entries.push((key.to_string(), value.to_string()));
+    }
+    write_config(&entries, global)?;
+    Ok(())
+}
+
+/// Builds the `Name <email> timestamp offset` signature used on author and committer lines.
+pub fn signature() -> std::io::Result<String> {
+    let name = match get_config_value("user.name")? {
+        Some(name) => name,
+        None => std::env::var("USER").unwrap_or("unknown".to_string()),
+    };
+    let email = get_config_value("user.email")?.unwrap_or_default();
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(std::io::Error::other)?
+        .as_secs();
+    let timezone = match get_config_value("user.timezone")? {
+        Some(timezone) => timezone,
+        None => local_timezone(timestamp as i64),
+    };
+
+    Ok(format!("{} <{}> {} {}", name, email, timestamp, timezone))
+}
+
+/// The system's offset from UTC at `timestamp`, as `+HHMM` or `-HHMM`.
+pub fn local_timezone(timestamp: i64) -> String {
+    let offset = local_offset_seconds(timestamp);
+    let minutes = offset.abs() / 60;
+    format!("{}{:02}{:02}", if offset < 0 { '-' } else { '+' }, minutes / 60, minutes % 60)
+}
+
+#[cfg(unix)]
+#[allow(clippy::useless_conversion)] // tm_gmtoff is a c_long, only 32 bits on some targets
+fn local_offset_seconds(timestamp: i64) -> i64 {
+    let time = timestamp as libc::time_t;
+    // SAFETY: localtime_r only writes to the tm it is given, which is valid for the call
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return 0;
+    }
+    i64::from(tm.tm_gmtoff)
+}
+
+// Without a way to ask the system, times are recorded as UTC
+#[cfg(not(unix))]
+fn local_offset_seconds(_timestamp: i64) -> i64 {
+    0
+}
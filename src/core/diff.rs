@@ -1,8 +1,9 @@
 use crate::core::hash::hash;
-use crate::core::io::read_file;
+use crate::core::io::{ read_file, read_object };
 use crate::core::index::{ read_index, EntryType };
 use crate::core::repo::find_repo_root;
 use crate::core::tree::read_tree_to_index;
+use crate::core::refs::resolve_head;
 use crate::core::ignore::should_ignore;
 
 use std::fs::read_dir;
@@ -39,21 +40,12 @@ pub fn diff_index_to_head() -> std::io::Result<HashMap<DiffType, Vec<String>>> {
 
     let wrk_paths: HashSet<_> = working.iter().map(|(_, p)| p.clone()).collect();
 
-    let head_path = root.join(".nag").join("HEAD");
-    let proj_head_contents = read_file(&head_path.to_string_lossy())?;
-    let head_str = String::from_utf8_lossy(&proj_head_contents);
-
-    let target = head_str.trim();
-    let branch_path_fragment = target.strip_prefix("ref: ").unwrap_or(target);
-    let branch_path = root.join(".nag").join(branch_path_fragment);
-    let branch_contents = read_file(&branch_path.to_string_lossy())?;
-    let branch_oid = String::from_utf8_lossy(&branch_contents).trim().to_string();
+    let branch_oid = resolve_head()?.1;
 
     let head_index_map = if branch_oid.trim().is_empty() {
         HashMap::new()
     } else {
-        let commit_path = root.join(".nag").join("objects").join(&branch_oid);
-        let commit_contents = read_file(&commit_path.to_string_lossy())?;
+        let commit_contents = read_object(&branch_oid)?.into_payload("commit")?;
         let commit_str = String::from_utf8_lossy(&commit_contents);
 
         let tree_line = commit_str
@@ -165,3 +157,244 @@ fn walk(path: &Path, working: &mut Vec<(String, String)>, root: &Path) -> std::i
     }
     Ok(())
 }
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum LineOp {
+    Equal(usize, usize), // (old index, new index)
+    Delete(usize),       // old index
+    Insert(usize),       // new index
+}
+
+/// Myers' O(ND) shortest edit script between two sequences of lines.
+pub fn diff_lines<T: PartialEq>(old: &[T], new: &[T]) -> Vec<LineOp> {
+    // Common prefix and suffix never need the search, and trimming them keeps the trace small
+    let mut prefix = 0;
+    while prefix < old.len() && prefix < new.len() && old[prefix] == new[prefix] {
+        prefix += 1;
+    }
+    let mut suffix = 0;
+    while suffix < old.len() - prefix && suffix < new.len() - prefix
+        && old[old.len() - 1 - suffix] == new[new.len() - 1 - suffix] {
+        suffix += 1;
+    }
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = n + m;
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max {
+        trace.push(v.clone());
+        let mut k = -d;
+        while k <= d {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+            k += 2;
+        }
+    }
+
+    let mut middle = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let idx = (k + offset) as usize;
+        let prev_k = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) { k + 1 } else { k - 1 };
+        let prev_x = v[(prev_k + offset) as usize];
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            middle.push(LineOp::Equal(x as usize + prefix, y as usize + prefix));
+        }
+        if d > 0 {
+            if x == prev_x {
+                y -= 1;
+                middle.push(LineOp::Insert(y as usize + prefix));
+            } else {
+                x -= 1;
+                middle.push(LineOp::Delete(x as usize + prefix));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    middle.reverse();
+
+    let mut ops: Vec<LineOp> = (0..prefix).map(|i| LineOp::Equal(i, i)).collect();
+    ops.extend(middle);
+    for i in 0..suffix {
+        ops.push(LineOp::Equal(old.len() - suffix + i, new.len() - suffix + i));
+    }
+    ops
+}
+
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|b| *b == 0)
+}
+
+/// Renders `@@` hunks for two texts, keeping `context` unchanged lines around each change.
+pub fn unified_diff(old: &str, new: &str, context: usize) -> String {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+
+    let changes: Vec<usize> = ops.iter().enumerate()
+        .filter(|(_, op)| !matches!(op, LineOp::Equal(_, _)))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut buf = String::new();
+    let mut c = 0;
+    while c < changes.len() {
+        let start = changes[c].saturating_sub(context);
+        let mut last = changes[c];
+        while c + 1 < changes.len() && changes[c + 1] - last <= 2 * context + 1 {
+            c += 1;
+            last = changes[c];
+        }
+        let end = (last + context + 1).min(ops.len());
+        c += 1;
+
+        let old_before = ops[..start].iter().filter(|op| !matches!(op, LineOp::Insert(_))).count();
+        let new_before = ops[..start].iter().filter(|op| !matches!(op, LineOp::Delete(_))).count();
+        let old_count = ops[start..end].iter().filter(|op| !matches!(op, LineOp::Insert(_))).count();
+        let new_count = ops[start..end].iter().filter(|op| !matches!(op, LineOp::Delete(_))).count();
+
+        buf.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_before, old_count),
+            hunk_range(new_before, new_count),
+        ));
+
+        for op in &ops[start..end] {
+            let (marker, line) = match op {
+                LineOp::Equal(x, _) => (' ', old_lines[*x]),
+                LineOp::Delete(x) => ('-', old_lines[*x]),
+                LineOp::Insert(y) => ('+', new_lines[*y]),
+            };
+            buf.push(marker);
+            buf.push_str(line);
+            if !line.ends_with('\n') {
+                buf.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    buf
+}
+
+fn hunk_range(before: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", before),
+        1 => format!("{}", before + 1),
+        _ => format!("{},{}", before + 1, count),
+    }
+}
+
+pub struct MergeLabels<'a> {
+    pub ours: &'a str,
+    pub base: &'a str,
+    pub theirs: &'a str,
+}
+
+/// diff3-style merge of two descendants of `base`. Returns the merged text and
+/// whether any region needed conflict markers.
+pub fn merge3(base: &str, ours: &str, theirs: &str, labels: &MergeLabels) -> (String, bool) {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let our_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+    let their_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+
+    let our_matches = matched_lines(&diff_lines(&base_lines, &our_lines), base_lines.len());
+    let their_matches = matched_lines(&diff_lines(&base_lines, &their_lines), base_lines.len());
+
+    let mut merged = String::new();
+    let mut has_conflicts = false;
+    let (mut o, mut a, mut b) = (0, 0, 0);
+
+    loop {
+        // Lines unchanged on both sides are copied straight through
+        while o < base_lines.len() && our_matches[o] == Some(a) && their_matches[o] == Some(b) {
+            merged.push_str(base_lines[o]);
+            o += 1;
+            a += 1;
+            b += 1;
+        }
+
+        // Find the next base line both sides kept, which closes the unstable chunk
+        let next_stable = (o..base_lines.len()).find(|i| our_matches[*i].is_some() && their_matches[*i].is_some());
+        let (o_end, a_end, b_end) = match next_stable {
+            Some(i) => (i, our_matches[i].unwrap(), their_matches[i].unwrap()),
+            None => (base_lines.len(), our_lines.len(), their_lines.len()),
+        };
+
+        if o == o_end && a == a_end && b == b_end {
+            break;
+        }
+
+        let base_chunk = &base_lines[o..o_end];
+        let our_chunk = &our_lines[a..a_end];
+        let their_chunk = &their_lines[b..b_end];
+
+        if our_chunk == base_chunk || our_chunk == their_chunk {
+            merged.push_str(&their_chunk.concat());
+        } else if their_chunk == base_chunk {
+            merged.push_str(&our_chunk.concat());
+        } else {
+            has_conflicts = true;
+            push_conflict_side(&mut merged, &format!("<<<<<<< {}", labels.ours), our_chunk);
+            push_conflict_side(&mut merged, &format!("||||||| {}", labels.base), base_chunk);
+            push_conflict_side(&mut merged, "=======", their_chunk);
+            merged.push_str(&format!(">>>>>>> {}\n", labels.theirs));
+        }
+
+        o = o_end;
+        a = a_end;
+        b = b_end;
+    }
+
+    (merged, has_conflicts)
+}
+
+// For each base line, the line it is kept as on the other side, if any
+fn matched_lines(ops: &[LineOp], base_len: usize) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base_len];
+    for op in ops {
+        if let LineOp::Equal(x, y) = op {
+            matches[*x] = Some(*y);
+        }
+    }
+    matches
+}
+
+fn push_conflict_side(merged: &mut String, marker: &str, lines: &[&str]) {
+    if !merged.is_empty() && !merged.ends_with('\n') {
+        merged.push('\n');
+    }
+    merged.push_str(marker);
+    merged.push('\n');
+    for line in lines {
+        merged.push_str(line);
+    }
+    if !merged.ends_with('\n') {
+        merged.push('\n');
+    }
+}
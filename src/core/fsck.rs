@@ -0,0 +1,167 @@
+use crate::core::repo::find_repo_root;
+use crate::core::io::{ read_object_from, loose_oids };
+use crate::core::index::read_index;
+use crate::core::refs::{ list_refs, read_ref, resolve_head, read_merge_head };
+use crate::core::pack::{ packed_oids, read_packed_object };
+use crate::core::reflog::reflog_oids;
+use crate::core::tree::is_canonical_tree;
+
+use std::collections::{ HashMap, HashSet };
+
+/// What `check_repository` found. Errors are corrupt objects, missing objects, trees not in
+/// canonical form and links or refs that point at the wrong type of object; dangling
+/// objects are only reported.
+#[derive(Debug, Default)]
+pub struct FsckReport {
+    pub errors: Vec<String>,
+    pub dangling: Vec<String>,
+}
+
+// An object some ref or other object points at, and the type it must have
+struct Link {
+    oid: String,
+    expected: &'static str,
+    from: String,
+}
+
+/// Rehashes every loose and packed object, then walks everything reachable from the
+/// branches, tags, remote-tracking refs and `refs/stash` (plus every reflog entry, a
+/// detached HEAD, MERGE_HEAD and the index, which also keep objects alive) checking
+/// that each link resolves.
+pub fn check_repository() -> std::io::Result<FsckReport> {
+    let objects_dir = find_repo_root()?.join(".nag").join("objects");
+    let mut report = FsckReport::default();
+
+    // Kind of every object that decodes and hashes to its own oid, None for corrupt ones
+    let mut present: HashMap<String, Option<&'static str>> = HashMap::new();
+    for (oid, _) in loose_oids(&objects_dir)? {
+        let kind = match read_object_from(&objects_dir, &oid) {
+            Ok(object) if object.oid() == oid => Some(object.kind()),
+            Ok(object) => {
+                report.errors.push(format!("corrupt {}: content hashes to {}", oid, object.oid()));
+                None
+            },
+            Err(e) => {
+                report.errors.push(format!("corrupt {}: {}", oid, e));
+                None
+            },
+        };
+        present.insert(oid, kind);
+    }
+    for oid in packed_oids(&objects_dir)? {
+        if present.contains_key(&oid) {
+            continue;
+        }
+        let kind = match read_packed_object(&objects_dir, &oid) {
+            Ok(Some(object)) if object.oid() == oid => Some(object.kind()),
+            Ok(Some(object)) => {
+                report.errors.push(format!("corrupt {} in pack: content hashes to {}", oid, object.oid()));
+                None
+            },
+            Ok(None) => None,
+            Err(e) => {
+                report.errors.push(format!("corrupt {} in pack: {}", oid, e));
+                None
+            },
+        };
+        present.insert(oid, kind);
+    }
+
+    let mut stack = Vec::new();
+    for name in list_refs("refs")? {
+        let full_name = format!("refs/{}", name);
+        let oid = read_ref(&full_name)?;
+        if oid.is_empty() {
+            continue;
+        }
+        let expected = if name.starts_with("tags/") && present.get(&oid) == Some(&Some("tag")) {
+            "tag"
+        } else {
+            "commit"
+        };
+        stack.push(Link { oid, expected, from: full_name });
+    }
+    // Reflogs usually repeat what the refs point at; those are only reported once, via the ref
+    let ref_oids: HashSet<String> = stack.iter().map(|link| link.oid.clone()).collect();
+    for (log_name, oid) in reflog_oids()? {
+        if ref_oids.contains(&oid) {
+            continue;
+        }
+        stack.push(Link { oid, expected: "commit", from: format!("reflog of {}", log_name) });
+    }
+    if let Ok((None, head_oid)) = resolve_head() {
+        stack.push(Link { oid: head_oid, expected: "commit", from: "HEAD".to_string() });
+    }
+    if let Some(merge_oid) = read_merge_head()? {
+        stack.push(Link { oid: merge_oid, expected: "commit", from: "MERGE_HEAD".to_string() });
+    }
+    for entry in read_index()? {
+        for oid in entry.oids.into_iter().filter(|oid| oid != "empty") {
+            stack.push(Link { oid, expected: "blob", from: format!("index entry {}", entry.path) });
+        }
+    }
+
+    let mut reachable = HashSet::new();
+    while let Some(link) = stack.pop() {
+        let kind = match present.get(&link.oid) {
+            None => {
+                report.errors.push(format!("missing {} {} (referenced by {})", link.expected, link.oid, link.from));
+                continue;
+            },
+            Some(None) => continue,
+            Some(Some(kind)) => *kind,
+        };
+        if kind != link.expected {
+            report.errors.push(format!("{} points at {} {}, expected a {}", link.from, kind, link.oid, link.expected));
+            continue;
+        }
+        if !reachable.insert(link.oid.clone()) {
+            continue;
+        }
+
+        let object = read_object_from(&objects_dir, &link.oid)?;
+        let payload = String::from_utf8_lossy(object.data()).to_string();
+        let from = format!("{} {}", kind, link.oid);
+        match kind {
+            "commit" => {
+                for line in payload.lines().take_while(|line| !line.is_empty()) {
+                    if let Some(tree_oid) = line.strip_prefix("tree ") {
+                        stack.push(Link { oid: tree_oid.trim().to_string(), expected: "tree", from: from.clone() });
+                    } else if let Some(parent_oid) = line.strip_prefix("parent ") {
+                        stack.push(Link { oid: parent_oid.trim().to_string(), expected: "commit", from: from.clone() });
+                    }
+                }
+            },
+            "tree" => {
+                if !is_canonical_tree(object.data()) {
+                    report.errors.push(format!("{} is not canonical: entries are malformed, out of order or repeated", from));
+                }
+                for line in payload.lines() {
+                    let parts: Vec<&str> = line.split('\t').collect();
+                    if parts.len() < 4 {
+                        report.errors.push(format!("{} has a malformed entry '{}'", from, line));
+                        continue;
+                    }
+                    let expected = if parts[1] == "040000" { "tree" } else { "blob" };
+                    stack.push(Link { oid: parts[3].to_string(), expected, from: from.clone() });
+                }
+            },
+            "tag" => {
+                match payload.lines().next().and_then(|l| l.strip_prefix("object ")) {
+                    Some(target) => stack.push(Link { oid: target.trim().to_string(), expected: "commit", from }),
+                    None => report.errors.push(format!("{} names no object", from)),
+                }
+            },
+            _ => {},
+        }
+    }
+
+    let mut dangling: Vec<(&String, &str)> = present.iter()
+        .filter(|(oid, _)| !reachable.contains(*oid))
+        .filter_map(|(oid, kind)| kind.map(|kind| (oid, kind)))
+        .collect();
+    dangling.sort();
+    report.dangling = dangling.into_iter().map(|(oid, kind)| format!("dangling {} {}", kind, oid)).collect();
+
+    Ok(report)
+}
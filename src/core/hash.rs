@@ -1,12 +1,11 @@
 use std::path::Path;
-use sha2::{Sha256, Digest};
 use crate::core::io::write_object;
+use crate::core::object::Object;
 
+/// Computes the oid of `file_bytes` as a blob, storing the blob when run inside a repository.
 pub fn hash(file_bytes: &Vec<u8>) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(file_bytes);
-    let result = hasher.finalize();
-    let hex = format!("{:x}", result);
+    let blob = Object::Blob(file_bytes.to_vec());
+    let hex = blob.oid();
 
     let path = Path::new("./.nag/objects");
     match path.canonicalize() {
@@ -18,7 +17,7 @@ pub fn hash(file_bytes: &Vec<u8>) -> String {
             p
         }
     };
-    let _ = write_object(&file_bytes.to_vec(), &hex);
+    let _ = write_object(&blob);
 
     hex
 }
@@ -1,8 +1,10 @@
 use std::fs::{File, remove_file};
 use std::io::Write;
-use std::path::Path;
+use std::path::{ Path, PathBuf };
 use rand::random;
 use crate::core::repo::find_repo_root;
+use crate::core::object::Object;
+use crate::core::pack::{ has_packed_object, read_packed_object };
 
 pub fn read_file(path: &str) -> std::io::Result<Vec<u8>> {
     match std::fs::read(path) {
@@ -54,14 +56,98 @@ pub fn write_file(file: &Vec<u8>, path: &Path) -> std::io::Result<()>  {
     Ok(())
 }
 
-pub fn write_object(data: &Vec<u8>, oid: &String) -> std::io::Result<()> {
-    let obj_path = find_repo_root()?.join(".nag").join("objects").join(oid);
-    
-    if obj_path.exists() {
-        return Ok(());
+/// Removes the directories above `path` that are left empty, stopping at the first that is
+/// not (or at the repository root).
+pub fn remove_empty_parents(root: &Path, path: &Path) {
+    for dir in path.ancestors().skip(1).take_while(|dir| *dir != root) {
+        if std::fs::remove_dir(dir).is_err() {
+            break;
+        }
     }
+}
 
-    write_file(data, &obj_path)?;
+/// Where an object lives in `objects_dir`: fanned out as `ab/cdef…` by the first two hex
+/// characters, or flat as `abcdef…` for objects an older version wrote that way.
+pub fn object_path(objects_dir: &Path, oid: &str) -> PathBuf {
+    let flat_path = objects_dir.join(oid);
+    if oid.len() <= 2 || !oid.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return flat_path;
+    }
 
-    Ok(())
+    let fanned_path = objects_dir.join(&oid[..2]).join(&oid[2..]);
+    if !fanned_path.exists() && flat_path.is_file() {
+        return flat_path;
+    }
+    fanned_path
+}
+
+/// Whether `objects_dir` holds the object, either loose or in a pack.
+pub fn has_object(objects_dir: &Path, oid: &str) -> bool {
+    !oid.is_empty()
+        && (object_path(objects_dir, oid).is_file() || has_packed_object(objects_dir, oid).unwrap_or(false))
+}
+
+fn is_oid(name: &str) -> bool {
+    name.len() == 64 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Every loose object in `objects_dir`, fanned out or flat, with its path. The `empty`
+/// object, packs and temp files are not included.
+pub fn loose_oids(objects_dir: &Path) -> std::io::Result<Vec<(String, PathBuf)>> {
+    let mut loose = Vec::new();
+    for entry in std::fs::read_dir(objects_dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        if path.is_file() && is_oid(&name) {
+            loose.push((name, path));
+        } else if path.is_dir() && name.len() == 2 && name.bytes().all(|b| b.is_ascii_hexdigit()) {
+            for inner in std::fs::read_dir(&path)? {
+                let inner_path = inner?.path();
+                let oid = format!("{}{}", name, inner_path.file_name().unwrap().to_string_lossy());
+                if inner_path.is_file() && is_oid(&oid) {
+                    loose.push((oid, inner_path));
+                }
+            }
+        }
+    }
+    Ok(loose)
+}
+
+pub fn write_object(object: &Object) -> std::io::Result<String> {
+    let oid = object.oid();
+    let objects_dir = find_repo_root()?.join(".nag").join("objects");
+    let obj_path = object_path(&objects_dir, &oid);
+
+    if obj_path.exists() || has_packed_object(&objects_dir, &oid)? {
+        return Ok(oid);
+    }
+
+    write_file(&object.compress()?, &obj_path)?;
+
+    Ok(oid)
+}
+
+pub fn read_object(oid: &str) -> std::io::Result<Object> {
+    let objects_dir = find_repo_root()?.join(".nag").join("objects");
+    read_object_from(&objects_dir, oid)
+}
+
+/// Reads an object out of any objects directory, such as a remote's, looking in its
+/// packs when there is no loose copy.
+pub fn read_object_from(objects_dir: &Path, oid: &str) -> std::io::Result<Object> {
+    let obj_path = object_path(objects_dir, oid);
+    if !oid.is_empty() && !obj_path.is_file()
+        && let Some(object) = read_packed_object(objects_dir, oid)? {
+        return Ok(object);
+    }
+    if oid.is_empty() || !obj_path.is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Object {} not found", oid),
+        ));
+    }
+    Object::decompress(&read_file(&obj_path.to_string_lossy())?).map_err(|e| std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Object {} could not be decoded: {}", oid, e),
+    ))
 }
@@ -0,0 +1,324 @@
+use crate::core::repo::find_repo_root;
+use crate::core::io::{ read_file, write_file, write_object, object_path, loose_oids };
+use crate::core::object::Object;
+use crate::core::index::{ read_index, write_index, EntryType };
+use crate::core::tree::{ serialize_tree, TreeEntry };
+use crate::core::config::{ read_config, read_config_file, set_config_value };
+
+use std::path::{ Path, PathBuf };
+use std::collections::{ BTreeSet, HashMap };
+
+pub const FORMAT_KEY: &str = "core.repositoryformatversion";
+pub const FORMAT_VERSION: &str = "1";
+
+/// Converts a repository written before objects carried a type header, once.
+/// Repositories that already record the current format version are left alone.
+pub fn migrate_if_needed() -> std::io::Result<()> {
+    if find_repo_root().is_err() {
+        return Ok(());
+    }
+    if read_config(false)?.iter().any(|(key, value)| key == FORMAT_KEY && value == FORMAT_VERSION) {
+        return Ok(());
+    }
+
+    let (rewritten, missing) = migrate_objects()?;
+    set_config_value(FORMAT_KEY, FORMAT_VERSION, false)?;
+    if rewritten > 0 {
+        println!("Migrated {} objects to the typed object format", rewritten);
+    }
+    if !missing.is_empty() {
+        println!("Skipped {} missing objects; links to them were left as they were:", missing.len());
+        for oid in missing {
+            println!("  {}", oid);
+        }
+    }
+
+    Ok(())
+}
+
+/// Refuses another repository, such as a remote, that has not been migrated yet. Its
+/// objects cannot be read until a nag command has been run inside it.
+pub fn check_format(nag_dir: &Path) -> std::io::Result<()> {
+    if read_config_file(&nag_dir.join("config"))?.iter().any(|(key, value)| key == FORMAT_KEY && value == FORMAT_VERSION) {
+        return Ok(());
+    }
+    let repo = nag_dir.parent().unwrap_or(nag_dir);
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("'{}' uses the legacy object format. Run any nag command inside it to migrate it first", repo.display()),
+    ))
+}
+
+/// Rewrites every object reachable from the refs, HEAD, MERGE_HEAD and the index as a
+/// typed, compressed object, points those at the new oids and drops the legacy files.
+/// Legacy objects nothing refers to are converted too, typed by what their contents look
+/// like, so dangling work survives as dangling objects until `gc` prunes it.
+/// Objects that are already missing are skipped and anything pointing at them keeps the
+/// old oid. Returns how many objects were rewritten and the oids that were missing.
+pub fn migrate_objects() -> std::io::Result<(usize, Vec<String>)> {
+    let nag_dir = find_repo_root()?.join(".nag");
+    let mut migration = Migration {
+        objects_dir: nag_dir.join("objects"),
+        rewritten: HashMap::new(),
+        missing: BTreeSet::new(),
+    };
+
+    // Every new object is written before anything is repointed, so an interrupted run can simply be repeated
+    let mut ref_files = Vec::new();
+    collect_ref_files(&nag_dir.join("refs"), &mut ref_files)?;
+    let mut new_refs = Vec::new();
+    for path in ref_files {
+        let oid = read_oid(&path)?;
+        if !oid.is_empty() {
+            new_refs.push((path, migration.tagged(&oid)?));
+        }
+    }
+
+    let mut new_heads = Vec::new();
+    for name in ["HEAD", "MERGE_HEAD"] {
+        let path = nag_dir.join(name);
+        if !path.exists() {
+            continue;
+        }
+        let oid = read_oid(&path)?;
+        if !oid.is_empty() && !oid.starts_with("ref: ") {
+            new_heads.push((path, migration.commit(&oid)?));
+        }
+    }
+
+    let mut index = read_index()?;
+    for entry in index.iter_mut() {
+        for oid in entry.oids.iter_mut() {
+            if oid != "empty" {
+                *oid = migration.blob(oid)?;
+            }
+        }
+    }
+
+    let empty_path = migration.objects_dir.join("empty");
+    if empty_path.is_file() && migration.read_legacy("empty")?.is_some() {
+        write_file(&Object::Blob(Vec::new()).compress()?, &empty_path)?;
+    }
+
+    for (path, oid) in new_refs.into_iter().chain(new_heads) {
+        write_file(&oid.into_bytes(), &path)?;
+    }
+    write_index(&index)?;
+
+    let mut unreferenced = Vec::new();
+    for (oid, path) in loose_oids(&migration.objects_dir)? {
+        if let Some(bytes) = migration.read_legacy(&oid)? {
+            unreferenced.push((guess_type(&bytes), oid, path));
+        }
+    }
+    // Commits first, then trees, so whatever they contain is typed by them rather than guessed
+    unreferenced.sort_by_key(|(object_type, _, _)| match *object_type {
+        "commit" => 0,
+        "tree" => 1,
+        _ => 2,
+    });
+    for (object_type, oid, _) in &unreferenced {
+        migration.guessed(object_type, oid)?;
+    }
+    for (_, _, path) in unreferenced {
+        if path.is_file() {
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    let rewritten = migration.rewritten.iter().filter(|(old, new)| old != new).count();
+    Ok((rewritten, migration.missing.into_iter().collect()))
+}
+
+struct Migration {
+    objects_dir: PathBuf,
+    rewritten: HashMap<String, String>,
+    missing: BTreeSet<String>,
+}
+
+impl Migration {
+    // Raw bytes of a legacy object, or None when it is already in the typed format or missing
+    fn read_legacy(&mut self, oid: &str) -> std::io::Result<Option<Vec<u8>>> {
+        let path = object_path(&self.objects_dir, oid);
+        if !path.is_file() {
+            self.missing.insert(oid.to_string());
+            return Ok(None);
+        }
+        let bytes = read_file(&path.to_string_lossy())?;
+        if Object::decompress(&bytes).is_ok() {
+            return Ok(None);
+        }
+        Ok(Some(bytes))
+    }
+
+    fn blob(&mut self, oid: &str) -> std::io::Result<String> {
+        if let Some(new_oid) = self.rewritten.get(oid) {
+            return Ok(new_oid.clone());
+        }
+        let new_oid = match self.read_legacy(oid)? {
+            Some(bytes) => write_object(&Object::Blob(bytes))?,
+            None => oid.to_string(),
+        };
+        self.rewritten.insert(oid.to_string(), new_oid.clone());
+        Ok(new_oid)
+    }
+
+    fn tree(&mut self, oid: &str) -> std::io::Result<String> {
+        if let Some(new_oid) = self.rewritten.get(oid) {
+            return Ok(new_oid.clone());
+        }
+        let Some(bytes) = self.read_legacy(oid)? else {
+            self.rewritten.insert(oid.to_string(), oid.to_string());
+            return Ok(oid.to_string());
+        };
+
+        // Legacy trees are written back in canonical order
+        let mut entries = Vec::new();
+        for line in String::from_utf8_lossy(&bytes).lines() {
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() < 4 {
+                continue;
+            }
+            let entry_oid = if parts[1] == "040000" {
+                self.tree(parts[3])?
+            } else {
+                self.blob(parts[3])?
+            };
+            entries.push(TreeEntry {
+                entry_type: if parts[0] == "X" { EntryType::X } else { EntryType::C },
+                mode: parts[1].to_string(),
+                name: parts[2].to_string(),
+                oid: entry_oid,
+            });
+        }
+
+        let new_oid = write_object(&Object::Tree(serialize_tree(entries)))?;
+        self.rewritten.insert(oid.to_string(), new_oid.clone());
+        Ok(new_oid)
+    }
+
+    fn commit(&mut self, oid: &str) -> std::io::Result<String> {
+        // Parents are rewritten before their children without recursing down long histories
+        let mut stack: Vec<(String, bool)> = vec![(oid.to_string(), false)];
+        while let Some((current, expanded)) = stack.pop() {
+            if self.rewritten.contains_key(&current) {
+                continue;
+            }
+            let Some(bytes) = self.read_legacy(&current)? else {
+                self.rewritten.insert(current.clone(), current);
+                continue;
+            };
+            let commit_str = String::from_utf8_lossy(&bytes).to_string();
+
+            if !expanded {
+                stack.push((current, true));
+                let parents = commit_str.lines()
+                    .take_while(|line| !line.is_empty())
+                    .filter_map(|line| line.strip_prefix("parent "));
+                for parent in parents {
+                    if !self.rewritten.contains_key(parent.trim()) {
+                        stack.push((parent.trim().to_string(), false));
+                    }
+                }
+                continue;
+            }
+
+            let mut commit_buf = String::new();
+            let mut in_header = true;
+            for line in commit_str.split_inclusive('\n') {
+                if in_header {
+                    if let Some(tree_oid) = line.strip_prefix("tree ") {
+                        commit_buf.push_str(&format!("tree {}\n", self.tree(tree_oid.trim())?));
+                        continue;
+                    }
+                    if let Some(parent_oid) = line.strip_prefix("parent ") {
+                        commit_buf.push_str(&format!("parent {}\n", self.rewritten[parent_oid.trim()]));
+                        continue;
+                    }
+                    in_header = line != "\n";
+                }
+                commit_buf.push_str(line);
+            }
+
+            let new_oid = write_object(&Object::Commit(commit_buf.into_bytes()))?;
+            self.rewritten.insert(current, new_oid);
+        }
+
+        Ok(self.rewritten[oid].clone())
+    }
+
+    // An unreferenced object is converted as the type it looks like, or as a blob when that fails
+    fn guessed(&mut self, object_type: &str, oid: &str) -> std::io::Result<String> {
+        if let Some(new_oid) = self.rewritten.get(oid) {
+            return Ok(new_oid.clone());
+        }
+        let converted = match object_type {
+            "commit" => self.commit(oid),
+            "tree" => self.tree(oid),
+            _ => self.blob(oid),
+        };
+        match converted {
+            Ok(new_oid) => Ok(new_oid),
+            Err(_) => self.blob(oid),
+        }
+    }
+
+    // Tag refs may point straight at a commit or at an annotated tag object
+    fn tagged(&mut self, oid: &str) -> std::io::Result<String> {
+        if let Some(new_oid) = self.rewritten.get(oid) {
+            return Ok(new_oid.clone());
+        }
+        let Some(bytes) = self.read_legacy(oid)? else {
+            return Ok(oid.to_string());
+        };
+        let tag_str = String::from_utf8_lossy(&bytes).to_string();
+        let Some((first_line, rest)) = tag_str.split_once('\n') else {
+            return self.commit(oid);
+        };
+        let Some(target) = first_line.strip_prefix("object ") else {
+            return self.commit(oid);
+        };
+
+        let new_target = self.commit(target.trim())?;
+        let tag_buf = format!("object {}\n{}", new_target, rest);
+        let new_oid = write_object(&Object::Tag(tag_buf.into_bytes()))?;
+        self.rewritten.insert(oid.to_string(), new_oid.clone());
+        Ok(new_oid)
+    }
+}
+
+// Legacy objects carry no type, so one is inferred from the layout of the contents
+fn guess_type(bytes: &[u8]) -> &'static str {
+    let is_oid = |oid: &str| oid.len() == 64 && oid.bytes().all(|b| b.is_ascii_hexdigit());
+    let contents = String::from_utf8_lossy(bytes);
+    if contents.lines().next().and_then(|line| line.strip_prefix("tree ")).is_some_and(is_oid) {
+        return "commit";
+    }
+    let tree_line = |line: &str| {
+        let parts: Vec<&str> = line.split('\t').collect();
+        parts.len() == 4 && (parts[0] == "C" || parts[0] == "X") && (is_oid(parts[3]) || parts[3] == "empty")
+    };
+    if !contents.is_empty() && contents.lines().all(tree_line) {
+        return "tree";
+    }
+    "blob"
+}
+
+fn collect_ref_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_ref_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_oid(path: &Path) -> std::io::Result<String> {
+    Ok(String::from_utf8_lossy(&read_file(&path.to_string_lossy())?).trim().to_string())
+}
@@ -1,4 +1,5 @@
 pub mod io;
+pub mod object;
 pub mod repo;
 pub mod tree;
 pub mod index;
@@ -6,3 +7,10 @@ pub mod hash;
 pub mod diff;
 pub mod refs;
 pub mod ignore;
+pub mod config;
+pub mod commit;
+pub mod ancestry;
+pub mod migrate;
+pub mod pack;
+pub mod fsck;
+pub mod reflog;
@@ -0,0 +1,97 @@
+use std::io::{ Read, Write };
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use sha2::{ Sha256, Digest };
+
+/// A stored object. On disk every object is `<type> <len>\0<payload>` compressed
+/// with zlib, and its oid is the SHA-256 of that uncompressed header plus payload.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    Blob(Vec<u8>),
+    Tree(Vec<u8>),
+    Commit(Vec<u8>),
+    Tag(Vec<u8>),
+}
+
+impl Object {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Object::Blob(_) => "blob",
+            Object::Tree(_) => "tree",
+            Object::Commit(_) => "commit",
+            Object::Tag(_) => "tag",
+        }
+    }
+
+    pub fn data(&self) -> &Vec<u8> {
+        match self {
+            Object::Blob(data) | Object::Tree(data) | Object::Commit(data) | Object::Tag(data) => data,
+        }
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        match self {
+            Object::Blob(data) | Object::Tree(data) | Object::Commit(data) | Object::Tag(data) => data,
+        }
+    }
+
+    /// Returns the payload, or an error if the object is not of the expected type.
+    pub fn into_payload(self, kind: &str) -> std::io::Result<Vec<u8>> {
+        if self.kind() != kind {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Expected a {} object but found a {}", kind, self.kind()),
+            ));
+        }
+        Ok(self.into_data())
+    }
+
+    /// Header plus payload, the bytes the oid is computed over.
+    pub fn encode(&self) -> Vec<u8> {
+        let data = self.data();
+        let mut bytes = format!("{} {}\0", self.kind(), data.len()).into_bytes();
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    pub fn oid(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.encode());
+        format!("{:x}", hasher.finalize())
+    }
+
+    pub fn compress(&self) -> std::io::Result<Vec<u8>> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&self.encode())?;
+        encoder.finish()
+    }
+
+    pub fn decompress(bytes: &[u8]) -> std::io::Result<Object> {
+        let mut encoded = Vec::new();
+        ZlibDecoder::new(bytes).read_to_end(&mut encoded)?;
+        Object::decode(&encoded)
+    }
+
+    pub fn decode(bytes: &[u8]) -> std::io::Result<Object> {
+        let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg.to_string());
+
+        let nul = bytes.iter().position(|b| *b == 0).ok_or_else(|| invalid("Object header is not terminated"))?;
+        let header = std::str::from_utf8(&bytes[..nul]).map_err(|_| invalid("Object header is not valid UTF-8"))?;
+        let (kind, len) = header.split_once(' ').ok_or_else(|| invalid("Object header is missing its length"))?;
+        let len: usize = len.parse().map_err(|_| invalid("Object length is not a number"))?;
+
+        let data = bytes[nul + 1..].to_vec();
+        if data.len() != len {
+            return Err(invalid("Object length does not match its header"));
+        }
+
+        match kind {
+            "blob" => Ok(Object::Blob(data)),
+            "tree" => Ok(Object::Tree(data)),
+            "commit" => Ok(Object::Commit(data)),
+            "tag" => Ok(Object::Tag(data)),
+            _ => Err(invalid(&format!("Unknown object type '{}'", kind))),
+        }
+    }
+}
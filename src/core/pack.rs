@@ -0,0 +1,403 @@
+use std::fs::File;
+use std::io::{ Read, Seek, SeekFrom, Write };
+use std::path::{ Path, PathBuf };
+use std::collections::{ HashMap, HashSet };
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use sha2::{ Sha256, Digest };
+
+use crate::core::io::write_file;
+use crate::core::object::Object;
+
+/*
+A pack is `pack-<checksum>.pack` plus `pack-<checksum>.idx` in `objects/pack/`.
+
+The pack starts with `NAGPACK` and a format byte, then a big-endian u32 object count.
+Each entry is a type byte, for deltas the 64-character oid of the base, a big-endian
+u64 length and that many bytes of zlib data: the payload itself, or a delta that
+rebuilds the payload from the base's payload.
+
+The index starts with `NAGIDX` and a format byte, then a big-endian u32 count and
+fixed-width records of a 64-character oid and a big-endian u64 pack offset, sorted by
+oid so a lookup can binary search without reading the whole file.
+*/
+
+const PACK_MAGIC: &[u8; 8] = b"NAGPACK\x01";
+const IDX_MAGIC: &[u8; 7] = b"NAGIDX\x01";
+const IDX_RECORD: u64 = 72;
+const IDX_HEADER: u64 = 11;
+
+const TYPE_BLOB: u8 = 1;
+const TYPE_TREE: u8 = 2;
+const TYPE_COMMIT: u8 = 3;
+const TYPE_TAG: u8 = 4;
+const TYPE_DELTA: u8 = 5;
+
+const DELTA_COPY: u8 = 0;
+const DELTA_INSERT: u8 = 1;
+const DELTA_BLOCK: usize = 16;
+
+/// How many recent objects of the same type are tried as delta bases.
+pub const DELTA_WINDOW: usize = 10;
+/// Longest chain of deltas a reader may have to follow to rebuild an object.
+pub const MAX_DELTA_DEPTH: usize = 10;
+
+fn invalid(msg: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg.to_string())
+}
+
+pub fn pack_dir(objects_dir: &Path) -> PathBuf {
+    objects_dir.join("pack")
+}
+
+/// Index files of every pack in `objects_dir`.
+pub fn list_packs(objects_dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let dir = pack_dir(objects_dir);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut idx_paths = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "idx") {
+            idx_paths.push(path);
+        }
+    }
+    idx_paths.sort();
+    Ok(idx_paths)
+}
+
+pub fn has_packed_object(objects_dir: &Path, oid: &str) -> std::io::Result<bool> {
+    for idx_path in list_packs(objects_dir)? {
+        if find_offset(&idx_path, oid)?.is_some() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+pub fn read_packed_object(objects_dir: &Path, oid: &str) -> std::io::Result<Option<Object>> {
+    for idx_path in list_packs(objects_dir)? {
+        if let Some(offset) = find_offset(&idx_path, oid)? {
+            let mut pack = File::open(idx_path.with_extension("pack"))?;
+            return Ok(Some(read_entry(&mut pack, &idx_path, offset, 0)?));
+        }
+    }
+    Ok(None)
+}
+
+/// Every oid stored in the packs of `objects_dir`.
+pub fn packed_oids(objects_dir: &Path) -> std::io::Result<Vec<String>> {
+    let mut oids = Vec::new();
+    for idx_path in list_packs(objects_dir)? {
+        oids.extend(pack_oids(&idx_path)?);
+    }
+    Ok(oids)
+}
+
+/// The oids one pack holds, in index order.
+pub fn pack_oids(idx_path: &Path) -> std::io::Result<Vec<String>> {
+    let mut idx = File::open(idx_path)?;
+    let count = read_idx_header(&mut idx)?;
+    let mut oids = Vec::with_capacity(count as usize);
+    for position in 0..count {
+        oids.push(read_idx_record(&mut idx, position)?.0);
+    }
+    Ok(oids)
+}
+
+fn read_idx_header(idx: &mut File) -> std::io::Result<u64> {
+    let mut header = [0u8; IDX_HEADER as usize];
+    idx.read_exact(&mut header)?;
+    if &header[..7] != IDX_MAGIC {
+        return Err(invalid("Pack index has an unknown format"));
+    }
+    Ok(u32::from_be_bytes([header[7], header[8], header[9], header[10]]) as u64)
+}
+
+fn read_idx_record(idx: &mut File, position: u64) -> std::io::Result<(String, u64)> {
+    let mut record = [0u8; IDX_RECORD as usize];
+    idx.seek(SeekFrom::Start(IDX_HEADER + position * IDX_RECORD))?;
+    idx.read_exact(&mut record)?;
+    let oid = String::from_utf8_lossy(&record[..64]).to_string();
+    let offset = u64::from_be_bytes(record[64..].try_into().unwrap());
+    Ok((oid, offset))
+}
+
+fn find_offset(idx_path: &Path, oid: &str) -> std::io::Result<Option<u64>> {
+    let mut idx = File::open(idx_path)?;
+    let count = read_idx_header(&mut idx)?;
+
+    let (mut low, mut high) = (0, count);
+    while low < high {
+        let middle = (low + high) / 2;
+        let (found, offset) = read_idx_record(&mut idx, middle)?;
+        match found.as_str().cmp(oid) {
+            std::cmp::Ordering::Equal => return Ok(Some(offset)),
+            std::cmp::Ordering::Less => low = middle + 1,
+            std::cmp::Ordering::Greater => high = middle,
+        }
+    }
+    Ok(None)
+}
+
+fn read_entry(pack: &mut File, idx_path: &Path, offset: u64, depth: usize) -> std::io::Result<Object> {
+    if depth > MAX_DELTA_DEPTH {
+        return Err(invalid("Pack delta chain is too deep"));
+    }
+
+    pack.seek(SeekFrom::Start(offset))?;
+    let mut kind = [0u8; 1];
+    pack.read_exact(&mut kind)?;
+
+    let mut base_oid = None;
+    if kind[0] == TYPE_DELTA {
+        let mut oid = [0u8; 64];
+        pack.read_exact(&mut oid)?;
+        base_oid = Some(String::from_utf8_lossy(&oid).to_string());
+    }
+
+    let mut len = [0u8; 8];
+    pack.read_exact(&mut len)?;
+    let mut compressed = vec![0u8; u64::from_be_bytes(len) as usize];
+    pack.read_exact(&mut compressed)?;
+    let mut data = Vec::new();
+    ZlibDecoder::new(&compressed[..]).read_to_end(&mut data)?;
+
+    match kind[0] {
+        TYPE_BLOB => Ok(Object::Blob(data)),
+        TYPE_TREE => Ok(Object::Tree(data)),
+        TYPE_COMMIT => Ok(Object::Commit(data)),
+        TYPE_TAG => Ok(Object::Tag(data)),
+        TYPE_DELTA => {
+            let base_oid = base_oid.unwrap();
+            let base_offset = find_offset(idx_path, &base_oid)?
+                .ok_or_else(|| invalid(&format!("Delta base {} is not in the pack", base_oid)))?;
+            let base = read_entry(pack, idx_path, base_offset, depth + 1)?;
+            let rebuilt = apply_delta(base.data(), &data)?;
+            Ok(match base {
+                Object::Blob(_) => Object::Blob(rebuilt),
+                Object::Tree(_) => Object::Tree(rebuilt),
+                Object::Commit(_) => Object::Commit(rebuilt),
+                Object::Tag(_) => Object::Tag(rebuilt),
+            })
+        },
+        _ => Err(invalid("Pack entry has an unknown type")),
+    }
+}
+
+/// An object headed for a pack, with a name hint (usually a file name) that groups similar objects.
+pub struct PackInput {
+    pub object: Object,
+    pub hint: String,
+}
+
+/// Writes one pack holding every given object, deltified against similar objects of the
+/// same type, and returns the index path. Objects are sorted by type, hint and size so
+/// likely bases sit near each other, then each is compared with the previous
+/// `DELTA_WINDOW` objects and stored as a delta when that is under half its size.
+pub fn write_pack(objects_dir: &Path, inputs: Vec<PackInput>) -> std::io::Result<PathBuf> {
+    let mut seen = HashSet::new();
+    let mut inputs: Vec<PackInput> = inputs.into_iter().filter(|input| seen.insert(input.object.oid())).collect();
+    inputs.sort_by(|a, b| {
+        a.object.kind().cmp(b.object.kind())
+            .then(a.hint.cmp(&b.hint))
+            .then(b.object.data().len().cmp(&a.object.data().len()))
+    });
+
+    let oids: Vec<String> = inputs.iter().map(|input| input.object.oid()).collect();
+    let mut depths: HashMap<usize, usize> = HashMap::new();
+
+    let mut pack_bytes = PACK_MAGIC.to_vec();
+    pack_bytes.extend_from_slice(&(inputs.len() as u32).to_be_bytes());
+    let mut records: Vec<(String, u64)> = Vec::new();
+
+    for (position, input) in inputs.iter().enumerate() {
+        let data = input.object.data();
+        let mut best: Option<(usize, Vec<u8>)> = None;
+        let window_start = position.saturating_sub(DELTA_WINDOW);
+        for (candidate, base) in inputs[window_start..position].iter().enumerate() {
+            let candidate = window_start + candidate;
+            let base = &base.object;
+            if base.kind() != input.object.kind() || depths.get(&candidate).copied().unwrap_or(0) >= MAX_DELTA_DEPTH {
+                continue;
+            }
+            let delta = create_delta(base.data(), data);
+            if delta.len() < data.len() / 2 && best.as_ref().is_none_or(|(_, b)| delta.len() < b.len()) {
+                best = Some((candidate, delta));
+            }
+        }
+
+        records.push((oids[position].clone(), pack_bytes.len() as u64));
+        let body = match best {
+            Some((base, delta)) => {
+                depths.insert(position, depths.get(&base).copied().unwrap_or(0) + 1);
+                pack_bytes.push(TYPE_DELTA);
+                pack_bytes.extend_from_slice(oids[base].as_bytes());
+                delta
+            },
+            None => {
+                let kind = match input.object {
+                    Object::Blob(_) => TYPE_BLOB,
+                    Object::Tree(_) => TYPE_TREE,
+                    Object::Commit(_) => TYPE_COMMIT,
+                    Object::Tag(_) => TYPE_TAG,
+                };
+                pack_bytes.push(kind);
+                data.clone()
+            },
+        };
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&body)?;
+        let compressed = encoder.finish()?;
+        pack_bytes.extend_from_slice(&(compressed.len() as u64).to_be_bytes());
+        pack_bytes.extend_from_slice(&compressed);
+    }
+
+    records.sort();
+    let mut idx_bytes = IDX_MAGIC.to_vec();
+    idx_bytes.extend_from_slice(&(records.len() as u32).to_be_bytes());
+    for (oid, offset) in &records {
+        idx_bytes.extend_from_slice(oid.as_bytes());
+        idx_bytes.extend_from_slice(&offset.to_be_bytes());
+    }
+
+    let checksum = format!("{:x}", Sha256::digest(&pack_bytes));
+    let pack_path = pack_dir(objects_dir).join(format!("pack-{}.pack", checksum));
+    let idx_path = pack_path.with_extension("idx");
+
+    // The index goes in last so readers never find an index without its pack
+    write_file(&pack_bytes, &pack_path)?;
+    write_file(&idx_bytes, &idx_path)?;
+
+    Ok(idx_path)
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> std::io::Result<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*pos).ok_or_else(|| invalid("Delta ends inside a number"))?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+        if shift > 63 {
+            return Err(invalid("Delta number is too large"));
+        }
+    }
+}
+
+fn flush_insert(delta: &mut Vec<u8>, pending: &mut Vec<u8>) {
+    if pending.is_empty() {
+        return;
+    }
+    delta.push(DELTA_INSERT);
+    write_varint(delta, pending.len() as u64);
+    delta.append(pending);
+}
+
+/// Encodes `target` as copies of byte ranges from `base` plus inserted literal bytes.
+/// Blocks of the base are indexed at fixed offsets and matches are grown in both directions.
+pub fn create_delta(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut delta = Vec::new();
+    write_varint(&mut delta, base.len() as u64);
+    write_varint(&mut delta, target.len() as u64);
+
+    let mut blocks: HashMap<&[u8], usize> = HashMap::new();
+    let mut start = 0;
+    while start + DELTA_BLOCK <= base.len() {
+        blocks.entry(&base[start..start + DELTA_BLOCK]).or_insert(start);
+        start += DELTA_BLOCK;
+    }
+
+    let mut pending = Vec::new();
+    let mut pos = 0;
+    while pos < target.len() {
+        let matched = if pos + DELTA_BLOCK <= target.len() {
+            blocks.get(&target[pos..pos + DELTA_BLOCK]).copied()
+        } else {
+            None
+        };
+        let Some(mut base_start) = matched else {
+            pending.push(target[pos]);
+            pos += 1;
+            continue;
+        };
+
+        let mut len = DELTA_BLOCK;
+        while base_start + len < base.len() && pos + len < target.len() && base[base_start + len] == target[pos + len] {
+            len += 1;
+        }
+        // Reclaim literal bytes just before the match that the base also has
+        let mut target_start = pos;
+        while !pending.is_empty() && base_start > 0 && base[base_start - 1] == target[target_start - 1] {
+            pending.pop();
+            base_start -= 1;
+            target_start -= 1;
+            len += 1;
+        }
+
+        flush_insert(&mut delta, &mut pending);
+        delta.push(DELTA_COPY);
+        write_varint(&mut delta, base_start as u64);
+        write_varint(&mut delta, len as u64);
+        pos = target_start + len;
+    }
+    flush_insert(&mut delta, &mut pending);
+
+    delta
+}
+
+pub fn apply_delta(base: &[u8], delta: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut pos = 0;
+    let base_len = read_varint(delta, &mut pos)? as usize;
+    let target_len = read_varint(delta, &mut pos)? as usize;
+    if base_len != base.len() {
+        return Err(invalid("Delta was made against a different base"));
+    }
+
+    let mut target = Vec::with_capacity(target_len);
+    while pos < delta.len() {
+        let op = delta[pos];
+        pos += 1;
+        match op {
+            DELTA_COPY => {
+                let start = read_varint(delta, &mut pos)? as usize;
+                let len = read_varint(delta, &mut pos)? as usize;
+                let end = start.checked_add(len).filter(|end| *end <= base.len())
+                    .ok_or_else(|| invalid("Delta copies past the end of its base"))?;
+                target.extend_from_slice(&base[start..end]);
+            },
+            DELTA_INSERT => {
+                let len = read_varint(delta, &mut pos)? as usize;
+                let end = pos.checked_add(len).filter(|end| *end <= delta.len())
+                    .ok_or_else(|| invalid("Delta inserts past its own end"))?;
+                target.extend_from_slice(&delta[pos..end]);
+                pos = end;
+            },
+            _ => return Err(invalid("Delta has an unknown instruction")),
+        }
+    }
+
+    if target.len() != target_len {
+        return Err(invalid("Delta produced the wrong size"));
+    }
+    Ok(target)
+}
@@ -0,0 +1,154 @@
+use crate::core::repo::find_repo_root;
+use crate::core::io::{ read_file, write_file };
+use crate::core::commit::Signature;
+use crate::core::config::signature;
+
+use std::path::{ Path, PathBuf };
+
+/// Stands in for the old value of a ref that did not exist before.
+pub const ZERO_OID: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// One line of a ref's log: `<old> <new> <signature>\t<message>`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ReflogEntry {
+    pub old: String,
+    pub new: String,
+    pub who: Signature,
+    pub message: String,
+}
+
+impl ReflogEntry {
+    fn format(&self) -> String {
+        format!(
+            "{} {} {} <{}> {} {}\t{}\n",
+            self.old, self.new, self.who.name, self.who.email, self.who.timestamp, self.who.timezone, self.message,
+        )
+    }
+
+    fn parse(line: &str) -> Option<ReflogEntry> {
+        let (head, message) = line.split_once('\t').unwrap_or((line, ""));
+        let (old, rest) = head.split_once(' ')?;
+        let (new, who) = rest.split_once(' ')?;
+        Some(ReflogEntry {
+            old: old.to_string(),
+            new: new.to_string(),
+            who: Signature::parse(who)?,
+            message: message.to_string(),
+        })
+    }
+}
+
+/// Where the log of a ref such as `refs/stash` lives: `.nag/logs/<ref>`.
+pub fn reflog_path(ref_name: &str) -> std::io::Result<PathBuf> {
+    Ok(find_repo_root()?.join(".nag").join("logs").join(ref_name))
+}
+
+/// Entries of a ref's log, oldest first. A ref without a log has no entries.
+pub fn read_reflog(ref_name: &str) -> std::io::Result<Vec<ReflogEntry>> {
+    read_log_file(&reflog_path(ref_name)?)
+}
+
+fn read_log_file(path: &Path) -> std::io::Result<Vec<ReflogEntry>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let contents = read_file(&path.to_string_lossy())?;
+    Ok(String::from_utf8_lossy(&contents).lines().filter_map(ReflogEntry::parse).collect())
+}
+
+pub fn write_reflog(ref_name: &str, entries: &[ReflogEntry]) -> std::io::Result<()> {
+    write_log_file(&reflog_path(ref_name)?, entries)
+}
+
+fn write_log_file(path: &Path, entries: &[ReflogEntry]) -> std::io::Result<()> {
+    if entries.is_empty() {
+        if path.is_file() {
+            std::fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+    let buf = entries.iter().map(ReflogEntry::format).collect::<String>();
+    write_file(&buf.into_bytes(), path)
+}
+
+/// Records that `ref_name` moved from `old` (empty when it is new) to `new`.
+pub fn append_reflog(ref_name: &str, old: &str, new: &str, message: &str) -> std::io::Result<()> {
+    append_reflog_in(&find_repo_root()?.join(".nag"), ref_name, old, new, message)
+}
+
+/// Records a ref move in another repository's logs, such as a remote's branch on push.
+pub fn append_reflog_in(nag_dir: &Path, ref_name: &str, old: &str, new: &str, message: &str) -> std::io::Result<()> {
+    let path = nag_dir.join("logs").join(ref_name);
+    let who = Signature::parse(&signature()?).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "Could not build a signature for the reflog",
+    ))?;
+    let mut entries = read_log_file(&path)?;
+    entries.push(ReflogEntry {
+        old: if old.is_empty() { ZERO_OID.to_string() } else { old.to_string() },
+        new: new.to_string(),
+        who,
+        // Messages are single lines so every entry stays one line of the log
+        message: message.lines().next().unwrap_or("").to_string(),
+    });
+    write_log_file(&path, &entries)
+}
+
+/// Drops the entries of every log recorded before `cutoff` (seconds since the epoch) and
+/// returns how many went. The newest entry of each log always stays, since it records
+/// where the ref is now (and, for `refs/stash`, the top of the stack).
+pub fn expire_reflogs(cutoff: i64) -> std::io::Result<usize> {
+    let logs_dir = find_repo_root()?.join(".nag").join("logs");
+    let mut log_names = Vec::new();
+    collect_logs(&logs_dir, "", &mut log_names)?;
+
+    let mut expired = 0;
+    for name in log_names {
+        let entries = read_reflog(&name)?;
+        let newest = entries.len().saturating_sub(1);
+        let kept: Vec<ReflogEntry> = entries.iter().enumerate()
+            .filter(|(i, entry)| *i == newest || entry.who.timestamp >= cutoff)
+            .map(|(_, entry)| entry.clone())
+            .collect();
+        if kept.len() < entries.len() {
+            expired += entries.len() - kept.len();
+            write_reflog(&name, &kept)?;
+        }
+    }
+    Ok(expired)
+}
+
+/// Every commit a reflog entry moved a ref to, with the name of its log, so that gc and
+/// fsck treat older ref positions (and older stashes) as still in use. An entry's old
+/// value is the previous entry's new one, so once that has expired it no longer counts.
+pub fn reflog_oids() -> std::io::Result<Vec<(String, String)>> {
+    let logs_dir = find_repo_root()?.join(".nag").join("logs");
+    let mut log_names = Vec::new();
+    collect_logs(&logs_dir, "", &mut log_names)?;
+
+    let mut oids = Vec::new();
+    for name in log_names {
+        for entry in read_reflog(&name)? {
+            if entry.new != ZERO_OID {
+                oids.push((name.clone(), entry.new));
+            }
+        }
+    }
+    Ok(oids)
+}
+
+fn collect_logs(dir: &Path, prefix: &str, names: &mut Vec<String>) -> std::io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = format!("{}{}", prefix, path.file_name().unwrap().to_string_lossy());
+        if path.is_dir() {
+            collect_logs(&path, &format!("{}/", name), names)?;
+        } else {
+            names.push(name);
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,10 @@
 use crate::core::repo::find_repo_root;
-use crate::core::io::{ read_file, write_file };
+use crate::core::io::{ read_file, read_object, write_file, has_object, loose_oids };
+use crate::core::object::Object;
+use crate::core::pack::packed_oids;
+use crate::core::commit::{ read_commit, parse_date };
+use crate::core::config::get_config_value;
+use crate::core::reflog::{ append_reflog, read_reflog, reflog_path };
 
 use std::path::{ Path, PathBuf };
 use std::fs::read_dir;
@@ -43,14 +48,17 @@ pub fn read_ref(ref_name: &str) -> std::io::Result<String> {
     Ok(trimmed)
 }
 
-pub fn update_ref(name: &str, oid: &str) -> std::io::Result<()> {
+/// Points a ref at `oid` and records the move in its reflog (and in HEAD's, when HEAD is
+/// on that branch). Tags are not logged.
+pub fn update_ref(name: &str, oid: &str, message: &str) -> std::io::Result<()> {
     let name_full = if !name.starts_with("refs/") {
         format!("refs/heads/{}", name)
     } else {
         name.to_string()
     };
 
-    let full_path = find_repo_root()?.join(".nag").join(name_full);
+    let full_path = find_repo_root()?.join(".nag").join(&name_full);
+    let old_oid = read_ref(&name_full).unwrap_or_default();
 
     if let Some(parent) = full_path.parent() {
         std::fs::create_dir_all(parent)?;
@@ -58,9 +66,31 @@ pub fn update_ref(name: &str, oid: &str) -> std::io::Result<()> {
 
     write_file(&oid.as_bytes().to_vec(), &full_path)?;
 
+    if !oid.is_empty() && !name_full.starts_with("refs/tags/") {
+        append_reflog(&name_full, &old_oid, oid, message)?;
+        if head_target().ok().flatten().as_deref() == Some(name_full.as_str()) {
+            append_reflog("HEAD", &old_oid, oid, message)?;
+        }
+    }
+
     Ok(())
 }
 
+/// Advances whatever HEAD is on: the current branch, or HEAD itself when detached.
+pub fn update_head(oid: &str, message: &str) -> std::io::Result<()> {
+    match head_target()? {
+        Some(ref_name) => update_ref(&ref_name, oid, message),
+        None => set_head_detached(oid, message),
+    }
+}
+
+// The ref HEAD points at, or None when it is detached
+fn head_target() -> std::io::Result<Option<String>> {
+    let head_contents = read_file(&find_repo_root()?.join(".nag").join("HEAD").to_string_lossy())?;
+    let head_str = String::from_utf8_lossy(&head_contents);
+    Ok(head_str.trim().strip_prefix("ref: ").map(|target| target.to_string()))
+}
+
 pub fn get_ref_path(ref_name: &str) -> std::io::Result<PathBuf> {
     let ref_name_full = if !ref_name.starts_with("refs/") {
         format!("refs/heads/{}", ref_name)
@@ -73,36 +103,347 @@ pub fn get_ref_path(ref_name: &str) -> std::io::Result<PathBuf> {
     Ok(ref_path)
 }
 
-pub fn set_head_ref(branch: &str) -> std::io::Result<()> {
-    if read_ref(branch).is_err() {
+pub fn set_head_ref(branch: &str, message: &str) -> std::io::Result<()> {
+    let Ok(new_oid) = read_ref(branch) else {
         return Err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
             format!("Branch '{}' not found", branch),
         ));
-    }
+    };
 
+    let old_oid = resolve_head().map(|head| head.1).unwrap_or_default();
     let nag_dir = find_repo_root()?.join(".nag");
     let head_path = nag_dir.join("HEAD");
     let ref_line = format!("ref: refs/heads/{}\n", branch);
     write_file(&ref_line.as_bytes().to_vec(), &head_path)?;
 
+    if !new_oid.is_empty() {
+        append_reflog("HEAD", &old_oid, &new_oid, message)?;
+    }
+
     Ok(())
 }
 
-pub fn set_head_detached(oid: &str) -> std::io::Result<()> {
+pub fn set_head_detached(oid: &str, message: &str) -> std::io::Result<()> {
     let nag_dir = find_repo_root()?.join(".nag");
     let head_path = nag_dir.join("HEAD");
-    let object_path = nag_dir.join("objects").join(oid);
 
-    if read_file(&object_path.as_os_str().to_string_lossy().to_string()).is_err() {
+    if !has_object(&nag_dir.join("objects"), oid) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
-            format!("Commit object '{}' not found", object_path.display()),
+            format!("Commit object '{}' not found", oid),
         ));
     }
 
+    let old_oid = resolve_head().map(|head| head.1).unwrap_or_default();
     write_file(&oid.as_bytes().to_vec(), &head_path)?;
+    append_reflog("HEAD", &old_oid, oid, message)?;
+
+    Ok(())
+}
+
+/// Resolves a revision expression to a commit oid. Annotated tags are peeled to their
+/// commit, and anything else that is not a commit (such as `rev:path` naming a file) is refused.
+pub fn resolve_revision(spec: &str) -> std::io::Result<String> {
+    let oid = peel_tag(&resolve_object(spec)?)?;
+    match read_object(&oid)? {
+        Object::Commit(_) => Ok(oid),
+        other => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Revision '{}' names a {}, not a commit", spec, other.kind()),
+        )),
+    }
+}
+
+/// Resolves a revision expression to the oid of any object:
+/// - `HEAD` (or `@`), a branch, tag or remote-tracking name, a full `refs/...` name,
+///   or a unique prefix of at least four characters of an object id;
+/// - `<name>@{upstream}` (or `@{u}`), the remote-tracking branch a branch follows;
+/// - `<ref>@{N}`, where the ref was N moves ago, and `<ref>@{<date>}`, where it was at
+///   that time, both read from the ref's log (a bare `@{...}` means the current branch);
+/// - followed by any number of `~N` (Nth first-parent ancestor) and `^N` (Nth parent, `^0` the commit itself);
+/// - `<rev>:<path>`, the blob or tree at `path` in that commit.
+pub fn resolve_object(spec: &str) -> std::io::Result<String> {
+    // Dates inside `@{...}` may contain `:`, so only a colon outside braces starts a path
+    if let Some(colon) = outside_braces(spec, |c| c == ':') {
+        let (rev, path) = (&spec[..colon], &spec[colon + 1..]);
+        let commit = read_commit(&resolve_revision(rev)?)?;
+        return tree_entry_oid(&commit.tree, path).map_err(|_| std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Path '{}' does not exist in '{}'", path, rev),
+        ));
+    }
+
+    // The base name runs up to the first `~` or `^` outside an `@{...}` suffix
+    let base_end = outside_braces(spec, |c| c == '~' || c == '^').unwrap_or(spec.len());
+
+    let mut oid = resolve_base(&spec[..base_end])?;
+    let mut rest = &spec[base_end..];
+    while let Some(op) = rest.chars().next() {
+        // Anything else, possibly a multi-byte character, is rejected before slicing past it
+        if op != '~' && op != '^' {
+            return Err(unknown_revision(spec));
+        }
+        let digits = rest[1..].chars().take_while(|c| c.is_ascii_digit()).count();
+        let count = if digits == 0 {
+            1
+        } else {
+            rest[1..1 + digits].parse::<usize>().map_err(|_| unknown_revision(spec))?
+        };
+        rest = &rest[1 + digits..];
 
+        oid = peel_tag(&oid)?;
+        let parents = |oid: &str| -> std::io::Result<Vec<String>> { Ok(read_commit(oid)?.parents) };
+        match op {
+            '~' => {
+                for _ in 0..count {
+                    oid = parents(&oid)?.into_iter().next().ok_or_else(|| no_parent(spec))?;
+                }
+            },
+            '^' if count > 0 => {
+                oid = parents(&oid)?.into_iter().nth(count - 1).ok_or_else(|| no_parent(spec))?;
+            },
+            '^' => {
+                read_commit(&oid)?;
+            },
+            _ => return Err(unknown_revision(spec)),
+        }
+    }
+
+    Ok(oid)
+}
+
+// Position of the first character matching `pred` that is not inside `{...}`
+fn outside_braces(spec: &str, pred: impl Fn(char) -> bool) -> Option<usize> {
+    let mut depth = 0;
+    spec.char_indices().find(|(_, c)| {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {},
+        }
+        depth == 0 && pred(*c)
+    }).map(|(i, _)| i)
+}
+
+fn unknown_revision(spec: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("Unknown revision '{}'", spec),
+    )
+}
+
+fn no_parent(spec: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("Revision '{}' goes past the first commit", spec),
+    )
+}
+
+// Annotated tags point at a tag object rather than the commit itself
+fn peel_tag(oid: &str) -> std::io::Result<String> {
+    if let Ok(Object::Tag(tag_bytes)) = read_object(oid) {
+        let object_str = String::from_utf8_lossy(&tag_bytes);
+        if let Some(target) = object_str.lines().next().and_then(|l| l.strip_prefix("object ")) {
+            return Ok(target.trim().to_string());
+        }
+    }
+    Ok(oid.to_string())
+}
+
+fn resolve_base(base: &str) -> std::io::Result<String> {
+    let Some((name, suffix)) = base.split_once("@{") else {
+        return resolve_name(base);
+    };
+    let name = if name.is_empty() { "HEAD" } else { name };
+    match suffix.strip_suffix('}') {
+        Some("upstream") | Some("u") => resolve_name(&upstream_ref(name)?),
+        Some(selector) => {
+            // A bare `@{N}` follows the current branch rather than HEAD itself
+            let log_name = match (base.starts_with("@{"), resolve_head()) {
+                (true, Ok((Some(current), _))) => format!("refs/heads/{}", current),
+                _ => log_ref_name(name)?,
+            };
+            if !selector.is_empty() && selector.bytes().all(|b| b.is_ascii_digit()) {
+                let n = selector.parse::<usize>().map_err(|_| unknown_revision(base))?;
+                reflog_nth(&log_name, name, n)
+            } else {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_err(std::io::Error::other)?
+                    .as_secs() as i64;
+                let when = parse_date(selector, now).ok_or_else(|| unknown_revision(base))?;
+                reflog_at(&log_name, name, when)
+            }
+        },
+        None => Err(unknown_revision(base)),
+    }
+}
+
+/// The ref whose log `name` means: `HEAD`, a full `refs/...` name, or a branch,
+/// remote-tracking branch or other ref under `refs/` such as `stash`.
+pub fn log_ref_name(name: &str) -> std::io::Result<String> {
+    if name == "HEAD" || name == "@" {
+        return Ok("HEAD".to_string());
+    }
+    if name.starts_with("refs/") {
+        return Ok(name.to_string());
+    }
+    for candidate in [format!("refs/heads/{}", name), format!("refs/remotes/{}", name), format!("refs/{}", name)] {
+        if get_ref_path(&candidate)?.is_file() || reflog_path(&candidate)?.is_file() {
+            return Ok(candidate);
+        }
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("Unknown ref '{}'", name),
+    ))
+}
+
+// Where the ref was `n` moves ago; `@{0}` is where it is now
+fn reflog_nth(log_name: &str, name: &str, n: usize) -> std::io::Result<String> {
+    let entries = read_reflog(log_name)?;
+    if n >= entries.len() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Log for '{}' only has {} entries", name, entries.len()),
+        ));
+    }
+    Ok(entries[entries.len() - 1 - n].new.clone())
+}
+
+// Where the ref was at `when`: the newest move made at or before that time
+fn reflog_at(log_name: &str, name: &str, when: i64) -> std::io::Result<String> {
+    let entries = read_reflog(log_name)?;
+    match entries.iter().rev().find(|entry| entry.who.timestamp <= when) {
+        Some(entry) => Ok(entry.new.clone()),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            match entries.first() {
+                Some(first) => format!("Log for '{}' only goes back to {}", name, first.who.date_string()),
+                None => format!("Log for '{}' is empty", name),
+            },
+        )),
+    }
+}
+
+fn resolve_name(name: &str) -> std::io::Result<String> {
+    if name == "HEAD" || name == "@" {
+        let head_oid = resolve_head().map(|head| head.1).unwrap_or_default();
+        if head_oid.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "HEAD does not point at a commit yet",
+            ));
+        }
+        return Ok(head_oid);
+    }
+
+    for candidate in [name.to_string(), format!("refs/tags/{}", name), format!("refs/remotes/{}", name)] {
+        if let Ok(oid) = read_ref(&candidate) && !oid.is_empty() {
+            return Ok(oid);
+        }
+    }
+
+    let objects_dir = find_repo_root()?.join(".nag").join("objects");
+    if has_object(&objects_dir, name) {
+        return Ok(name.to_string());
+    }
+    if name.len() >= 4 && name.bytes().all(|b| b.is_ascii_hexdigit()) {
+        let prefix = name.to_lowercase();
+        let mut matches: Vec<String> = loose_oids(&objects_dir)?.into_iter()
+            .map(|(oid, _)| oid)
+            .chain(packed_oids(&objects_dir)?)
+            .filter(|oid| oid.starts_with(&prefix))
+            .collect();
+        matches.sort();
+        matches.dedup();
+        match matches.len() {
+            0 => {},
+            1 => return Ok(matches.remove(0)),
+            n => return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Short object id '{}' is ambiguous ({} objects match)", name, n),
+            )),
+        }
+    }
+
+    Err(unknown_revision(name))
+}
+
+/// The remote-tracking ref a branch follows: `refs/remotes/<remote>/<branch>`, where the
+/// remote comes from `branch.<name>.remote` (default `origin`) and the remote branch from
+/// `branch.<name>.merge` (default the same name).
+pub fn upstream_ref(branch: &str) -> std::io::Result<String> {
+    let branch = if branch == "HEAD" || branch == "@" {
+        let (Some(current), _) = resolve_head()? else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Detached HEAD has no upstream",
+            ));
+        };
+        current
+    } else {
+        branch.to_string()
+    };
+    if read_ref(&branch).is_err() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Branch '{}' not found", branch),
+        ));
+    }
+
+    let remote = get_config_value(&format!("branch.{}.remote", branch))?.unwrap_or("origin".to_string());
+    let merge = get_config_value(&format!("branch.{}.merge", branch))?.unwrap_or(branch.clone());
+    let upstream = format!("refs/remotes/{}/{}", remote, merge.strip_prefix("refs/heads/").unwrap_or(&merge));
+    if read_ref(&upstream).is_err() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Branch '{}' has no upstream ('{}' does not exist)", branch, upstream),
+        ));
+    }
+    Ok(upstream)
+}
+
+// Walks a tree down `path`, one component at a time
+fn tree_entry_oid(tree_oid: &str, path: &str) -> std::io::Result<String> {
+    let mut oid = tree_oid.to_string();
+    for component in path.split('/').filter(|c| !c.is_empty()) {
+        let tree = read_object(&oid)?.into_payload("tree")?;
+        let tree_str = String::from_utf8_lossy(&tree).to_string();
+        oid = tree_str.lines()
+            .map(|line| line.split('\t').collect::<Vec<&str>>())
+            .find(|parts| parts.len() >= 4 && parts[2] == component)
+            .map(|parts| parts[3].to_string())
+            .ok_or_else(|| std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No entry '{}' in tree {}", component, oid),
+            ))?;
+    }
+    Ok(oid)
+}
+
+pub fn read_merge_head() -> std::io::Result<Option<String>> {
+    let merge_head_path = find_repo_root()?.join(".nag").join("MERGE_HEAD");
+    if !merge_head_path.exists() {
+        return Ok(None);
+    }
+    let contents = read_file(&merge_head_path.to_string_lossy())?;
+    let oid = String::from_utf8_lossy(&contents).trim().to_string();
+    Ok(if oid.is_empty() { None } else { Some(oid) })
+}
+
+pub fn write_merge_head(oid: &str) -> std::io::Result<()> {
+    let merge_head_path = find_repo_root()?.join(".nag").join("MERGE_HEAD");
+    write_file(&oid.as_bytes().to_vec(), &merge_head_path)?;
+    Ok(())
+}
+
+pub fn clear_merge_head() -> std::io::Result<()> {
+    let merge_head_path = find_repo_root()?.join(".nag").join("MERGE_HEAD");
+    if merge_head_path.exists() {
+        std::fs::remove_file(merge_head_path)?;
+    }
     Ok(())
 }
 
@@ -14,3 +14,35 @@ pub fn find_repo_root() -> std::io::Result<std::path::PathBuf> {
         "Not a NAG repository",
     ))
 }
+
+/// Turns a path given on the command line (relative to the current directory, or
+/// absolute) into the `/`-separated path the index uses, relative to the repository root.
+pub fn repo_relative_path(path: &str) -> std::io::Result<String> {
+    let root = find_repo_root()?;
+    let joined = std::env::current_dir()?.join(path);
+
+    // Resolved lexically, since the path may not exist (yet)
+    let mut parts: Vec<String> = Vec::new();
+    for component in joined.components() {
+        match component {
+            std::path::Component::CurDir => {},
+            std::path::Component::ParentDir => { parts.pop(); },
+            std::path::Component::Normal(name) => parts.push(name.to_string_lossy().to_string()),
+            _ => parts.clear(),
+        }
+    }
+    let root_parts: Vec<String> = root.components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+
+    if !parts.starts_with(&root_parts) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("'{}' is outside the repository", path),
+        ));
+    }
+    Ok(parts[root_parts.len()..].join("/"))
+}
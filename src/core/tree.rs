@@ -5,7 +5,8 @@ use std::os::unix::fs::PermissionsExt;
 use std::collections::HashMap;
 
 use crate::core::hash::hash;
-use crate::core::io::{ write_object, read_file };
+use crate::core::io::{ write_object, read_object, read_file, has_object };
+use crate::core::object::Object;
 use crate::core::repo::find_repo_root;
 use crate::core::index::{ IndexEntry, EntryType };
 
@@ -22,6 +23,64 @@ fn format_entry(entry_type: &EntryType, perms: &str, name: &str, oid: &str) -> S
     entry
 }
 
+/// One line of a tree object: `<type>\t<mode>\t<name>\t<oid>`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct TreeEntry {
+    pub entry_type: EntryType,
+    pub mode: String,
+    pub name: String,
+    pub oid: String,
+}
+
+// Entries are ordered by the bytes of their names, with subtrees compared as if their
+// name ended in '/', so a flattened tree comes out in the same order as sorted paths
+fn sort_key(mode: &str, name: &str) -> Vec<u8> {
+    let mut key = name.as_bytes().to_vec();
+    if mode == "040000" {
+        key.push(b'/');
+    }
+    key
+}
+
+/// The canonical bytes of a tree: its entries sorted by name, one per line. Every
+/// tree is written through this, so the same content always gets the same oid.
+pub fn serialize_tree(mut entries: Vec<TreeEntry>) -> Vec<u8> {
+    entries.sort_by_key(|entry| sort_key(&entry.mode, &entry.name));
+    entries.iter()
+        .map(|entry| format_entry(&entry.entry_type, &entry.mode, &entry.name, &entry.oid))
+        .collect::<String>()
+        .into_bytes()
+}
+
+/// Whether a tree's payload is exactly what `serialize_tree` would write for its entries:
+/// well-formed lines in strictly increasing name order, with no name repeated.
+pub fn is_canonical_tree(payload: &[u8]) -> bool {
+    let Ok(tree_str) = std::str::from_utf8(payload) else {
+        return false;
+    };
+    let mut entries = Vec::new();
+    for line in tree_str.lines() {
+        let parts: Vec<&str> = line.split('\t').collect();
+        let entry_type = match parts[0] {
+            "C" => EntryType::C,
+            "X" => EntryType::X,
+            _ => return false,
+        };
+        if parts.len() != 4 || parts[2].is_empty() || parts[2].contains('/') {
+            return false;
+        }
+        entries.push(TreeEntry {
+            entry_type,
+            mode: parts[1].to_string(),
+            name: parts[2].to_string(),
+            oid: parts[3].to_string(),
+        });
+    }
+    let increasing = entries.windows(2)
+        .all(|pair| sort_key(&pair[0].mode, &pair[0].name) < sort_key(&pair[1].mode, &pair[1].name));
+    increasing && serialize_tree(entries) == payload
+}
+
 fn get_perms(entry: &DirEntry) -> &str {
     let metadata = entry.metadata();
     match metadata {
@@ -49,7 +108,7 @@ fn get_perms(entry: &DirEntry) -> &str {
 }
 
 pub fn write_tree(root_path: &PathBuf) -> std::io::Result<String> {
-    let mut string_buf = String::new();
+    let mut entries = Vec::new();
     let paths = fs::read_dir(root_path);
     for path in paths? {
         if let Ok(p) = path {
@@ -63,14 +122,22 @@ pub fn write_tree(root_path: &PathBuf) -> std::io::Result<String> {
             if p_type.is_file() {
                 let data = read_file(&p.path().display().to_string())?;
                 let blob = hash(&data);
-                let entry = format_entry(&EntryType::C, perms, &name_str, &blob);
-                string_buf.push_str(&entry);
+                entries.push(TreeEntry {
+                    entry_type: EntryType::C,
+                    mode: perms.to_string(),
+                    name: name_str.to_string(),
+                    oid: blob,
+                });
             } else if p_type.is_dir() {
                 let dir_path = write_tree(&p.path());
                 match dir_path {
                     Ok(sub_dirs) => {
-                        let entry = format_entry(&EntryType::C, perms, &name_str, &sub_dirs);
-                        string_buf.push_str(&entry);
+                        entries.push(TreeEntry {
+                            entry_type: EntryType::C,
+                            mode: perms.to_string(),
+                            name: name_str.to_string(),
+                            oid: sub_dirs,
+                        });
                     },
                     Err(e) => {
                         println!("Error: {:?}", e);
@@ -79,9 +146,7 @@ pub fn write_tree(root_path: &PathBuf) -> std::io::Result<String> {
             }
         }
     }
-    let buffer_bytes = string_buf.into_bytes();
-    let tree_hash = hash(&buffer_bytes);
-    write_object(&buffer_bytes, &tree_hash)?;
+    let tree_hash = write_object(&Object::Tree(serialize_tree(entries)))?;
     Ok(tree_hash)
 }
 
@@ -116,35 +181,40 @@ pub fn write_tree_from_index(index: &Vec<IndexEntry>) -> std::io::Result<String>
         }
     }
 
-    let mut str_buf = String::new();
+    let mut entries = Vec::new();
 
     if let Some(files) = groups.remove("") {
         let repo_root = find_repo_root()?; // project root
         let objects_dir = repo_root.join(".nag").join("objects");
 
         for item in files {
-            let obj_path = objects_dir.join(&item.oids[0]);
-            if !obj_path.exists() {
+            if !has_object(&objects_dir, &item.oids[0]) {
                 return Err(std::io::Error::new(
                     std::io::ErrorKind::NotFound,
                     format!("missing blob object for {}", &item.path),
                 ));
             }
 
-            let entry = format_entry(&item.entry_type, item.mode.as_str(), &item.path, &item.oids[0]);
-            str_buf.push_str(&entry);
+            entries.push(TreeEntry {
+                entry_type: item.entry_type,
+                mode: item.mode,
+                name: item.path,
+                oid: item.oids[0].clone(),
+            });
         }
     }
 
-    for group in groups.iter() {
-        let sub_dir = write_tree_from_index(group.1)?;
-        let entry = format_entry(&EntryType::C, "040000", &group.0, &sub_dir);
-        str_buf.push_str(&entry);
+    for (name, group) in groups {
+        let sub_dir = write_tree_from_index(&group)?;
+        entries.push(TreeEntry {
+            entry_type: EntryType::C,
+            mode: "040000".to_string(),
+            name,
+            oid: sub_dir,
+        });
     }
 
-    let buf_bytes: Vec<u8> = str_buf.as_bytes().to_vec();
-    let tree_hash = hash(&buf_bytes);
-    write_object(&buf_bytes, &tree_hash)?;
+    let tree_hash = write_object(&Object::Tree(serialize_tree(entries)))?;
     Ok(tree_hash)
 }
 
@@ -157,8 +227,7 @@ pub fn read_tree_to_index(tree_oid: &str) -> std::io::Result<Vec<IndexEntry>> {
 }
 
 fn read_t_to_i_walk(tree_oid: &str, entries: &mut Vec<IndexEntry>) -> std::io::Result<()> {
-    let tree_str = find_repo_root()?.join(".nag").join("objects").join(tree_oid);
-    let tree_bytes = read_file(&tree_str.to_string_lossy())?;
+    let tree_bytes = read_object(tree_oid)?.into_payload("tree")?;
     let tree_str = String::from_utf8_lossy(&tree_bytes);
 
     for line in tree_str.lines() {
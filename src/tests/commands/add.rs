@@ -3,6 +3,7 @@ use std::path::Path;
 use tempfile::TempDir;
 
 use crate::core::index::read_index;
+use crate::core::io::object_path;
 use crate::commands::add::add;
 use crate::commands::init::init;
 
@@ -38,7 +39,7 @@ fn add_single_file_creates_blob_and_index_entry() {
     assert_eq!(e.oids.len(), 1);
 
     let oid = &e.oids[0];
-    let blob_path = tmp.path().join(".nag/objects").join(oid);
+    let blob_path = object_path(&tmp.path().join(".nag/objects"), oid);
     assert!(blob_path.exists());
 }
 
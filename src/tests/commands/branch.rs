@@ -78,6 +78,31 @@ fn branch_can_be_created_from_specific_oid() {
     assert_eq!(retro_oid, first_commit_oid);
 }
 
+#[test]
+fn branch_resolves_revision_expressions_and_rejects_unknown_ones() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let file = root.join("file.txt");
+    commit_helper(&file, "v1", "first commit");
+    let main_path = find_repo_root().unwrap().join(".nag/refs/heads/main");
+    let first_commit_oid = String::from_utf8_lossy(&read_file(&main_path.to_string_lossy()).unwrap()).trim().to_string();
+    commit_helper(&file, "v2", "second commit");
+
+    branch("previous".to_string(), Some("HEAD~1".to_string())).unwrap();
+    branch("short".to_string(), Some(first_commit_oid[..8].to_string())).unwrap();
+
+    let read_branch = |name: &str| {
+        let path = find_repo_root().unwrap().join(".nag/refs/heads").join(name);
+        String::from_utf8_lossy(&read_file(&path.to_string_lossy()).unwrap()).trim().to_string()
+    };
+    assert_eq!(read_branch("previous"), first_commit_oid);
+    assert_eq!(read_branch("short"), first_commit_oid);
+
+    assert!(branch("bogus".to_string(), Some("not-a-commit".to_string())).is_err());
+    assert!(!find_repo_root().unwrap().join(".nag/refs/heads/bogus").exists());
+}
+
 #[test]
 fn branch_fails_on_duplicate_name() {
     let tmp = TempDir::new().unwrap();
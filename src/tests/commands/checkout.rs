@@ -7,11 +7,14 @@ use crate::commands::{
     add::add,
     commit::commit,
     branch::branch,
-    checkout::checkout,
+    checkout::{ checkout, checkout_new_branch },
     status::status,
+    tag::tag,
 };
 use crate::core::repo::find_repo_root;
 use crate::core::io::read_file;
+use crate::core::refs::{ resolve_head, update_ref };
+use crate::core::commit::read_commit;
 
 // Helper: create and cd into initialized repo
 fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
@@ -37,12 +40,12 @@ fn checkout_switches_branches_cleanly() {
     commit_helper(&main_file, "main branch data", "initial commit");
 
     branch("feature".to_string(), None).unwrap();
-    checkout("feature".to_string()).unwrap();
+    checkout("feature".to_string(), false).unwrap();
 
     let new_file = root.join("feature.txt");
     commit_helper(&new_file, "feature branch data", "feature commit");
 
-    checkout("main".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
     assert!(main_file.exists());
     assert!(!new_file.exists());
 }
@@ -55,12 +58,15 @@ fn checkout_refuses_to_overwrite_dirty_working_dir() {
     let file_path = root.join("restore_me.txt");
     commit_helper(&file_path, "old data", "initial commit");
 
-    // make uncommitted edit
-    fs::write(&file_path, "new data").unwrap();
+    // the target branch changes the file, so the uncommitted edit cannot be carried over
     branch("restore".to_string(), None).unwrap();
+    checkout("restore".to_string(), false).unwrap();
+    commit_helper(&file_path, "restore data", "restore commit");
+    checkout("main".to_string(), false).unwrap();
+    fs::write(&file_path, "new data").unwrap();
 
     // this should fail, not succeed
-    let result = checkout("restore".to_string());
+    let result = checkout("restore".to_string(), false);
     assert!(result.is_err());
     let err = result.unwrap_err().to_string();
     assert!(err.contains("un-committed changes"));
@@ -74,7 +80,7 @@ fn checkout_fails_on_nonexistent_branch() {
     let file_path = root.join("dummy.txt");
     commit_helper(&file_path, "some data", "commit");
 
-    let result = checkout("notabranch".to_string());
+    let result = checkout("notabranch".to_string(), false);
     assert!(result.is_err());
 }
 
@@ -86,12 +92,16 @@ fn checkout_refuses_with_uncommitted_changes() {
     let file_path = root.join("warn.txt");
     commit_helper(&file_path, "clean", "commit");
 
+    branch("other".to_string(), None).unwrap();
+    commit_helper(&file_path, "changed on main", "second commit");
     fs::write(&file_path, "dirty change").unwrap();
 
-    let result = checkout("main".to_string());
+    let result = checkout("other".to_string(), false);
     assert!(result.is_err());
     let err_str = result.unwrap_err().to_string();
     assert!(err_str.contains("un-committed changes"));
+    assert!(err_str.contains("warn.txt"));
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "dirty change");
 }
 
 #[test]
@@ -103,7 +113,7 @@ fn checkout_updates_head_to_target_branch() {
     commit_helper(&file_path, "abc", "initial");
 
     branch("dev".to_string(), None).unwrap();
-    checkout("dev".to_string()).unwrap();
+    checkout("dev".to_string(), false).unwrap();
 
     let head_path = find_repo_root().unwrap().join(".nag/HEAD");
     let bytes = read_file(&head_path.to_string_lossy()).unwrap();
@@ -120,10 +130,285 @@ fn checkout_produces_clean_status_after_switch() {
     commit_helper(&main_file, "stable", "init");
 
     branch("feature".to_string(), None).unwrap();
-    checkout("feature".to_string()).unwrap();
+    checkout("feature".to_string(), false).unwrap();
 
     let out = status(false).unwrap();
     assert!(!out.contains("Untracked"));
     assert!(!out.contains("Modified"));
     assert!(!out.contains("Deleted"));
 }
+
+fn head_contents(root: &Path) -> String {
+    String::from_utf8_lossy(&read_file(&root.join(".nag/HEAD").to_string_lossy()).unwrap()).trim().to_string()
+}
+
+fn main_oid(root: &Path) -> String {
+    String::from_utf8_lossy(&read_file(&root.join(".nag/refs/heads/main").to_string_lossy()).unwrap()).trim().to_string()
+}
+
+#[test]
+fn checkout_of_a_commit_detaches_head() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let file = root.join("file.txt");
+    commit_helper(&file, "v1", "first");
+    let first = main_oid(&root);
+    commit_helper(&file, "v2", "second");
+
+    checkout(first[..8].to_string(), false).unwrap();
+
+    assert_eq!(head_contents(&root), first);
+    assert_eq!(resolve_head().unwrap(), (None, first.clone()));
+    assert_eq!(fs::read_to_string(&file).unwrap(), "v1");
+    assert!(status(false).unwrap().is_empty());
+}
+
+#[test]
+fn checkout_of_an_annotated_tag_detaches_at_its_commit() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let file = root.join("file.txt");
+    commit_helper(&file, "v1", "first");
+    let first = main_oid(&root);
+    tag(Some("v1".to_string()), None, Some("release one".to_string())).unwrap();
+    commit_helper(&file, "v2", "second");
+
+    checkout("v1".to_string(), false).unwrap();
+
+    assert_eq!(head_contents(&root), first);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "v1");
+}
+
+#[test]
+fn checkout_of_a_remote_tracking_branch_detaches_head() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let file = root.join("file.txt");
+    commit_helper(&file, "v1", "first");
+    let first = main_oid(&root);
+    update_ref("refs/remotes/origin/main", &first, "").unwrap();
+    commit_helper(&file, "v2", "second");
+
+    checkout("origin/main".to_string(), false).unwrap();
+
+    assert_eq!(head_contents(&root), first);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "v1");
+}
+
+#[test]
+fn commits_on_a_detached_head_move_head_only() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let file = root.join("file.txt");
+    commit_helper(&file, "v1", "first");
+    let first = main_oid(&root);
+    commit_helper(&file, "v2", "second");
+    let second = main_oid(&root);
+
+    checkout("HEAD~1".to_string(), false).unwrap();
+    commit_helper(&root.join("other.txt"), "detached work", "detached");
+
+    let detached = head_contents(&root);
+    assert_ne!(detached, first);
+    assert_eq!(main_oid(&root), second);
+    assert_eq!(read_commit(&detached).unwrap().parents, vec![first]);
+
+    branch("rescued".to_string(), None).unwrap();
+    checkout("rescued".to_string(), false).unwrap();
+    assert_eq!(head_contents(&root), "ref: refs/heads/rescued");
+    assert_eq!(fs::read_to_string(root.join("other.txt")).unwrap(), "detached work");
+}
+
+#[test]
+fn checkout_keeps_untracked_and_ignored_files() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    commit_helper(&root.join(".nagignore"), "build\n", "ignore build output");
+    branch("feature".to_string(), None).unwrap();
+    commit_helper(&root.join("main.txt"), "main only", "main work");
+
+    fs::create_dir_all(root.join("build")).unwrap();
+    fs::write(root.join("build/output.bin"), "artifact").unwrap();
+    fs::write(root.join("notes.txt"), "scratch notes").unwrap();
+
+    checkout("feature".to_string(), false).unwrap();
+
+    assert!(!root.join("main.txt").exists());
+    assert_eq!(fs::read_to_string(root.join("build/output.bin")).unwrap(), "artifact");
+    assert_eq!(fs::read_to_string(root.join("notes.txt")).unwrap(), "scratch notes");
+    assert_eq!(fs::read_to_string(root.join(".nagignore")).unwrap(), "build\n");
+}
+
+#[test]
+fn checkout_leaves_unchanged_files_untouched() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let shared = root.join("shared.txt");
+    commit_helper(&shared, "same on both", "shared");
+    branch("feature".to_string(), None).unwrap();
+    commit_helper(&root.join("main.txt"), "main only", "main work");
+
+    let old_time = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+    fs::File::options().write(true).open(&shared).unwrap().set_modified(old_time).unwrap();
+
+    checkout("feature".to_string(), false).unwrap();
+
+    assert_eq!(fs::metadata(&shared).unwrap().modified().unwrap(), old_time);
+}
+
+#[test]
+fn checkout_aborts_when_an_untracked_file_would_be_overwritten() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    commit_helper(&root.join("base.txt"), "base", "base");
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&root.join("clash.txt"), "from feature", "feature adds clash");
+    checkout("main".to_string(), false).unwrap();
+    assert!(!root.join("clash.txt").exists());
+
+    fs::write(root.join("clash.txt"), "my local work").unwrap();
+
+    let err = checkout("feature".to_string(), false).unwrap_err();
+    assert!(err.to_string().contains("clash.txt"), "unexpected error: {}", err);
+    assert_eq!(fs::read_to_string(root.join("clash.txt")).unwrap(), "my local work");
+    assert_eq!(String::from_utf8_lossy(&read_file(&root.join(".nag/HEAD").to_string_lossy()).unwrap()).trim(), "ref: refs/heads/main");
+
+    // An untracked copy identical to the target is not in the way
+    fs::write(root.join("clash.txt"), "from feature").unwrap();
+    checkout("feature".to_string(), false).unwrap();
+}
+
+#[test]
+fn checkout_removes_directories_it_empties() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    commit_helper(&root.join("base.txt"), "base", "base");
+    branch("feature".to_string(), None).unwrap();
+    fs::create_dir_all(root.join("only/on/main")).unwrap();
+    commit_helper(&root.join("only/on/main/file.txt"), "nested", "nested");
+    fs::create_dir_all(root.join("kept")).unwrap();
+    commit_helper(&root.join("kept/tracked.txt"), "tracked", "kept dir");
+    fs::write(root.join("kept/untracked.txt"), "untracked").unwrap();
+
+    checkout("feature".to_string(), false).unwrap();
+
+    assert!(!root.join("only").exists());
+    assert!(!root.join("kept/tracked.txt").exists());
+    assert!(root.join("kept/untracked.txt").exists());
+}
+
+#[test]
+fn checkout_carries_over_changes_to_files_both_commits_agree_on() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    commit_helper(&root.join("shared.txt"), "shared", "shared");
+    branch("feature".to_string(), None).unwrap();
+    commit_helper(&root.join("main.txt"), "main only", "main work");
+
+    fs::write(root.join("shared.txt"), "edited locally").unwrap();
+    fs::write(root.join("staged.txt"), "new and staged").unwrap();
+    add(&root.join("staged.txt")).unwrap();
+
+    checkout("feature".to_string(), false).unwrap();
+
+    assert!(!root.join("main.txt").exists());
+    assert_eq!(fs::read_to_string(root.join("shared.txt")).unwrap(), "edited locally");
+    assert_eq!(fs::read_to_string(root.join("staged.txt")).unwrap(), "new and staged");
+    let status_str = status(false).unwrap();
+    assert!(status_str.contains("shared.txt"));
+    assert!(status_str.contains("staged.txt"));
+
+    // Committing on the new branch picks up the staged file
+    commit("on feature".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
+    assert!(!root.join("staged.txt").exists());
+}
+
+#[test]
+fn checkout_merge_folds_local_edits_into_the_target_version() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let file = root.join("lines.txt");
+    commit_helper(&file, "one\ntwo\nthree\n", "base");
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&file, "one\ntwo\nthree\nfour\n", "feature appends");
+    checkout("main".to_string(), false).unwrap();
+
+    fs::write(&file, "ONE\ntwo\nthree\n").unwrap();
+    assert!(checkout("feature".to_string(), false).is_err());
+
+    checkout("feature".to_string(), true).unwrap();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "ONE\ntwo\nthree\nfour\n");
+    assert_eq!(String::from_utf8_lossy(&read_file(&root.join(".nag/HEAD").to_string_lossy()).unwrap()).trim(), "ref: refs/heads/feature");
+    assert!(status(false).unwrap().contains("lines.txt"), "merged edits stay unstaged");
+}
+
+#[test]
+fn checkout_merge_leaves_conflicts_to_resolve() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let file = root.join("lines.txt");
+    commit_helper(&file, "one\n", "base");
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&file, "feature\n", "feature edit");
+    checkout("main".to_string(), false).unwrap();
+    fs::write(&file, "local\n").unwrap();
+
+    checkout("feature".to_string(), true).unwrap();
+
+    let contents = fs::read_to_string(&file).unwrap();
+    assert!(contents.contains("<<<<<<<") && contents.contains("local") && contents.contains("feature"));
+    assert!(commit("not yet".to_string()).is_err(), "conflicts block commits until resolved");
+    assert!(checkout("main".to_string(), false).is_err());
+}
+
+#[test]
+fn checkout_b_creates_and_switches_to_a_branch() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let file = root.join("file.txt");
+    commit_helper(&file, "v1", "first");
+    commit_helper(&file, "v2", "second");
+
+    checkout_new_branch("topic".to_string(), None, false).unwrap();
+    assert_eq!(String::from_utf8_lossy(&read_file(&root.join(".nag/HEAD").to_string_lossy()).unwrap()).trim(), "ref: refs/heads/topic");
+    assert_eq!(fs::read_to_string(&file).unwrap(), "v2");
+
+    checkout_new_branch("old".to_string(), Some("HEAD~1".to_string()), false).unwrap();
+    assert_eq!(String::from_utf8_lossy(&read_file(&root.join(".nag/HEAD").to_string_lossy()).unwrap()).trim(), "ref: refs/heads/old");
+    assert_eq!(fs::read_to_string(&file).unwrap(), "v1");
+
+    assert!(checkout_new_branch("old".to_string(), None, false).is_err(), "existing branches are not recreated");
+}
+
+#[test]
+fn checkout_b_drops_the_branch_when_the_switch_fails() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let file = root.join("file.txt");
+    commit_helper(&file, "v1", "first");
+    commit_helper(&file, "v2", "second");
+    fs::write(&file, "dirty").unwrap();
+
+    assert!(checkout_new_branch("old".to_string(), Some("HEAD~1".to_string()), false).is_err());
+    assert!(!root.join(".nag/refs/heads/old").exists());
+    assert!(!root.join(".nag/logs/refs/heads/old").exists());
+    assert_eq!(fs::read_to_string(&file).unwrap(), "dirty");
+}
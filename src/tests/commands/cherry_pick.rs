@@ -0,0 +1,222 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    branch::branch,
+    checkout::checkout,
+    merge::merge,
+    resolve::resolve,
+    cherry_pick::{ cherry_pick, cherry_pick_continue, cherry_pick_abort },
+};
+use crate::core::refs::{ resolve_head, read_ref };
+use crate::core::commit::read_commit;
+use crate::core::config::set_config_value;
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) -> String {
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+    resolve_head().unwrap().1
+}
+
+#[test]
+fn cherry_pick_applies_a_commit_with_a_trailer() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    set_config_value("user.name", "Ada", false).unwrap();
+    set_config_value("user.timezone", "+0100", false).unwrap();
+    let picked = commit_helper(&root.join("b.txt"), "b", "add b");
+
+    checkout("main".to_string(), false).unwrap();
+    set_config_value("user.name", "Bob", false).unwrap();
+    set_config_value("user.timezone", "-0500", false).unwrap();
+    let main_before = read_ref("main").unwrap();
+    cherry_pick(vec!["feature".to_string()]).unwrap();
+
+    let head = read_commit(&read_ref("main").unwrap()).unwrap();
+    assert_eq!(head.parents, vec![main_before]);
+    assert_eq!(head.message.trim_end(), format!("add b\n\n(cherry picked from commit {})", picked));
+    // The original author keeps the credit; whoever picked it is the committer
+    assert_eq!(head.author, read_commit(&picked).unwrap().author);
+    let committer = head.committer.unwrap();
+    assert_eq!(committer.name, "Bob");
+    assert_eq!(committer.timezone, "-0500");
+    assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "b");
+    assert!(!root.join(".nag/sequencer").exists());
+}
+
+#[test]
+fn cherry_pick_applies_several_commits_in_order() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    let first = commit_helper(&root.join("b.txt"), "b1", "b one");
+    let second = commit_helper(&root.join("b.txt"), "b2", "b two");
+
+    checkout("main".to_string(), false).unwrap();
+    commit_helper(&root.join("c.txt"), "c", "add c");
+    cherry_pick(vec![first, second]).unwrap();
+
+    let head = read_commit(&read_ref("main").unwrap()).unwrap();
+    assert!(head.message.starts_with("b two"));
+    assert!(read_commit(&head.parents[0]).unwrap().message.starts_with("b one"));
+    assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "b2");
+    assert_eq!(fs::read_to_string(root.join("c.txt")).unwrap(), "c");
+}
+
+#[test]
+fn cherry_pick_deletes_files_the_commit_removed() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    fs::create_dir(root.join("dir")).unwrap();
+    commit_helper(&root.join("dir/gone.txt"), "gone", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    fs::remove_file(root.join("dir/gone.txt")).unwrap();
+    add(&root.join("dir/gone.txt")).unwrap();
+    commit("remove gone".to_string()).unwrap();
+
+    checkout("main".to_string(), false).unwrap();
+    commit_helper(&root.join("a.txt"), "a", "add a");
+    cherry_pick(vec!["feature".to_string()]).unwrap();
+
+    assert!(!root.join("dir").exists());
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "a");
+}
+
+#[test]
+fn conflicted_cherry_pick_is_finished_with_continue() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("c.txt");
+    commit_helper(&file, "base\n", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    set_config_value("user.name", "Ada", false).unwrap();
+    let conflicting = commit_helper(&file, "feature\n", "feature edit");
+    let clean = commit_helper(&root.join("d.txt"), "d", "add d");
+
+    checkout("main".to_string(), false).unwrap();
+    set_config_value("user.name", "Bob", false).unwrap();
+    let main_before = commit_helper(&file, "main\n", "main edit");
+
+    let err = cherry_pick(vec![conflicting.clone(), clean]).unwrap_err();
+    assert!(err.to_string().contains("Could not apply"));
+    assert_eq!(read_ref("main").unwrap(), main_before);
+    assert!(!root.join("d.txt").exists());
+
+    assert!(cherry_pick_continue().is_err(), "conflicts must be resolved first");
+    assert!(cherry_pick(vec!["feature".to_string()]).is_err(), "a second pick is refused");
+
+    fs::write(&file, "resolved\n").unwrap();
+    resolve("c.txt").unwrap();
+    cherry_pick_continue().unwrap();
+
+    let head = read_commit(&read_ref("main").unwrap()).unwrap();
+    assert!(head.message.starts_with("add d"));
+    let resolved = read_commit(&head.parents[0]).unwrap();
+    assert!(resolved.message.contains(&format!("(cherry picked from commit {})", conflicting)));
+    assert_eq!(resolved.parents, vec![main_before]);
+    assert_eq!(resolved.author.unwrap().name, "Ada", "the resolved pick keeps its author");
+    assert_eq!(resolved.committer.unwrap().name, "Bob");
+    assert_eq!(fs::read_to_string(&file).unwrap(), "resolved\n");
+    assert!(!root.join(".nag/sequencer").exists());
+}
+
+#[test]
+fn cherry_pick_abort_restores_the_original_head() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("c.txt");
+    commit_helper(&file, "base\n", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    let clean = commit_helper(&root.join("d.txt"), "d", "add d");
+    let conflicting = commit_helper(&file, "feature\n", "feature edit");
+
+    checkout("main".to_string(), false).unwrap();
+    let main_before = commit_helper(&file, "main\n", "main edit");
+
+    assert!(cherry_pick(vec![clean, conflicting]).is_err());
+    assert_ne!(read_ref("main").unwrap(), main_before, "the clean pick was committed");
+
+    cherry_pick_abort().unwrap();
+
+    assert_eq!(read_ref("main").unwrap(), main_before);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "main\n");
+    assert!(!root.join("d.txt").exists());
+    assert!(!root.join(".nag/sequencer").exists());
+    assert!(cherry_pick_abort().is_err());
+}
+
+#[test]
+fn cherry_pick_skips_changes_already_in_head() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    let picked = commit_helper(&root.join("b.txt"), "b", "add b");
+
+    checkout("main".to_string(), false).unwrap();
+    let main_before = commit_helper(&root.join("b.txt"), "b", "also add b");
+    cherry_pick(vec![picked]).unwrap();
+
+    assert_eq!(read_ref("main").unwrap(), main_before);
+}
+
+#[test]
+fn cherry_pick_refuses_a_dirty_working_directory() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "base");
+    let picked = commit_helper(&root.join("b.txt"), "b", "add b");
+
+    fs::write(root.join("a.txt"), "edited").unwrap();
+    let err = cherry_pick(vec![picked]).unwrap_err();
+    assert!(err.to_string().contains("not clean"));
+    assert!(!root.join(".nag/sequencer").exists());
+}
+
+#[test]
+fn cherry_pick_refuses_merge_commits() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&root.join("b.txt"), "b", "add b");
+    checkout("main".to_string(), false).unwrap();
+    commit_helper(&root.join("c.txt"), "c", "add c");
+    merge("feature".to_string()).unwrap();
+    let merge_oid = read_ref("main").unwrap();
+
+    branch("other".to_string(), Some(read_ref("feature").unwrap())).unwrap();
+    checkout("other".to_string(), false).unwrap();
+    let err = cherry_pick(vec![merge_oid]).unwrap_err();
+    assert!(err.to_string().contains("is a merge"));
+    assert!(!root.join(".nag/sequencer").exists());
+}
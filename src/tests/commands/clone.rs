@@ -0,0 +1,111 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    branch::branch,
+    clone::clone,
+    status::status,
+};
+use crate::core::io::{ read_file, object_path };
+use crate::core::index::read_index;
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+}
+
+fn read_oid(path: &Path) -> String {
+    String::from_utf8_lossy(&read_file(&path.to_string_lossy()).unwrap()).trim().to_string()
+}
+
+#[test]
+fn clone_copies_history_and_populates_working_tree() {
+    let tmp_src = TempDir::new().unwrap();
+    let src_root = init_test_repo(&tmp_src);
+    commit_helper(&src_root.join("a.txt"), "alpha", "first");
+    commit_helper(&src_root.join("dir/b.txt"), "beta", "second");
+
+    let tmp_dest = TempDir::new().unwrap();
+    let dest = tmp_dest.path().join("copy");
+    clone(src_root.to_string_lossy().to_string(), Some(dest.to_string_lossy().to_string())).unwrap();
+
+    let head_oid = read_oid(&src_root.join(".nag/refs/heads/main"));
+    assert_eq!(read_oid(&dest.join(".nag/refs/heads/main")), head_oid);
+    assert_eq!(read_oid(&dest.join(".nag/refs/remotes/origin/main")), head_oid);
+    assert!(object_path(&dest.join(".nag/objects"), &head_oid).exists());
+
+    assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "alpha");
+    assert_eq!(fs::read_to_string(dest.join("dir/b.txt")).unwrap(), "beta");
+    assert_eq!(read_index().unwrap().len(), 2);
+    assert!(status(false).unwrap().is_empty(), "fresh clone should be clean");
+}
+
+#[test]
+fn clone_records_origin_remote() {
+    let tmp_src = TempDir::new().unwrap();
+    let src_root = init_test_repo(&tmp_src);
+    commit_helper(&src_root.join("a.txt"), "a", "first");
+
+    let tmp_dest = TempDir::new().unwrap();
+    let dest = tmp_dest.path().join("copy");
+    clone(src_root.to_string_lossy().to_string(), Some(dest.to_string_lossy().to_string())).unwrap();
+
+    let origin = fs::read_to_string(dest.join(".nag/remotes/origin")).unwrap();
+    assert_eq!(Path::new(origin.trim()), src_root.canonicalize().unwrap());
+}
+
+#[test]
+fn clone_creates_every_branch_and_follows_remote_head() {
+    let tmp_src = TempDir::new().unwrap();
+    let src_root = init_test_repo(&tmp_src);
+    commit_helper(&src_root.join("a.txt"), "a", "first");
+    branch("dev".to_string(), None).unwrap();
+    fs::write(src_root.join(".nag/HEAD"), "ref: refs/heads/dev\n").unwrap();
+
+    let tmp_dest = TempDir::new().unwrap();
+    let dest = tmp_dest.path().join("copy");
+    clone(src_root.to_string_lossy().to_string(), Some(dest.to_string_lossy().to_string())).unwrap();
+
+    assert!(dest.join(".nag/refs/heads/main").exists());
+    assert!(dest.join(".nag/refs/heads/dev").exists());
+    let head = fs::read_to_string(dest.join(".nag/HEAD")).unwrap();
+    assert_eq!(head.trim(), "ref: refs/heads/dev");
+}
+
+#[test]
+fn clone_refuses_non_empty_destination() {
+    let tmp_src = TempDir::new().unwrap();
+    let src_root = init_test_repo(&tmp_src);
+    commit_helper(&src_root.join("a.txt"), "a", "first");
+
+    let tmp_dest = TempDir::new().unwrap();
+    fs::write(tmp_dest.path().join("existing.txt"), "x").unwrap();
+
+    let res = clone(src_root.to_string_lossy().to_string(), Some(tmp_dest.path().to_string_lossy().to_string()));
+    assert!(res.is_err());
+}
+
+#[test]
+fn clone_fails_for_non_repository_source() {
+    let tmp_src = TempDir::new().unwrap();
+    let tmp_dest = TempDir::new().unwrap();
+    std::env::set_current_dir(tmp_dest.path()).unwrap();
+
+    let res = clone(tmp_src.path().to_string_lossy().to_string(), Some(tmp_dest.path().join("copy").to_string_lossy().to_string()));
+    assert!(res.is_err());
+}
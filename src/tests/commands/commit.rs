@@ -4,6 +4,8 @@ use std::path::Path;
 
 use crate::commands::{init::init, add::add, commit::commit};
 use crate::core::index::read_index;
+use crate::core::io::read_object;
+use crate::core::config::local_timezone;
 
 // Helper: create a real repo via `init` and cd into it
 fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
@@ -35,7 +37,7 @@ fn first_commit_creates_commit_object_and_branch() {
     let commit_oid = fs::read_to_string(&branch_ref).unwrap();
     assert!(!commit_oid.trim().is_empty(), "branch should point to a commit oid");
 
-    let commit_obj = fs::read_to_string(tmp.path().join(".nag/objects").join(commit_oid.trim())).unwrap();
+    let commit_obj = String::from_utf8(read_object(commit_oid.trim()).unwrap().into_payload("commit").unwrap()).unwrap();
     assert!(commit_obj.contains("tree"), "commit object must contain a tree");
     assert!(commit_obj.contains("first"), "commit object must contain the commit message");
 }
@@ -57,7 +59,7 @@ fn commit_records_parent_oid() {
     commit("second".into()).unwrap();
 
     let second_commit_oid = fs::read_to_string(tmp.path().join(".nag/refs/heads/main")).unwrap();
-    let second_body = fs::read_to_string(tmp.path().join(".nag/objects").join(second_commit_oid.trim())).unwrap();
+    let second_body = String::from_utf8(read_object(second_commit_oid.trim()).unwrap().into_payload("commit").unwrap()).unwrap();
 
     assert!(second_body.contains(&format!("parent {}", first_commit_oid.trim())));
 }
@@ -73,7 +75,31 @@ fn commit_message_is_preserved() {
     commit("special commit message".into()).unwrap();
 
     let commit_oid = fs::read_to_string(tmp.path().join(".nag/refs/heads/main")).unwrap();
-    let body = fs::read_to_string(tmp.path().join(".nag/objects").join(commit_oid.trim())).unwrap();
+    let body = String::from_utf8(read_object(commit_oid.trim()).unwrap().into_payload("commit").unwrap()).unwrap();
 
     assert!(body.contains("special commit message"), "commit object should contain the commit message verbatim");
 }
+
+#[test]
+fn commit_records_author_and_committer() {
+    let tmp = TempDir::new().unwrap();
+    init_test_repo(&tmp);
+
+    crate::core::config::set_config_value("user.name", "Ada", false).unwrap();
+    crate::core::config::set_config_value("user.email", "ada@example.com", false).unwrap();
+
+    let file_path = tmp.path().join("who.txt");
+    write_file(&file_path, "me");
+    add(&file_path).unwrap();
+    commit("authored".into()).unwrap();
+
+    let commit_oid = fs::read_to_string(tmp.path().join(".nag/refs/heads/main")).unwrap();
+    let body = String::from_utf8(read_object(commit_oid.trim()).unwrap().into_payload("commit").unwrap()).unwrap();
+
+    let author = body.lines().find(|l| l.starts_with("author ")).expect("commit must have an author line");
+    let committer = body.lines().find(|l| l.starts_with("committer ")).expect("commit must have a committer line");
+    assert!(author.starts_with("author Ada <ada@example.com> "));
+    assert!(committer.starts_with("committer Ada <ada@example.com> "));
+    let timestamp: i64 = author.rsplit(' ').nth(1).unwrap().parse().unwrap();
+    assert!(author.ends_with(&format!(" {}", local_timezone(timestamp))), "default timezone offset should be the system's");
+}
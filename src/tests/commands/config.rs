@@ -0,0 +1,42 @@
+use tempfile::TempDir;
+
+use crate::commands::init::init;
+use crate::commands::config::{ config_get, config_set, config_list };
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+#[test]
+fn config_get_returns_value_that_was_set() {
+    let tmp = TempDir::new().unwrap();
+    init_test_repo(&tmp);
+
+    config_set("user.name".into(), "Linus".into(), false).unwrap();
+    assert_eq!(config_get("user.name".into()).unwrap(), "Linus");
+}
+
+#[test]
+fn config_get_errors_on_missing_key() {
+    let tmp = TempDir::new().unwrap();
+    init_test_repo(&tmp);
+
+    let res = config_get("user.nothing".into());
+    assert!(res.is_err());
+}
+
+#[test]
+fn config_list_shows_all_local_entries() {
+    let tmp = TempDir::new().unwrap();
+    init_test_repo(&tmp);
+
+    config_set("user.name".into(), "Linus".into(), false).unwrap();
+    config_set("user.email".into(), "linus@example.com".into(), false).unwrap();
+
+    let list = config_list(false, false).unwrap();
+    assert!(list.contains("user.name=Linus"));
+    assert!(list.contains("user.email=linus@example.com"));
+}
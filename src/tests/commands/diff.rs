@@ -0,0 +1,118 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    diff::diff,
+};
+use crate::core::io::read_file;
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) {
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+}
+
+#[test]
+fn diff_shows_unstaged_line_changes() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("notes.txt");
+    commit_helper(&file, "one\ntwo\nthree\n", "init");
+
+    fs::write(&file, "one\n2\nthree\n").unwrap();
+
+    let out = diff(false, vec![], 3, false).unwrap();
+    assert!(out.contains("diff --nag a/notes.txt b/notes.txt"));
+    assert!(out.contains("@@ -1,3 +1,3 @@"));
+    assert!(out.contains("\x1b[31m-two\x1b[0m"));
+    assert!(out.contains("\x1b[32m+2\x1b[0m"));
+
+    // Nothing is staged yet
+    assert!(diff(true, vec![], 3, false).unwrap().is_empty());
+}
+
+#[test]
+fn diff_staged_compares_index_to_head() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a\n", "init");
+
+    let new_file = root.join("b.txt");
+    fs::write(&new_file, "brand new\n").unwrap();
+    add(&new_file).unwrap();
+
+    let out = diff(true, vec![], 3, false).unwrap();
+    assert!(out.contains("new file mode 100644"));
+    assert!(out.contains("--- /dev/null"));
+    assert!(out.contains("+brand new"));
+    assert!(!out.contains("a.txt"));
+
+    // The working tree matches the index, so the default diff is empty
+    assert!(diff(false, vec![], 3, false).unwrap().is_empty());
+}
+
+#[test]
+fn diff_between_two_commits() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("f.txt");
+    commit_helper(&file, "v1\n", "first");
+    let first = String::from_utf8_lossy(&read_file(&root.join(".nag/refs/heads/main").to_string_lossy()).unwrap()).trim().to_string();
+    commit_helper(&file, "v2\n", "second");
+    fs::remove_file(&file).unwrap();
+    add(&file).unwrap();
+    commit("remove".to_string()).unwrap();
+
+    let out = diff(false, vec![first.clone(), "main".to_string()], 3, false).unwrap();
+    assert!(out.contains("deleted file mode 100644"));
+    assert!(out.contains("-v1"));
+    assert!(out.contains("+++ /dev/null"));
+}
+
+#[test]
+fn diff_detects_binary_files() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("image.bin");
+    fs::write(&file, b"\x00\x01\x02").unwrap();
+    add(&file).unwrap();
+    commit("binary".to_string()).unwrap();
+
+    fs::write(&file, b"\x00\x01\x03").unwrap();
+    let out = diff(false, vec![], 3, false).unwrap();
+    assert!(out.contains("Binary files a/image.bin and b/image.bin differ"));
+    assert!(!out.contains("@@"));
+}
+
+#[test]
+fn diff_respects_context_lines() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("ctx.txt");
+    commit_helper(&file, "1\n2\n3\n4\n5\n", "init");
+    fs::write(&file, "1\n2\nthree\n4\n5\n").unwrap();
+
+    let out = diff(false, vec![], 0, false).unwrap();
+    assert!(out.contains("@@ -3 +3 @@"));
+    assert!(!out.contains(" 2\n"));
+}
+
+#[test]
+fn diff_rejects_single_commit_argument() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "init");
+
+    assert!(diff(false, vec!["main".to_string()], 3, false).is_err());
+}
@@ -0,0 +1,140 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    tag::tag,
+    gc::gc,
+    fsck::fsck,
+};
+use crate::core::fsck::check_repository;
+use crate::core::io::{ read_object, write_object, object_path };
+use crate::core::object::Object;
+use crate::core::refs::{ resolve_head, update_ref };
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) {
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+}
+
+fn head_tree(commit_oid: &str) -> String {
+    let commit_bytes = read_object(commit_oid).unwrap().into_payload("commit").unwrap();
+    let commit_str = String::from_utf8_lossy(&commit_bytes).to_string();
+    commit_str.lines().next().unwrap().strip_prefix("tree ").unwrap().to_string()
+}
+
+#[test]
+fn fsck_passes_on_a_healthy_repository() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    commit_helper(&root.join("a.txt"), "two", "second");
+    tag(Some("v1".to_string()), None, Some("release".to_string())).unwrap();
+
+    assert_eq!(fsck(false).unwrap(), "");
+}
+
+#[test]
+fn fsck_passes_on_a_packed_repository() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    commit_helper(&root.join("a.txt"), "two", "second");
+    gc(14, false).unwrap();
+
+    assert_eq!(fsck(false).unwrap(), "");
+}
+
+#[test]
+fn fsck_reports_dangling_objects_without_failing() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let orphan = write_object(&Object::Blob(b"orphan".to_vec())).unwrap();
+
+    assert_eq!(fsck(false).unwrap(), format!("dangling blob {}\n", orphan));
+}
+
+#[test]
+fn fsck_fails_on_an_object_that_does_not_match_its_oid() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let blob_oid = Object::Blob(b"one".to_vec()).oid();
+    let path = object_path(&root.join(".nag/objects"), &blob_oid);
+    fs::write(&path, Object::Blob(b"tampered".to_vec()).compress().unwrap()).unwrap();
+
+    assert!(fsck(false).is_err());
+    let report = check_repository().unwrap();
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.errors[0].starts_with(&format!("corrupt {}: content hashes to", blob_oid)));
+}
+
+#[test]
+fn fsck_fails_on_an_undecodable_object() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let blob_oid = Object::Blob(b"one".to_vec()).oid();
+    fs::write(object_path(&root.join(".nag/objects"), &blob_oid), b"garbage").unwrap();
+
+    let report = check_repository().unwrap();
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.errors[0].starts_with(&format!("corrupt {}", blob_oid)));
+}
+
+#[test]
+fn fsck_fails_on_a_missing_tree() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let commit_oid = resolve_head().unwrap().1;
+    let tree_oid = head_tree(&commit_oid);
+    fs::remove_file(object_path(&root.join(".nag/objects"), &tree_oid)).unwrap();
+
+    let err = fsck(false).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let report = check_repository().unwrap();
+    assert!(report.errors.contains(&format!("missing tree {} (referenced by commit {})", tree_oid, commit_oid)));
+}
+
+#[test]
+fn fsck_fails_on_a_branch_pointing_at_a_blob() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let blob_oid = Object::Blob(b"one".to_vec()).oid();
+    update_ref("broken", &blob_oid, "").unwrap();
+
+    let report = check_repository().unwrap();
+    assert_eq!(report.errors, vec![format!("refs/heads/broken points at blob {}, expected a commit", blob_oid)]);
+}
+
+#[test]
+fn fsck_fails_on_a_tree_out_of_canonical_order() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let blob_oid = Object::Blob(b"one".to_vec()).oid();
+    let unsorted = format!("C\t100644\tb.txt\t{}\nC\t100644\ta.txt\t{}\n", blob_oid, blob_oid);
+    let tree_oid = write_object(&Object::Tree(unsorted.into_bytes())).unwrap();
+    let commit_str = format!("tree {}\nauthor A <a@b> 0 +0000\ncommitter A <a@b> 0 +0000\n\nunsorted\n", tree_oid);
+    let commit_oid = write_object(&Object::Commit(commit_str.into_bytes())).unwrap();
+    update_ref("unsorted", &commit_oid, "").unwrap();
+
+    let report = check_repository().unwrap();
+    assert_eq!(report.errors, vec![format!(
+        "tree {} is not canonical: entries are malformed, out of order or repeated", tree_oid,
+    )]);
+}
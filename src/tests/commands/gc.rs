@@ -0,0 +1,138 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    branch::branch,
+    checkout::checkout,
+    log::log,
+    gc::gc,
+};
+use crate::core::io::{ read_object, write_object, has_object, object_path };
+use crate::core::object::Object;
+use crate::core::pack::{ list_packs, write_pack, PackInput };
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) {
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+}
+
+// Loose object files left in the fan-out directories
+fn loose_count(root: &Path) -> usize {
+    fs::read_dir(root.join(".nag/objects")).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir() && path.file_name().unwrap().len() == 2)
+        .map(|dir| fs::read_dir(dir).unwrap().count())
+        .sum()
+}
+
+#[test]
+fn gc_packs_history_and_keeps_it_readable() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\n", "first");
+    commit_helper(&root.join("a.txt"), "one\ntwo\n", "second");
+    branch("feature".to_string(), None).unwrap();
+    commit_helper(&root.join("a.txt"), "one\ntwo\nthree\n", "third");
+    let log_before = log(None, true, false, None, Vec::new(), false).unwrap();
+
+    let output = gc(14, false).unwrap();
+
+    assert!(output.starts_with("Packed 9 objects"), "unexpected output: {}", output);
+    assert_eq!(loose_count(&root), 0);
+    assert_eq!(list_packs(&root.join(".nag/objects")).unwrap().len(), 1);
+    assert!(root.join(".nag/objects/empty").exists());
+    assert_eq!(log(None, true, false, None, Vec::new(), false).unwrap(), log_before);
+
+    checkout("feature".to_string(), false).unwrap();
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "one\ntwo\n");
+}
+
+#[test]
+fn gc_twice_replaces_the_old_pack() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    gc(14, false).unwrap();
+    commit_helper(&root.join("a.txt"), "two", "second");
+
+    let output = gc(14, false).unwrap();
+
+    assert!(output.starts_with("Packed 6 objects"), "unexpected output: {}", output);
+    assert_eq!(list_packs(&root.join(".nag/objects")).unwrap().len(), 1);
+    assert_eq!(loose_count(&root), 0);
+}
+
+#[test]
+fn gc_keeps_staged_blobs() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    fs::write(root.join("b.txt"), "staged").unwrap();
+    add(&root.join("b.txt")).unwrap();
+
+    gc(0, false).unwrap();
+
+    let staged = Object::Blob(b"staged".to_vec()).oid();
+    assert_eq!(read_object(&staged).unwrap(), Object::Blob(b"staged".to_vec()));
+}
+
+#[test]
+fn gc_prunes_unreachable_loose_objects_only_after_the_grace_period() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let objects = root.join(".nag/objects");
+    let orphan = write_object(&Object::Blob(b"orphan".to_vec())).unwrap();
+
+    let output = gc(14, false).unwrap();
+    assert!(output.ends_with("pruned 0 unreachable objects\n"), "unexpected output: {}", output);
+    assert!(object_path(&objects, &orphan).exists());
+
+    let output = gc(0, false).unwrap();
+    assert!(output.ends_with("pruned 1 unreachable objects\n"), "unexpected output: {}", output);
+    assert!(!has_object(&objects, &orphan));
+}
+
+#[test]
+fn gc_explodes_recent_unreachable_packed_objects() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let objects = root.join(".nag/objects");
+    let orphan = Object::Blob(b"packed orphan".to_vec());
+    write_pack(&objects, vec![PackInput { object: orphan.clone(), hint: String::new() }]).unwrap();
+
+    gc(14, false).unwrap();
+    assert!(object_path(&objects, &orphan.oid()).exists());
+    assert_eq!(read_object(&orphan.oid()).unwrap(), orphan);
+
+    gc(0, false).unwrap();
+    assert!(!has_object(&objects, &orphan.oid()));
+}
+
+#[test]
+fn gc_drops_unreachable_objects_from_expired_packs() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let objects = root.join(".nag/objects");
+    let orphan = Object::Blob(b"packed orphan".to_vec());
+    write_pack(&objects, vec![PackInput { object: orphan.clone(), hint: String::new() }]).unwrap();
+
+    let output = gc(0, false).unwrap();
+
+    assert!(output.ends_with("pruned 1 unreachable objects\n"), "unexpected output: {}", output);
+    assert!(!has_object(&objects, &orphan.oid()));
+}
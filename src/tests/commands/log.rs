@@ -0,0 +1,151 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    branch::branch,
+    checkout::checkout,
+    tag::tag,
+    log::log,
+};
+use crate::core::io::{ read_file, read_object, write_object };
+use crate::core::object::Object;
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+}
+
+fn head_oid(root: &Path) -> String {
+    String::from_utf8_lossy(&read_file(&root.join(".nag/refs/heads/main").to_string_lossy()).unwrap()).trim().to_string()
+}
+
+#[test]
+fn log_lists_commits_newest_first() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("a.txt");
+    commit_helper(&file, "1", "first change");
+    commit_helper(&file, "2", "second change");
+    commit_helper(&file, "3", "third change");
+
+    let out = log(None, false, false, None, vec![], false).unwrap();
+    let third = out.find("third change").unwrap();
+    let second = out.find("second change").unwrap();
+    let first = out.find("first change").unwrap();
+    assert!(third < second && second < first);
+    assert!(out.contains(&format!("commit {}", head_oid(&root))));
+    assert!(out.contains("Author: "));
+}
+
+#[test]
+fn log_oneline_prints_short_oid_and_summary() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "1", "only commit");
+
+    let out = log(None, true, false, None, vec![], false).unwrap();
+    assert_eq!(out.lines().count(), 1);
+    assert!(out.contains(&head_oid(&root)[..7]));
+    assert!(out.contains("only commit"));
+}
+
+#[test]
+fn log_count_limits_output() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("a.txt");
+    for i in 0..5 {
+        commit_helper(&file, &i.to_string(), &format!("commit {}", i));
+    }
+
+    let out = log(None, true, false, Some(2), vec![], false).unwrap();
+    assert_eq!(out.lines().count(), 2);
+    assert!(out.contains("commit 4") && out.contains("commit 3"));
+}
+
+#[test]
+fn log_path_filter_only_shows_commits_touching_path() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("src/lib.rs"), "a", "touch src");
+    commit_helper(&root.join("README"), "b", "touch readme");
+    commit_helper(&root.join("src/lib.rs"), "c", "touch src again");
+
+    let out = log(None, true, false, None, vec!["src".to_string()], false).unwrap();
+    assert_eq!(out.lines().count(), 2);
+    assert!(!out.contains("touch readme"));
+
+    let out = log(None, true, false, None, vec!["README".to_string()], false).unwrap();
+    assert_eq!(out.lines().count(), 1);
+    assert!(out.contains("touch readme"));
+}
+
+#[test]
+fn log_accepts_branch_and_tag_names() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("a.txt");
+    commit_helper(&file, "1", "on main");
+    tag(Some("v1".to_string()), None, Some("release".to_string())).unwrap();
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&file, "2", "on feature");
+
+    let main_out = log(Some("main".to_string()), true, false, None, vec![], false).unwrap();
+    assert!(!main_out.contains("on feature"));
+
+    let tag_out = log(Some("v1".to_string()), true, false, None, vec![], false).unwrap();
+    assert_eq!(tag_out.lines().count(), 1);
+    assert!(tag_out.contains("on main"));
+
+    assert!(log(Some("missing".to_string()), true, false, None, vec![], false).is_err());
+}
+
+#[test]
+fn log_graph_draws_merge_topology() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("base.txt"), "base", "base");
+
+    branch("side".to_string(), None).unwrap();
+    checkout("side".to_string(), false).unwrap();
+    commit_helper(&root.join("side.txt"), "side", "side work");
+    let side_oid = String::from_utf8_lossy(&read_file(&root.join(".nag/refs/heads/side").to_string_lossy()).unwrap()).trim().to_string();
+
+    checkout("main".to_string(), false).unwrap();
+    commit_helper(&root.join("main.txt"), "main", "main work");
+    let main_oid = head_oid(&root);
+
+    // Hand-build a merge commit joining both lines of history
+    let commit_bytes = read_object(&main_oid).unwrap().into_payload("commit").unwrap();
+    let tree = String::from_utf8_lossy(&commit_bytes).lines().next().unwrap().replacen("tree ", "", 1);
+    let merge_body = format!("tree {}\nparent {}\nparent {}\n\nmerge side\n", tree, main_oid, side_oid);
+    let merge_oid = write_object(&Object::Commit(merge_body.into_bytes())).unwrap();
+    fs::write(root.join(".nag/refs/heads/main"), &merge_oid).unwrap();
+
+    let out = log(None, true, true, None, vec![], false).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 6, "unexpected graph:\n{}", out);
+    assert!(lines[0].starts_with("* ") && lines[0].ends_with("merge side"));
+    assert_eq!(lines[1], "|\\");
+    assert!(lines[2].starts_with("| * ") || lines[2].starts_with("* | "));
+    assert!(lines[3].starts_with("| * ") || lines[3].starts_with("* | "));
+    assert_eq!(lines[4], "|/");
+    assert!(lines[5].starts_with("* ") && lines[5].ends_with("base"));
+}
@@ -2,7 +2,7 @@ use tempfile::TempDir;
 use std::fs;
 use crate::commands::{init::init, add::add, commit::commit, branch::branch, checkout::checkout, status::status};
 use crate::core::repo::find_repo_root;
-use crate::core::io::{read_file, write_file};
+use crate::core::io::{read_file, read_object, write_file};
 use crate::commands::merge::merge;
 
 // helper
@@ -28,10 +28,10 @@ fn ff_merge_succeeds_on_direct_descendant() {
     commit_helper(&file, "v1", "first commit");
 
     branch("feature".to_string(), None).unwrap();
-    checkout("feature".to_string()).unwrap();
+    checkout("feature".to_string(), false).unwrap();
 
     commit_helper(&file, "v2", "second commit");
-    checkout("main".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
     merge("feature".to_string()).unwrap();
 
     // both branches now share same oid
@@ -65,10 +65,10 @@ fn ff_merge_fails_on_diverged_history() {
     commit_helper(&file, "base", "base commit");
 
     branch("alt".to_string(), None).unwrap();
-    checkout("alt".to_string()).unwrap();
+    checkout("alt".to_string(), false).unwrap();
     commit_helper(&file, "alt change", "alt commit");
 
-    checkout("main".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
     commit_helper(&file, "main change", "main commit");
 
     let result = merge("alt".to_string());
@@ -84,10 +84,10 @@ fn ff_merge_fails_on_dirty_working_directory() {
     commit_helper(&file, "clean", "commit clean");
 
     branch("dirty".to_string(), None).unwrap();
-    checkout("dirty".to_string()).unwrap();
+    checkout("dirty".to_string(), false).unwrap();
     commit_helper(&file, "new", "new commit");
 
-    checkout("main".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
     fs::write(&file, "unsaved").unwrap(); // dirty
 
     let result = merge("dirty".to_string());
@@ -128,11 +128,11 @@ fn merge_simple_conflict() {
     commit_helper(&file, "main-change", "main change");
 
     // Switch to feature and modify differently
-    checkout("feature".to_string()).unwrap();
+    checkout("feature".to_string(), false).unwrap();
     commit_helper(&file, "feature-change", "feature change");
 
     // Go back to main and try to merge feature - should create conflict
-    checkout("main".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
     let res = merge("feature".to_string());
     
     // Should succeed but create conflict markers
@@ -175,12 +175,12 @@ fn merge_both_add_same_file_conflict() {
     commit_helper(&new_main, "main-new", "main add");
 
     // Switch to feature and add same file with different content
-    checkout("f".to_string()).unwrap();
+    checkout("f".to_string(), false).unwrap();
     let new_feature = root.join("new.txt");
     commit_helper(&new_feature, "feature-new", "feature add");
 
     // Go back to main and merge - should create conflict
-    checkout("main".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
     let res = merge("f".to_string());
     assert!(res.is_err()); // Merge runs but creates conflict
 }
@@ -244,11 +244,11 @@ fn merge_delete_vs_edit_conflict() {
     commit("main delete".to_string()).unwrap();
 
     // Go to alt and edit
-    checkout("alt".to_string()).unwrap();
+    checkout("alt".to_string(), false).unwrap();
     commit_helper(&file, "alt edit", "alt edit");
 
     // Back to main and merge - should produce conflict (delete vs edit)
-    checkout("main".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
     let res = merge("alt".to_string());
     assert!(res.is_err()); // merge runs, but conflict entries produced
 }
@@ -269,11 +269,11 @@ fn merge_nested_file_conflict() {
     commit_helper(&f, "main edit", "main edit");
 
     // Go to work and make different edit
-    checkout("work".to_string()).unwrap();
+    checkout("work".to_string(), false).unwrap();
     commit_helper(&f, "work edit", "work edit");
 
     // Back to main and merge - should create conflict
-    checkout("main".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
     let res = merge("work".to_string());
     assert!(res.is_err()); // Runs but creates conflict
 }
@@ -316,13 +316,313 @@ fn merge_both_add_dirs() {
     commit_helper(&d1, "aaa", "add a1");
 
     // Go to other and add dir B
-    checkout("other".to_string()).unwrap();
+    checkout("other".to_string(), false).unwrap();
     let d2 = root.join("b/b1.txt");
     fs::create_dir_all(d2.parent().unwrap()).unwrap();
     commit_helper(&d2, "bbb", "add b1");
 
     // Back to main and merge - should succeed (no conflicts, just combine both dirs)
-    checkout("main".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
     let res = merge("other".to_string());
     assert!(res.is_ok());
 }
+
+#[test]
+fn merge_auto_resolves_edits_to_different_lines() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let file = root.join("lines.txt");
+    commit_helper(&file, "one\ntwo\nthree\nfour\nfive\n", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    commit_helper(&file, "ONE\ntwo\nthree\nfour\nfive\n", "main edits top");
+
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&file, "one\ntwo\nthree\nfour\nFIVE\n", "feature edits bottom");
+
+    checkout("main".to_string(), false).unwrap();
+    merge("feature".to_string()).unwrap();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "ONE\ntwo\nthree\nfour\nFIVE\n");
+    let index = crate::core::index::read_index().unwrap();
+    let entry = index.iter().find(|e| e.path == "lines.txt").unwrap();
+    assert_eq!(entry.entry_type, crate::core::index::EntryType::C);
+}
+
+#[test]
+fn merge_conflict_markers_surround_only_conflicting_lines() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+
+    let file = root.join("story.txt");
+    commit_helper(&file, "intro\nmiddle\nending\n", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    commit_helper(&file, "intro\nmain middle\nending\n", "main middle");
+
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&file, "intro\nfeature middle\nending\n", "feature middle");
+
+    checkout("main".to_string(), false).unwrap();
+    assert!(merge("feature".to_string()).is_err());
+
+    let contents = fs::read_to_string(&file).unwrap();
+    assert!(contents.starts_with("intro\n<<<<<<< HEAD\nmain middle\n||||||| ancestor\nmiddle\n=======\nfeature middle\n>>>>>>> "));
+    assert!(contents.ends_with("\nending\n"));
+
+    let index = crate::core::index::read_index().unwrap();
+    let entry = index.iter().find(|e| e.path == "story.txt").unwrap();
+    assert_eq!(entry.entry_type, crate::core::index::EntryType::X);
+}
+
+fn commit_parents(oid: &str) -> Vec<String> {
+    let bytes = read_object(oid).unwrap().into_payload("commit").unwrap();
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .take_while(|l| !l.is_empty())
+        .filter_map(|l| l.strip_prefix("parent ").map(|p| p.to_string()))
+        .collect()
+}
+
+fn ref_oid(name: &str) -> String {
+    let nag = find_repo_root().unwrap().join(".nag");
+    String::from_utf8_lossy(&read_file(&nag.join("refs/heads").join(name).to_string_lossy()).unwrap()).trim().to_string()
+}
+
+#[test]
+fn clean_three_way_merge_creates_two_parent_commit() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("base.txt"), "base", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    commit_helper(&root.join("main.txt"), "main", "main work");
+    let main_before = ref_oid("main");
+
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&root.join("feature.txt"), "feature", "feature work");
+    let feature_oid = ref_oid("feature");
+
+    checkout("main".to_string(), false).unwrap();
+    merge("feature".to_string()).unwrap();
+
+    let merge_oid = ref_oid("main");
+    assert_eq!(commit_parents(&merge_oid), vec![main_before, feature_oid]);
+    assert!(!root.join(".nag/MERGE_HEAD").exists());
+    assert!(status(false).unwrap().is_empty());
+
+    // The merged branch is now an ancestor, so a repeat merge has nothing to do
+    merge("feature".to_string()).unwrap();
+    assert_eq!(ref_oid("main"), merge_oid);
+}
+
+#[test]
+fn conflicted_merge_is_finished_by_commit_after_resolve() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("c.txt");
+    commit_helper(&file, "base\n", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    commit_helper(&file, "main\n", "main edit");
+    let main_before = ref_oid("main");
+
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&file, "feature\n", "feature edit");
+    let feature_oid = ref_oid("feature");
+
+    checkout("main".to_string(), false).unwrap();
+    assert!(merge("feature".to_string()).is_err());
+
+    let merge_head = fs::read_to_string(root.join(".nag/MERGE_HEAD")).unwrap();
+    assert_eq!(merge_head.trim(), feature_oid);
+    assert_eq!(ref_oid("main"), main_before, "no commit until conflicts are resolved");
+
+    // Committing with unresolved entries is refused
+    assert!(commit("too early".to_string()).is_err());
+
+    fs::write(&file, "resolved\n").unwrap();
+    crate::commands::resolve::resolve("c.txt").unwrap();
+    commit("merge feature".to_string()).unwrap();
+
+    assert_eq!(commit_parents(&ref_oid("main")), vec![main_before, feature_oid]);
+    assert!(!root.join(".nag/MERGE_HEAD").exists());
+}
+
+#[test]
+fn merge_refuses_while_another_merge_is_in_progress() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "base");
+
+    fs::write(root.join(".nag/MERGE_HEAD"), "deadbeef").unwrap();
+    let res = merge("main".to_string());
+    assert!(res.is_err());
+    assert!(res.unwrap_err().to_string().contains("already in progress"));
+}
+
+#[test]
+fn criss_cross_merge_uses_virtual_ancestor() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("lines.txt");
+    commit_helper(&file, "1\n2\n3\n", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    commit_helper(&file, "B\n2\n3\n", "main edits first line");
+    branch("main-side".to_string(), None).unwrap();
+
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&file, "1\n2\nC\n", "feature edits last line");
+    branch("feature-side".to_string(), None).unwrap();
+
+    // Each branch merges the other's first commit, leaving two equally good bases
+    merge("main-side".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
+    merge("feature-side".to_string()).unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "B\n2\nC\n");
+
+    commit_helper(&file, "B2\n2\nC\n", "main again");
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&file, "B\n2\nC2\n", "feature again");
+    let feature_oid = ref_oid("feature");
+
+    checkout("main".to_string(), false).unwrap();
+    let main_before = ref_oid("main");
+    merge("feature".to_string()).unwrap();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "B2\n2\nC2\n");
+    assert_eq!(commit_parents(&ref_oid("main")), vec![main_before, feature_oid]);
+}
+
+#[test]
+fn criss_cross_merge_with_conflicting_bases_runs_from_a_subdirectory() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("f.txt");
+    fs::create_dir(root.join("sub")).unwrap();
+    commit_helper(&root.join("sub/keep.txt"), "keep\n", "base");
+    commit_helper(&file, "base\n", "base file");
+
+    branch("feature".to_string(), None).unwrap();
+    commit_helper(&file, "main\n", "main edit");
+    branch("main-side".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&file, "feature\n", "feature edit");
+    branch("feature-side".to_string(), None).unwrap();
+
+    // Both sides resolve the same conflict, so the two merge bases disagree on f.txt
+    assert!(merge("main-side".to_string()).is_err());
+    fs::write(&file, "both\n").unwrap();
+    crate::commands::resolve::resolve("f.txt").unwrap();
+    commit("merge main-side".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
+    assert!(merge("feature-side".to_string()).is_err());
+    fs::write(&file, "both\n").unwrap();
+    crate::commands::resolve::resolve("f.txt").unwrap();
+    commit("merge feature-side".to_string()).unwrap();
+
+    commit_helper(&root.join("m.txt"), "m\n", "main again");
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&root.join("g.txt"), "g\n", "feature again");
+    checkout("main".to_string(), false).unwrap();
+
+    // The virtual ancestor's conflicted blob must be stored wherever the command runs from
+    std::env::set_current_dir(root.join("sub")).unwrap();
+    merge("feature".to_string()).unwrap();
+    std::env::set_current_dir(&root).unwrap();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "both\n");
+    assert_eq!(fs::read_to_string(root.join("g.txt")).unwrap(), "g\n");
+}
+
+#[test]
+fn failed_merge_without_conflicts_leaves_no_merge_head() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&root.join("g.txt"), "feature only", "feature adds g");
+    checkout("main".to_string(), false).unwrap();
+    commit_helper(&root.join("m.txt"), "m", "main adds m");
+
+    // A missing object is an error, not a conflict
+    let blob = crate::core::object::Object::Blob(b"feature only".to_vec()).oid();
+    fs::remove_file(crate::core::io::object_path(&root.join(".nag/objects"), &blob)).unwrap();
+    let err = merge("feature".to_string()).unwrap_err();
+    assert!(!err.to_string().contains("conflicts"));
+    assert!(!root.join(".nag/MERGE_HEAD").exists());
+}
+
+#[test]
+fn merging_a_descendant_with_shared_history_fast_forwards() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("file.txt");
+    commit_helper(&file, "v1", "first");
+    commit_helper(&file, "v2", "second");
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&file, "v3", "third");
+    commit_helper(&file, "v4", "fourth");
+    let feature_oid = ref_oid("feature");
+
+    // The branches share older ancestors too, but HEAD itself is one, so no merge commit is made
+    checkout("main".to_string(), false).unwrap();
+    merge("feature".to_string()).unwrap();
+
+    assert_eq!(ref_oid("main"), feature_oid);
+    assert_eq!(commit_parents(&feature_oid).len(), 1);
+}
+
+#[test]
+fn fast_forward_updates_the_working_directory_and_index() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file = root.join("file.txt");
+    let gone = root.join("gone.txt");
+    commit_helper(&gone, "bye", "add gone");
+    commit_helper(&file, "v1", "first");
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&file, "v2", "second");
+    fs::remove_file(&gone).unwrap();
+    add(&gone).unwrap();
+    commit("remove gone".to_string()).unwrap();
+
+    checkout("main".to_string(), false).unwrap();
+    merge("feature".to_string()).unwrap();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "v2");
+    assert!(!gone.exists());
+    assert!(status(false).unwrap().is_empty(), "the index matches the new HEAD");
+}
+
+#[test]
+fn three_way_merge_removes_files_the_target_deleted() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    fs::create_dir(root.join("docs")).unwrap();
+    let doomed = root.join("docs/old.txt");
+    commit_helper(&doomed, "old", "base");
+
+    branch("feature".to_string(), None).unwrap();
+    checkout("feature".to_string(), false).unwrap();
+    fs::remove_file(&doomed).unwrap();
+    add(&doomed).unwrap();
+    commit("remove old".to_string()).unwrap();
+
+    checkout("main".to_string(), false).unwrap();
+    commit_helper(&root.join("main.txt"), "main", "main work");
+    merge("feature".to_string()).unwrap();
+
+    assert_eq!(commit_parents(&ref_oid("main")).len(), 2);
+    assert!(!doomed.exists());
+    assert!(!root.join("docs").exists(), "the emptied directory goes too");
+    assert!(status(false).unwrap().is_empty());
+}
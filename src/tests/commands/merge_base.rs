@@ -0,0 +1,69 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    branch::branch,
+    checkout::checkout,
+    merge_base::merge_base,
+};
+use crate::core::io::read_file;
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) {
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+}
+
+fn ref_oid(root: &Path, name: &str) -> String {
+    String::from_utf8_lossy(&read_file(&root.join(".nag/refs/heads").join(name).to_string_lossy()).unwrap()).trim().to_string()
+}
+
+#[test]
+fn merge_base_prints_fork_point_of_two_branches() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "first");
+    commit_helper(&root.join("a.txt"), "b", "second");
+    let fork = ref_oid(&root, "main");
+
+    branch("feature".to_string(), None).unwrap();
+    commit_helper(&root.join("a.txt"), "main", "main work");
+    checkout("feature".to_string(), false).unwrap();
+    commit_helper(&root.join("f.txt"), "feature", "feature work");
+
+    let output = merge_base("main", "feature", false, false).unwrap();
+    assert_eq!(output, format!("{}\n", fork));
+}
+
+#[test]
+fn merge_base_of_ancestor_is_the_ancestor_itself() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "first");
+    branch("old".to_string(), None).unwrap();
+    let old = ref_oid(&root, "old");
+    commit_helper(&root.join("a.txt"), "b", "second");
+
+    let output = merge_base("HEAD", "old", true, false).unwrap();
+    assert_eq!(output, format!("{}\n", old));
+}
+
+#[test]
+fn merge_base_rejects_unknown_revision() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "first");
+
+    assert!(merge_base("main", "nope", false, false).is_err());
+}
@@ -0,0 +1,115 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    mv::mv,
+};
+use crate::core::index::read_index;
+use crate::core::object::Object;
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+}
+
+fn tracked() -> Vec<(String, String)> {
+    read_index().unwrap().into_iter().map(|entry| (entry.path, entry.oids[0].clone())).collect()
+}
+
+#[test]
+fn mv_renames_a_file_on_disk_and_in_the_index() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+
+    mv("a.txt".to_string(), "b.txt".to_string(), false).unwrap();
+
+    assert!(!root.join("a.txt").exists());
+    assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "one");
+    assert_eq!(tracked(), vec![("b.txt".to_string(), Object::Blob(b"one".to_vec()).oid())]);
+}
+
+#[test]
+fn mv_keeps_unstaged_changes_and_staged_content() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    fs::write(root.join("a.txt"), "edited").unwrap();
+
+    mv("a.txt".to_string(), "b.txt".to_string(), false).unwrap();
+
+    assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "edited");
+    assert_eq!(tracked(), vec![("b.txt".to_string(), Object::Blob(b"one".to_vec()).oid())]);
+}
+
+#[test]
+fn mv_moves_into_an_existing_directory() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    commit_helper(&root.join("dir/b.txt"), "two", "second");
+
+    mv("a.txt".to_string(), "dir".to_string(), false).unwrap();
+
+    assert_eq!(fs::read_to_string(root.join("dir/a.txt")).unwrap(), "one");
+    let paths: Vec<String> = tracked().into_iter().map(|(path, _)| path).collect();
+    assert_eq!(paths, vec!["dir/a.txt", "dir/b.txt"]);
+}
+
+#[test]
+fn mv_renames_a_whole_directory() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("old/a.txt"), "one", "first");
+    commit_helper(&root.join("old/sub/b.txt"), "two", "second");
+
+    mv("old".to_string(), "new/place".to_string(), false).unwrap();
+
+    assert!(!root.join("old").exists());
+    assert_eq!(fs::read_to_string(root.join("new/place/sub/b.txt")).unwrap(), "two");
+    let paths: Vec<String> = tracked().into_iter().map(|(path, _)| path).collect();
+    assert_eq!(paths, vec!["new/place/a.txt", "new/place/sub/b.txt"]);
+}
+
+#[test]
+fn mv_refuses_to_overwrite_unless_forced() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    commit_helper(&root.join("b.txt"), "two", "second");
+
+    assert!(mv("a.txt".to_string(), "b.txt".to_string(), false).is_err());
+    assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "two");
+
+    mv("a.txt".to_string(), "b.txt".to_string(), true).unwrap();
+    assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "one");
+    assert_eq!(tracked(), vec![("b.txt".to_string(), Object::Blob(b"one".to_vec()).oid())]);
+}
+
+#[test]
+fn mv_refuses_untracked_sources_and_moves_into_themselves() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("dir/a.txt"), "one", "first");
+    fs::write(root.join("loose.txt"), "untracked").unwrap();
+
+    assert!(mv("loose.txt".to_string(), "b.txt".to_string(), false).is_err());
+    assert!(mv("dir".to_string(), "dir/inner".to_string(), false).is_err());
+    assert!(root.join("loose.txt").exists());
+    assert!(root.join("dir/a.txt").exists());
+}
@@ -0,0 +1,114 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    remote::add_remote,
+    pull::pull,
+};
+use crate::core::io::read_file;
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) {
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+}
+
+fn remote_commit_helper(repo_root: &Path, path: &Path, content: &str, msg: &str) {
+    let original_dir = std::env::current_dir().unwrap();
+    std::env::set_current_dir(repo_root).unwrap();
+    commit_helper(path, content, msg);
+    std::env::set_current_dir(original_dir).unwrap();
+}
+
+fn read_oid(path: &Path) -> String {
+    String::from_utf8_lossy(&read_file(&path.to_string_lossy()).unwrap()).trim().to_string()
+}
+
+#[test]
+fn pull_into_empty_branch_fast_forwards_and_writes_files() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    commit_helper(&remote_root.join("hello.txt"), "hi", "initial");
+
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+
+    pull(None, None).unwrap();
+
+    assert_eq!(
+        read_oid(&local_root.join(".nag/refs/heads/main")),
+        read_oid(&remote_root.join(".nag/refs/heads/main")),
+    );
+    assert_eq!(fs::read_to_string(local_root.join("hello.txt")).unwrap(), "hi");
+}
+
+#[test]
+fn pull_fast_forwards_to_new_remote_commits() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    let remote_file = remote_root.join("f.txt");
+    commit_helper(&remote_file, "v1", "first");
+
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+    pull(Some("origin".into()), Some("main".into())).unwrap();
+
+    remote_commit_helper(&remote_root, &remote_file, "v2", "second");
+    remote_commit_helper(&remote_root, &remote_root.join("g.txt"), "new", "third");
+
+    pull(Some("origin".into()), Some("main".into())).unwrap();
+
+    assert_eq!(
+        read_oid(&local_root.join(".nag/refs/heads/main")),
+        read_oid(&remote_root.join(".nag/refs/heads/main")),
+    );
+    assert_eq!(fs::read_to_string(local_root.join("f.txt")).unwrap(), "v2");
+    assert_eq!(fs::read_to_string(local_root.join("g.txt")).unwrap(), "new");
+}
+
+#[test]
+fn pull_merges_diverged_histories() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    commit_helper(&remote_root.join("base.txt"), "base", "base");
+
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+    pull(None, None).unwrap();
+
+    remote_commit_helper(&remote_root, &remote_root.join("theirs.txt"), "theirs", "remote work");
+    commit_helper(&local_root.join("ours.txt"), "ours", "local work");
+
+    pull(None, None).unwrap();
+
+    assert_eq!(fs::read_to_string(local_root.join("ours.txt")).unwrap(), "ours");
+    assert_eq!(fs::read_to_string(local_root.join("theirs.txt")).unwrap(), "theirs");
+}
+
+#[test]
+fn pull_errors_when_remote_branch_missing() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    commit_helper(&remote_root.join("a.txt"), "a", "a");
+
+    let tmp_local = TempDir::new().unwrap();
+    init_test_repo(&tmp_local);
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+
+    let res = pull(None, Some("nope".into()));
+    assert!(res.is_err());
+}
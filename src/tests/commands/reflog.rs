@@ -0,0 +1,176 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    branch::branch,
+    checkout::checkout,
+    merge::merge,
+    tag::tag,
+    gc::gc,
+    reflog::reflog,
+    reset::{ reset, ResetMode },
+};
+use crate::core::refs::resolve_head;
+use crate::core::reflog::{ read_reflog, write_reflog, ZERO_OID };
+use crate::core::config::set_config_value;
+use crate::core::io::read_object;
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) -> String {
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+    resolve_head().unwrap().1
+}
+
+#[test]
+fn commits_are_logged_on_the_branch_and_head() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "1", "first");
+    let second = commit_helper(&root.join("a.txt"), "2", "second");
+
+    let entries = read_reflog("refs/heads/main").unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!((entries[0].old.as_str(), entries[0].new.as_str()), (ZERO_OID, first.as_str()));
+    assert_eq!(entries[0].message, "commit (initial): first");
+    assert_eq!((entries[1].old.as_str(), entries[1].new.as_str()), (first.as_str(), second.as_str()));
+    assert_eq!(entries[1].message, "commit: second");
+    assert_eq!(read_reflog("HEAD").unwrap(), entries);
+}
+
+#[test]
+fn reflog_lists_moves_newest_first() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "1", "first");
+    branch("topic".to_string(), None).unwrap();
+    let second = commit_helper(&root.join("a.txt"), "2", "second");
+    checkout("topic".to_string(), false).unwrap();
+
+    let output = reflog(None, false).unwrap();
+    assert_eq!(output, format!(
+        "{} HEAD@{{0}}: checkout: moving from main to topic\n{} HEAD@{{1}}: commit: second\n{} HEAD@{{2}}: commit (initial): first\n",
+        &first[..7], &second[..7], &first[..7],
+    ));
+    assert_eq!(reflog(Some("topic".to_string()), false).unwrap(), format!("{} topic@{{0}}: branch: Created from HEAD\n", &first[..7]));
+    assert!(reflog(Some("nope".to_string()), false).is_err());
+}
+
+#[test]
+fn detached_head_moves_are_logged_on_head_only() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "1", "first");
+    commit_helper(&root.join("a.txt"), "2", "second");
+    checkout(first.clone(), false).unwrap();
+    let detached = commit_helper(&root.join("a.txt"), "3", "detached");
+
+    let head = read_reflog("HEAD").unwrap();
+    assert_eq!(head[2].message, format!("checkout: moving from main to {}", first));
+    assert_eq!(head[3].new, detached);
+    assert_eq!(read_reflog("refs/heads/main").unwrap().len(), 2);
+}
+
+#[test]
+fn fast_forward_merges_are_logged() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "1", "first");
+    branch("topic".to_string(), None).unwrap();
+    checkout("topic".to_string(), false).unwrap();
+    let ahead = commit_helper(&root.join("a.txt"), "2", "second");
+    checkout("main".to_string(), false).unwrap();
+
+    merge("topic".to_string()).unwrap();
+
+    let main = read_reflog("refs/heads/main").unwrap();
+    assert_eq!(main.last().unwrap().new, ahead);
+    assert_eq!(main.last().unwrap().message, "merge topic: Fast-forward");
+    assert_eq!(read_reflog("HEAD").unwrap().last().unwrap(), main.last().unwrap());
+}
+
+#[test]
+fn tags_are_not_logged() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "1", "first");
+
+    tag(Some("v1".to_string()), None, None).unwrap();
+
+    assert!(read_reflog("refs/tags/v1").unwrap().is_empty());
+}
+
+#[test]
+fn gc_keeps_commits_only_the_reflog_remembers() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "1", "first");
+    branch("topic".to_string(), None).unwrap();
+    checkout("topic".to_string(), false).unwrap();
+    let lost = commit_helper(&root.join("a.txt"), "2", "lost");
+    checkout("main".to_string(), false).unwrap();
+    fs::write(root.join(".nag/refs/heads/topic"), &first).unwrap();
+
+    gc(0, false).unwrap();
+
+    assert!(read_object(&lost).is_ok());
+}
+
+#[test]
+fn gc_prunes_commits_once_their_reflog_entries_expire() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "1", "first");
+    branch("topic".to_string(), None).unwrap();
+    checkout("topic".to_string(), false).unwrap();
+    let lost = commit_helper(&root.join("a.txt"), "2", "lost");
+    reset(Some(first.clone()), ResetMode::Hard).unwrap();
+    checkout("main".to_string(), false).unwrap();
+
+    gc(0, false).unwrap();
+    assert!(read_object(&lost).is_ok(), "fresh entries keep the commit");
+
+    for name in ["HEAD", "refs/heads/main", "refs/heads/topic"] {
+        let mut entries = read_reflog(name).unwrap();
+        for entry in entries.iter_mut() {
+            entry.who.timestamp -= 91 * 24 * 60 * 60;
+        }
+        write_reflog(name, &entries).unwrap();
+    }
+    let output = gc(0, false).unwrap();
+
+    assert!(output.starts_with("Expired "), "unexpected output: {}", output);
+    assert!(read_object(&lost).is_err());
+    assert_eq!(read_reflog("refs/heads/topic").unwrap().len(), 1, "the newest entry stays");
+    assert!(read_object(&first).is_ok());
+}
+
+#[test]
+fn reflog_expiry_follows_the_config() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "1", "first");
+    commit_helper(&root.join("a.txt"), "2", "second");
+
+    let mut entries = read_reflog("HEAD").unwrap();
+    entries[0].who.timestamp -= 2 * 24 * 60 * 60;
+    write_reflog("HEAD", &entries).unwrap();
+
+    gc(0, false).unwrap();
+    assert_eq!(read_reflog("HEAD").unwrap().len(), 2);
+
+    set_config_value("gc.reflogExpire", "1", false).unwrap();
+    gc(0, false).unwrap();
+    assert_eq!(read_reflog("HEAD").unwrap().len(), 1);
+}
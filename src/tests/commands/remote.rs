@@ -1,13 +1,18 @@
 use tempfile::TempDir;
 use std::fs;
-use crate::commands::remote::{ add_remote, remove_remote, fetch_remote };
+use crate::commands::remote::{ add_remote, remove_remote, fetch_remote, push_remote };
 use crate::core::repo::find_repo_root;
-use crate::core::io::read_file;
+use crate::core::io::{ read_file, read_object, read_object_from, write_object, object_path };
+use crate::core::object::Object;
 use crate::commands::init::init;
 use crate::commands::{
     add::add,
     commit::commit,
+    branch::branch,
+    checkout::checkout,
 };
+use crate::core::tree::read_tree_to_index;
+use crate::core::hash::hash;
 
 use std::env;
 use std::path::Path;
@@ -143,7 +148,7 @@ fn fetch_copies_new_branch_commit_and_tree() {
     fetch_remote("origin".into()).unwrap();
 
     // Confirm local now has remote's commit object
-    let local_commit_path = local_root.join(".nag/objects").join(&remote_oid);
+    let local_commit_path = object_path(&local_root.join(".nag/objects"), &remote_oid);
     assert!(local_commit_path.exists(), "Local repo should contain remote commit");
 
     // Confirm remote-tracking ref exists
@@ -207,8 +212,7 @@ fn fetch_copies_parent_commits() {
     let head_oid = String::from_utf8_lossy(&read_file(&remote_head_path.to_string_lossy()).unwrap()).trim().to_string();
 
     // Now read the COMMIT OBJECT itself
-    let commit_obj_path = remote_root.join(".nag/objects").join(&head_oid);
-    let commit_bytes = read_file(&commit_obj_path.to_string_lossy()).unwrap();
+    let commit_bytes = read_object_from(&remote_root.join(".nag/objects"), &head_oid).unwrap().into_payload("commit").unwrap();
     let commit_text = String::from_utf8_lossy(&commit_bytes);
 
     // Extract parent line if present
@@ -225,7 +229,7 @@ fn fetch_copies_parent_commits() {
     fetch_remote("r".into()).unwrap();
 
     // Confirm parent commit also exists locally
-    let parent_path = local_root.join(".nag/objects").join(&parent_oid);
+    let parent_path = object_path(&local_root.join(".nag/objects"), &parent_oid);
     assert!(parent_path.exists(), "Fetch must copy parent commit objects");
 }
 
@@ -271,3 +275,285 @@ fn fetch_multiple_branches() {
         "dev must be fetched"
     );
 }
+
+#[test]
+fn push_copies_objects_and_updates_refs() {
+    // REMOTE repo
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+
+    // LOCAL repo
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+
+    let file = local_root.join("pushed.txt");
+    commit_helper(&file, "v1", "first");
+    commit_helper(&file, "v2", "second");
+
+    let local_oid = String::from_utf8_lossy(
+        &read_file(&local_root.join(".nag/refs/heads/main").to_string_lossy()).unwrap()
+    ).trim().to_string();
+
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+    allow_push_to_current_branch(&remote_root);
+    push_remote("origin".into(), None, false).unwrap();
+
+    // Remote branch now points at the pushed commit, and the commit exists there
+    let remote_ref = remote_root.join(".nag/refs/heads/main");
+    let remote_oid = String::from_utf8_lossy(&read_file(&remote_ref.to_string_lossy()).unwrap()).trim().to_string();
+    assert_eq!(remote_oid, local_oid);
+    assert!(object_path(&remote_root.join(".nag/objects"), &local_oid).exists());
+
+    // The parent commit must be copied as well
+    let commit_bytes = read_object(&local_oid).unwrap().into_payload("commit").unwrap();
+    let parent_oid = String::from_utf8_lossy(&commit_bytes)
+        .lines()
+        .find(|l| l.starts_with("parent "))
+        .unwrap()
+        .replacen("parent ", "", 1);
+    assert!(object_path(&remote_root.join(".nag/objects"), parent_oid.trim()).exists());
+
+    // Local tracking ref mirrors the remote branch
+    let tracking_ref = local_root.join(".nag/refs/remotes/origin/main");
+    let tracking_oid = String::from_utf8_lossy(&read_file(&tracking_ref.to_string_lossy()).unwrap()).trim().to_string();
+    assert_eq!(tracking_oid, local_oid);
+}
+
+#[test]
+fn push_rejects_non_fast_forward_unless_forced() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    remote_commit_helper(&remote_root, &remote_root.join("r.txt"), "remote", "remote commit");
+
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+    commit_helper(&local_root.join("l.txt"), "local", "local commit");
+
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+    allow_push_to_current_branch(&remote_root);
+
+    let res = push_remote("origin".into(), Some("main".into()), false);
+    assert!(res.is_err(), "Diverged push must be rejected");
+
+    push_remote("origin".into(), Some("main".into()), true).unwrap();
+
+    let local_oid = String::from_utf8_lossy(
+        &read_file(&local_root.join(".nag/refs/heads/main").to_string_lossy()).unwrap()
+    ).trim().to_string();
+    let remote_oid = String::from_utf8_lossy(
+        &read_file(&remote_root.join(".nag/refs/heads/main").to_string_lossy()).unwrap()
+    ).trim().to_string();
+    assert_eq!(remote_oid, local_oid);
+}
+
+#[test]
+fn push_fast_forwards_after_fetch() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    remote_commit_helper(&remote_root, &remote_root.join("f.txt"), "base", "base");
+
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+    fetch_remote("origin".into()).unwrap();
+
+    // Point local main at the fetched commit and build on top of it
+    let fetched = fs::read_to_string(local_root.join(".nag/refs/remotes/origin/main")).unwrap();
+    fs::write(local_root.join(".nag/refs/heads/main"), fetched.trim()).unwrap();
+    commit_helper(&local_root.join("g.txt"), "more", "on top");
+
+    allow_push_to_current_branch(&remote_root);
+    assert!(push_remote("origin".into(), None, false).is_ok());
+}
+
+#[test]
+fn push_errors_on_unknown_remote() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "a", "a");
+
+    let res = push_remote("missing".into(), None, false);
+    assert!(res.is_err());
+}
+
+// Lets pushes update the branch the remote has checked out
+fn allow_push_to_current_branch(remote_root: &Path) {
+    let config = remote_root.join(".nag/config");
+    let mut contents = fs::read_to_string(&config).unwrap_or_default();
+    contents.push_str("receive.denyCurrentBranch = ignore\n");
+    fs::write(config, contents).unwrap();
+}
+
+fn read_oid(path: &Path) -> String {
+    String::from_utf8_lossy(&read_file(&path.to_string_lossy()).unwrap()).trim().to_string()
+}
+
+fn commit_field(objects: &Path, oid: &str, field: &str) -> Vec<String> {
+    let bytes = read_object_from(objects, oid).unwrap().into_payload("commit").unwrap();
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .filter_map(|l| l.strip_prefix(field).map(|v| v.trim().to_string()))
+        .collect()
+}
+
+#[test]
+fn fetch_copies_trees_of_older_commits() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    let nested = remote_root.join("src/lib/old.txt");
+    fs::create_dir_all(nested.parent().unwrap()).unwrap();
+    commit_helper(&nested, "old", "first");
+    commit_helper(&remote_root.join("top.txt"), "new", "second");
+
+    let remote_objects = remote_root.join(".nag/objects");
+    let head_oid = read_oid(&remote_root.join(".nag/refs/heads/main"));
+    let parent_oid = commit_field(&remote_objects, &head_oid, "parent ")[0].clone();
+    let parent_tree = commit_field(&remote_objects, &parent_oid, "tree ")[0].clone();
+
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+    fetch_remote("origin".into()).unwrap();
+
+    // The older commit's tree, subtrees and blobs are all readable locally
+    let entries = read_tree_to_index(&parent_tree).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].path, "src/lib/old.txt");
+    assert!(object_path(&local_root.join(".nag/objects"), &entries[0].oids[0]).exists());
+}
+
+#[test]
+fn fetch_follows_every_parent_of_merge_commits() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    commit_helper(&remote_root.join("base.txt"), "base", "base");
+
+    branch("side".to_string(), None).unwrap();
+    checkout("side".to_string(), false).unwrap();
+    commit_helper(&remote_root.join("side.txt"), "side", "side work");
+    let side_oid = read_oid(&remote_root.join(".nag/refs/heads/side"));
+
+    checkout("main".to_string(), false).unwrap();
+    commit_helper(&remote_root.join("main.txt"), "main", "main work");
+    let main_oid = read_oid(&remote_root.join(".nag/refs/heads/main"));
+
+    // Hand-build a merge commit joining both lines of history
+    let remote_objects = remote_root.join(".nag/objects");
+    let tree = commit_field(&remote_objects, &main_oid, "tree ")[0].clone();
+    let merge_body = format!("tree {}\nparent {}\nparent {}\n\nmerge\n", tree, main_oid, side_oid);
+    let merge_oid = write_object(&Object::Commit(merge_body.into_bytes())).unwrap();
+    fs::write(remote_root.join(".nag/refs/heads/main"), &merge_oid).unwrap();
+    fs::remove_file(remote_root.join(".nag/refs/heads/side")).unwrap();
+
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+    fetch_remote("origin".into()).unwrap();
+
+    let local_objects = local_root.join(".nag/objects");
+    assert!(object_path(&local_objects, &merge_oid).exists());
+    assert!(object_path(&local_objects, &side_oid).exists(), "second parent must be fetched");
+    let side_tree = commit_field(&local_objects, &side_oid, "tree ")[0].clone();
+    let entries = read_tree_to_index(&side_tree).unwrap();
+    assert!(entries.iter().any(|e| e.path == "side.txt"));
+}
+
+#[test]
+fn fetch_leaves_refs_untouched_when_remote_is_incomplete() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    let file = remote_root.join("gone.txt");
+    commit_helper(&file, "will vanish", "first");
+
+    // Corrupt the remote by removing the blob
+    let blob = hash(&b"will vanish".to_vec());
+    fs::remove_file(object_path(&remote_root.join(".nag/objects"), &blob)).unwrap();
+
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+
+    assert!(fetch_remote("origin".into()).is_err());
+    assert!(!local_root.join(".nag/refs/remotes/origin/main").exists());
+}
+
+#[test]
+fn fetch_fills_in_history_an_earlier_fetch_left_incomplete() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    commit_helper(&remote_root.join("old.txt"), "old", "first");
+
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+    fetch_remote("origin".into()).unwrap();
+    let first = read_oid(&local_root.join(".nag/refs/remotes/origin/main"));
+
+    // An older fetch copied the commit but not its tree
+    let old_tree = commit_field(&local_root.join(".nag/objects"), &first, "tree ")[0].clone();
+    fs::remove_file(object_path(&local_root.join(".nag/objects"), &old_tree)).unwrap();
+
+    remote_commit_helper(&remote_root, &remote_root.join("new.txt"), "new", "second");
+    fetch_remote("origin".into()).unwrap();
+
+    let second = read_oid(&local_root.join(".nag/refs/remotes/origin/main"));
+    assert_eq!(commit_field(&local_root.join(".nag/objects"), &second, "parent "), vec![first]);
+    assert!(object_path(&local_root.join(".nag/objects"), &old_tree).exists(), "the missing tree is fetched again");
+    let entries = read_tree_to_index(&old_tree).unwrap();
+    assert!(entries.iter().any(|e| e.path == "old.txt"));
+}
+
+#[test]
+fn push_is_logged_in_the_remote() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    remote_commit_helper(&remote_root, &remote_root.join("r.txt"), "remote", "remote commit");
+    let remote_before = read_oid(&remote_root.join(".nag/refs/heads/main"));
+
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+    fetch_remote("origin".into()).unwrap();
+    fs::write(local_root.join(".nag/refs/heads/main"), &remote_before).unwrap();
+    commit_helper(&local_root.join("l.txt"), "local", "local commit");
+    let local_oid = read_oid(&local_root.join(".nag/refs/heads/main"));
+
+    allow_push_to_current_branch(&remote_root);
+    push_remote("origin".into(), None, false).unwrap();
+
+    // Both the branch and the remote's HEAD, which points at it, record the push
+    for log in ["logs/refs/heads/main", "logs/HEAD"] {
+        let contents = fs::read_to_string(remote_root.join(".nag").join(log)).unwrap();
+        let last = contents.lines().last().unwrap();
+        assert!(last.starts_with(&format!("{} {} ", remote_before, local_oid)), "{}: {}", log, last);
+        assert!(last.contains("\tpush: from "), "{}: {}", log, last);
+    }
+}
+
+#[test]
+fn push_refuses_the_branch_checked_out_in_the_remote() {
+    let tmp_remote = TempDir::new().unwrap();
+    let remote_root = init_test_repo(&tmp_remote);
+    remote_commit_helper(&remote_root, &remote_root.join("r.txt"), "remote", "remote commit");
+    let remote_before = read_oid(&remote_root.join(".nag/refs/heads/main"));
+
+    let tmp_local = TempDir::new().unwrap();
+    let local_root = init_test_repo(&tmp_local);
+    add_remote("origin".into(), remote_root.to_string_lossy().to_string()).unwrap();
+    fetch_remote("origin".into()).unwrap();
+    fs::write(local_root.join(".nag/refs/heads/main"), &remote_before).unwrap();
+    commit_helper(&local_root.join("l.txt"), "local", "local commit");
+    let local_oid = read_oid(&local_root.join(".nag/refs/heads/main"));
+
+    let err = push_remote("origin".into(), None, false).unwrap_err();
+    assert!(err.to_string().contains("checked out in remote 'origin'"));
+    assert_eq!(read_oid(&remote_root.join(".nag/refs/heads/main")), remote_before);
+
+    // Other branches are pushed as usual
+    fs::write(local_root.join(".nag/refs/heads/topic"), &local_oid).unwrap();
+    push_remote("origin".into(), Some("topic".into()), false).unwrap();
+    assert_eq!(read_oid(&remote_root.join(".nag/refs/heads/topic")), local_oid);
+
+    push_remote("origin".into(), None, true).unwrap();
+    assert_eq!(read_oid(&remote_root.join(".nag/refs/heads/main")), local_oid);
+}
@@ -0,0 +1,201 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    branch::branch,
+    checkout::checkout,
+    merge::merge,
+    reset::{ reset, reset_paths, ResetMode },
+};
+use crate::core::refs::{ resolve_head, read_ref, read_merge_head };
+use crate::core::reflog::read_reflog;
+use crate::core::index::read_index;
+use crate::core::diff::{ get_all_diffs, DiffType };
+use crate::core::object::Object;
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) -> String {
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+    resolve_head().unwrap().1
+}
+
+fn diff_paths(kind: DiffType) -> Vec<String> {
+    let mut paths = get_all_diffs().unwrap().remove(&kind).unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+fn index_oid(path: &str) -> Option<String> {
+    read_index().unwrap().into_iter().find(|entry| entry.path == path).map(|entry| entry.oids[0].clone())
+}
+
+#[test]
+fn soft_reset_moves_the_branch_and_keeps_changes_staged() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "one", "first");
+    commit_helper(&root.join("a.txt"), "two", "second");
+
+    reset(Some("HEAD~1".to_string()), ResetMode::Soft).unwrap();
+
+    assert_eq!(read_ref("main").unwrap(), first);
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "two");
+    assert_eq!(index_oid("a.txt").unwrap(), Object::Blob(b"two".to_vec()).oid());
+    assert_eq!(diff_paths(DiffType::Staged), vec!["a.txt"]);
+}
+
+#[test]
+fn mixed_reset_unstages_but_keeps_the_working_directory() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "one", "first");
+    fs::write(root.join("b.txt"), "new").unwrap();
+    add(&root.join("b.txt")).unwrap();
+    commit_helper(&root.join("a.txt"), "two", "second");
+
+    reset(Some(first.clone()), ResetMode::Mixed).unwrap();
+
+    assert_eq!(read_ref("main").unwrap(), first);
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "two");
+    assert_eq!(index_oid("a.txt").unwrap(), Object::Blob(b"one".to_vec()).oid());
+    assert_eq!(index_oid("b.txt"), None);
+    assert_eq!(diff_paths(DiffType::Modified), vec!["a.txt"]);
+    assert_eq!(diff_paths(DiffType::Untracked), vec!["b.txt"]);
+}
+
+#[test]
+fn hard_reset_rewrites_tracked_files_and_keeps_untracked_ones() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "one", "first");
+    fs::create_dir_all(root.join("dir")).unwrap();
+    fs::write(root.join("dir/b.txt"), "new").unwrap();
+    add(&root.join("dir/b.txt")).unwrap();
+    commit_helper(&root.join("a.txt"), "two", "second");
+    fs::write(root.join("a.txt"), "edited").unwrap();
+    fs::write(root.join("notes.txt"), "scratch").unwrap();
+
+    reset(Some("HEAD~1".to_string()), ResetMode::Hard).unwrap();
+
+    assert_eq!(read_ref("main").unwrap(), first);
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "one");
+    assert!(!root.join("dir").exists());
+    assert_eq!(fs::read_to_string(root.join("notes.txt")).unwrap(), "scratch");
+    assert!(diff_paths(DiffType::Modified).is_empty());
+    assert!(diff_paths(DiffType::Staged).is_empty());
+}
+
+#[test]
+fn hard_reset_to_head_discards_local_changes() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "one", "first");
+    fs::write(root.join("a.txt"), "edited").unwrap();
+    fs::write(root.join("b.txt"), "staged").unwrap();
+    add(&root.join("b.txt")).unwrap();
+
+    reset(None, ResetMode::Hard).unwrap();
+
+    assert_eq!(read_ref("main").unwrap(), first);
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "one");
+    assert!(!root.join("b.txt").exists());
+}
+
+#[test]
+fn hard_reset_can_move_forward_again_through_the_reflog() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let second = commit_helper(&root.join("a.txt"), "two", "second");
+    reset(Some("HEAD~1".to_string()), ResetMode::Hard).unwrap();
+
+    reset(Some("main@{1}".to_string()), ResetMode::Hard).unwrap();
+
+    assert_eq!(read_ref("main").unwrap(), second);
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "two");
+    assert_eq!(read_reflog("refs/heads/main").unwrap().last().unwrap().message, "reset: moving to main@{1}");
+}
+
+#[test]
+fn reset_moves_a_detached_head_without_touching_branches() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "one", "first");
+    let second = commit_helper(&root.join("a.txt"), "two", "second");
+    checkout(second.clone(), false).unwrap();
+
+    reset(Some(first.clone()), ResetMode::Hard).unwrap();
+
+    assert_eq!(resolve_head().unwrap(), (None, first));
+    assert_eq!(read_ref("main").unwrap(), second);
+}
+
+#[test]
+fn reset_clears_a_conflicted_merge() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "base", "first");
+    branch("topic".to_string(), None).unwrap();
+    commit_helper(&root.join("a.txt"), "ours", "ours");
+    checkout("topic".to_string(), false).unwrap();
+    commit_helper(&root.join("a.txt"), "theirs", "theirs");
+    checkout("main".to_string(), false).unwrap();
+    let _ = merge("topic".to_string());
+    assert!(read_merge_head().unwrap().is_some());
+
+    assert!(reset(None, ResetMode::Soft).is_err());
+    reset(None, ResetMode::Hard).unwrap();
+
+    assert!(read_merge_head().unwrap().is_none());
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "ours");
+    assert!(read_index().unwrap().iter().all(|entry| entry.oids.len() == 1));
+}
+
+#[test]
+fn reset_paths_unstages_only_the_named_paths() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "one", "first");
+    fs::write(root.join("a.txt"), "two").unwrap();
+    add(&root.join("a.txt")).unwrap();
+    fs::create_dir_all(root.join("dir")).unwrap();
+    fs::write(root.join("dir/new.txt"), "new").unwrap();
+    add(&root.join("dir/new.txt")).unwrap();
+    fs::write(root.join("c.txt"), "kept").unwrap();
+    add(&root.join("c.txt")).unwrap();
+
+    reset_paths(None, vec!["a.txt".to_string(), "dir/".to_string()]).unwrap();
+
+    assert_eq!(read_ref("main").unwrap(), first);
+    assert_eq!(index_oid("a.txt").unwrap(), Object::Blob(b"one".to_vec()).oid());
+    assert_eq!(index_oid("dir/new.txt"), None);
+    assert_eq!(index_oid("c.txt").unwrap(), Object::Blob(b"kept".to_vec()).oid());
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "two");
+    assert!(root.join("dir/new.txt").exists());
+}
+
+#[test]
+fn reset_paths_takes_entries_from_another_revision() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    commit_helper(&root.join("a.txt"), "two", "second");
+
+    reset_paths(Some("HEAD~1".to_string()), vec!["a.txt".to_string()]).unwrap();
+
+    assert_eq!(index_oid("a.txt").unwrap(), Object::Blob(b"one".to_vec()).oid());
+    assert_eq!(diff_paths(DiffType::Staged), vec!["a.txt"]);
+    assert!(reset_paths(None, vec!["missing.txt".to_string()]).is_err());
+}
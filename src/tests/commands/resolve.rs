@@ -39,13 +39,13 @@ fn resolve_clears_conflict_and_updates_index() {
 
     // create feature branch
     branch("feature".to_string(), None).unwrap();
-    checkout("feature".to_string()).unwrap();
+    checkout("feature".to_string(), false).unwrap();
 
     // feature: a.txt = "two"
     commit_helper(&file, "two", "c2");
 
     // back to main, diverge to create conflict
-    checkout("main".to_string()).unwrap();
+    checkout("main".to_string(), false).unwrap();
     fs::write(&file, "THREE").unwrap();
     add(&file).unwrap();
     commit("c3".to_string()).unwrap();
@@ -6,8 +6,11 @@ use crate::commands::{
     init::init,
     add::add,
     commit::commit,
+    branch::branch,
     restore::restore,
 };
+use crate::core::index::read_index;
+use crate::core::object::Object;
 use crate::core::repo::find_repo_root;
 
 // Helper: create and cd into initialized repo
@@ -38,7 +41,7 @@ fn restore_brings_back_deleted_file() {
     assert!(!file_path.exists());
 
     // restore from HEAD
-    restore("hello.txt".to_string()).unwrap();
+    restore("hello.txt".to_string(), None, false, false).unwrap();
 
     let restored = fs::read_to_string(&file_path).unwrap();
     assert_eq!(restored, "hello world");
@@ -62,7 +65,7 @@ fn restore_recovers_entire_directory() {
     fs::remove_file(&file_b).unwrap();
     assert!(!file_a.exists() && !file_b.exists());
 
-    restore("src".to_string()).unwrap();
+    restore("src".to_string(), None, false, false).unwrap();
 
     assert!(file_a.exists() && file_b.exists());
     assert_eq!(fs::read_to_string(&file_a).unwrap(), "fn main() {}");
@@ -78,7 +81,7 @@ fn restore_is_noop_when_file_matches_head() {
     commit_helper(&file_path, "data", "commit");
 
     let before = fs::read_to_string(&file_path).unwrap();
-    restore("noop.txt".to_string()).unwrap();
+    restore("noop.txt".to_string(), None, false, false).unwrap();
     let after = fs::read_to_string(&file_path).unwrap();
 
     assert_eq!(before, after);
@@ -92,7 +95,7 @@ fn restore_fails_on_untracked_path() {
     let file_path = root.join("tracked.txt");
     commit_helper(&file_path, "ok", "commit");
 
-    let result = restore("not_tracked.txt".to_string());
+    let result = restore("not_tracked.txt".to_string(), None, false, false);
     assert!(result.is_err());
 
     let err = result.unwrap_err();
@@ -116,8 +119,116 @@ fn restore_partially_missing_directory() {
     fs::remove_file(&a).unwrap(); // only delete one file
     assert!(!a.exists() && b.exists());
 
-    restore("docs".to_string()).unwrap();
+    restore("docs".to_string(), None, false, false).unwrap();
 
     assert!(a.exists() && b.exists());
     assert_eq!(fs::read_to_string(&a).unwrap(), "intro");
 }
+
+fn index_oid(path: &str) -> Option<String> {
+    read_index().unwrap().into_iter().find(|entry| entry.path == path).map(|entry| entry.oids[0].clone())
+}
+
+#[test]
+fn restore_staged_unstages_without_touching_the_file() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file_path = root.join("a.txt");
+    commit_helper(&file_path, "one", "first");
+    fs::write(&file_path, "two").unwrap();
+    add(&file_path).unwrap();
+
+    restore("a.txt".to_string(), None, true, false).unwrap();
+
+    assert_eq!(index_oid("a.txt").unwrap(), Object::Blob(b"one".to_vec()).oid());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "two");
+}
+
+#[test]
+fn restore_staged_drops_newly_added_files_from_the_index() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let new_file = root.join("new.txt");
+    fs::write(&new_file, "new").unwrap();
+    add(&new_file).unwrap();
+
+    restore("new.txt".to_string(), None, true, false).unwrap();
+
+    assert_eq!(index_oid("new.txt"), None);
+    assert!(new_file.exists());
+}
+
+#[test]
+fn restore_keeps_staged_files_that_were_never_committed() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let new_file = root.join("new.txt");
+    fs::write(&new_file, "new").unwrap();
+    add(&new_file).unwrap();
+    fs::write(&new_file, "new, edited since staging").unwrap();
+
+    assert!(restore("new.txt".to_string(), None, false, false).is_err());
+
+    assert_eq!(fs::read_to_string(&new_file).unwrap(), "new, edited since staging");
+    assert_eq!(index_oid("new.txt").unwrap(), Object::Blob(b"new".to_vec()).oid());
+}
+
+#[test]
+fn restore_staged_and_worktree_resets_both() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file_path = root.join("a.txt");
+    commit_helper(&file_path, "one", "first");
+    fs::write(&file_path, "two").unwrap();
+    add(&file_path).unwrap();
+
+    restore("a.txt".to_string(), None, true, true).unwrap();
+
+    assert_eq!(index_oid("a.txt").unwrap(), Object::Blob(b"one".to_vec()).oid());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "one");
+}
+
+#[test]
+fn restore_from_source_takes_an_older_commit_or_another_branch() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let file_path = root.join("a.txt");
+    commit_helper(&file_path, "one", "first");
+    branch("old".to_string(), None).unwrap();
+    commit_helper(&file_path, "two", "second");
+
+    restore("a.txt".to_string(), Some("HEAD~1".to_string()), false, false).unwrap();
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "one");
+    assert_eq!(index_oid("a.txt").unwrap(), Object::Blob(b"two".to_vec()).oid(), "the index is left alone");
+
+    fs::write(&file_path, "scratch").unwrap();
+    restore("a.txt".to_string(), Some("old".to_string()), true, true).unwrap();
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "one");
+    assert_eq!(index_oid("a.txt").unwrap(), Object::Blob(b"one".to_vec()).oid());
+
+    assert!(restore("a.txt".to_string(), Some("nope".to_string()), false, false).is_err());
+}
+
+#[test]
+fn restore_deletes_tracked_files_missing_from_the_source() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    let dir = root.join("docs");
+    fs::create_dir_all(&dir).unwrap();
+    commit_helper(&dir.join("guide.txt"), "guide", "second");
+
+    fs::write(dir.join("notes.txt"), "notes").unwrap();
+    add(&dir.join("notes.txt")).unwrap();
+
+    restore("docs".to_string(), Some("HEAD~1".to_string()), true, true).unwrap();
+
+    // Only the committed, unchanged file is deleted; the staged one is just unstaged
+    assert!(!dir.join("guide.txt").exists());
+    assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "notes");
+    assert_eq!(index_oid("docs/notes.txt"), None);
+    assert_eq!(index_oid("docs/guide.txt"), None);
+    assert_eq!(index_oid("a.txt").unwrap(), Object::Blob(b"one".to_vec()).oid());
+}
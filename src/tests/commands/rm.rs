@@ -0,0 +1,123 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    rm::rm,
+};
+use crate::core::index::read_index;
+use crate::core::diff::{ get_all_diffs, DiffType };
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+}
+
+fn tracked() -> Vec<String> {
+    read_index().unwrap().into_iter().map(|entry| entry.path).collect()
+}
+
+#[test]
+fn rm_deletes_the_file_and_stages_the_deletion() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    commit_helper(&root.join("b.txt"), "two", "second");
+
+    rm(vec!["a.txt".to_string()], false, false, false).unwrap();
+
+    assert!(!root.join("a.txt").exists());
+    assert_eq!(tracked(), vec!["b.txt"]);
+    assert_eq!(get_all_diffs().unwrap().remove(&DiffType::StagedDelete).unwrap_or_default(), vec!["a.txt"]);
+}
+
+#[test]
+fn rm_cached_keeps_the_file_on_disk() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+
+    rm(vec!["a.txt".to_string()], true, false, false).unwrap();
+
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "one");
+    assert!(tracked().is_empty());
+    assert_eq!(get_all_diffs().unwrap().remove(&DiffType::Untracked).unwrap_or_default(), vec!["a.txt"]);
+}
+
+#[test]
+fn rm_needs_r_for_directories() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("dir/a.txt"), "one", "first");
+    commit_helper(&root.join("dir/sub/b.txt"), "two", "second");
+    commit_helper(&root.join("keep.txt"), "keep", "third");
+
+    assert!(rm(vec!["dir".to_string()], false, false, false).is_err());
+    assert_eq!(tracked().len(), 3);
+
+    rm(vec!["dir".to_string()], false, true, false).unwrap();
+
+    assert!(!root.join("dir").exists());
+    assert_eq!(tracked(), vec!["keep.txt"]);
+}
+
+#[test]
+fn rm_refuses_uncommitted_changes_unless_forced() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    fs::write(root.join("a.txt"), "edited").unwrap();
+    fs::write(root.join("new.txt"), "new").unwrap();
+    add(&root.join("new.txt")).unwrap();
+
+    let err = rm(vec!["a.txt".to_string()], false, false, false).unwrap_err();
+    assert!(err.to_string().contains("a.txt"), "unexpected error: {}", err);
+    assert!(rm(vec!["new.txt".to_string()], false, false, false).is_err());
+    assert!(root.join("a.txt").exists());
+    assert_eq!(tracked(), vec!["a.txt", "new.txt"]);
+
+    rm(vec!["new.txt".to_string()], true, false, false).unwrap();
+    assert!(root.join("new.txt").exists());
+
+    rm(vec!["a.txt".to_string()], false, false, true).unwrap();
+    assert!(!root.join("a.txt").exists());
+    assert!(tracked().is_empty());
+}
+
+#[test]
+fn rm_changes_nothing_when_one_path_fails() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+
+    assert!(rm(vec!["a.txt".to_string(), "missing.txt".to_string()], false, false, false).is_err());
+
+    assert!(root.join("a.txt").exists());
+    assert_eq!(tracked(), vec!["a.txt"]);
+}
+
+#[test]
+fn rm_accepts_files_already_deleted_from_disk() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one", "first");
+    fs::remove_file(root.join("a.txt")).unwrap();
+
+    rm(vec!["a.txt".to_string()], false, false, false).unwrap();
+
+    assert!(tracked().is_empty());
+}
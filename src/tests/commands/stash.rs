@@ -0,0 +1,210 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    status::status,
+    gc::gc,
+    stash::{ stash_push, stash_list, stash_show, stash_apply, stash_pop, stash_drop },
+};
+use crate::core::index::{ read_index, EntryType };
+use crate::core::refs::read_ref;
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    let repo_path = tmp.path().to_string_lossy().to_string();
+    init(Some(repo_path));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) {
+    fs::write(path, content).unwrap();
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+}
+
+#[test]
+fn stash_push_saves_changes_and_resets_to_head() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\n", "first");
+    let clean_status = status(false).unwrap();
+    fs::write(root.join("a.txt"), "one\ntwo\n").unwrap();
+    fs::write(root.join("b.txt"), "new\n").unwrap();
+    add(&root.join("b.txt")).unwrap();
+
+    stash_push(None, false).unwrap();
+
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "one\n");
+    assert!(!root.join("b.txt").exists());
+    assert_eq!(status(false).unwrap(), clean_status);
+    assert!(!read_ref("refs/stash").unwrap().is_empty());
+    let list = stash_list(false).unwrap();
+    assert!(list.starts_with("stash@{0}: WIP on main: "), "unexpected list: {}", list);
+    assert!(list.trim_end().ends_with(" first"), "unexpected list: {}", list);
+    assert_eq!(stash_show(0, false).unwrap(), "M\ta.txt\nA\tb.txt\n");
+}
+
+#[test]
+fn stash_push_without_changes_saves_nothing() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\n", "first");
+
+    stash_push(None, false).unwrap();
+
+    assert_eq!(stash_list(false).unwrap(), "");
+    assert!(!root.join(".nag/refs/stash").exists());
+}
+
+#[test]
+fn stash_list_shows_newest_first() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\n", "first");
+    fs::write(root.join("a.txt"), "older\n").unwrap();
+    stash_push(Some("older".to_string()), false).unwrap();
+    fs::write(root.join("a.txt"), "newer\n").unwrap();
+    stash_push(Some("newer".to_string()), false).unwrap();
+
+    assert_eq!(stash_list(false).unwrap(), "stash@{0}: On main: newer\nstash@{1}: On main: older\n");
+}
+
+#[test]
+fn stash_pop_restores_changes_and_drops_the_entry() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\n", "first");
+    fs::write(root.join("a.txt"), "one\ntwo\n").unwrap();
+    fs::write(root.join("b.txt"), "new\n").unwrap();
+    add(&root.join("b.txt")).unwrap();
+    stash_push(None, false).unwrap();
+
+    stash_pop(0).unwrap();
+
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "one\ntwo\n");
+    assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "new\n");
+    assert!(read_index().unwrap().iter().any(|entry| entry.path == "b.txt"));
+    assert_eq!(stash_list(false).unwrap(), "");
+    assert!(!root.join(".nag/refs/stash").exists());
+}
+
+#[test]
+fn stash_apply_keeps_the_entry_and_picks_by_position() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\n", "first");
+    fs::write(root.join("a.txt"), "older\n").unwrap();
+    stash_push(Some("older".to_string()), false).unwrap();
+    fs::write(root.join("a.txt"), "newer\n").unwrap();
+    stash_push(Some("newer".to_string()), false).unwrap();
+
+    assert!(stash_apply(1).unwrap());
+
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "older\n");
+    assert_eq!(stash_list(false).unwrap().lines().count(), 2);
+}
+
+#[test]
+fn stash_apply_merges_with_later_commits() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\ntwo\nthree\n", "first");
+    fs::write(root.join("a.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+    stash_push(None, false).unwrap();
+    commit_helper(&root.join("a.txt"), "zero\none\ntwo\nthree\n", "second");
+
+    assert!(stash_apply(0).unwrap());
+
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "zero\none\ntwo\nthree\nfour\n");
+}
+
+#[test]
+fn stash_pop_with_conflicts_keeps_the_entry() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\n", "first");
+    fs::write(root.join("a.txt"), "stashed\n").unwrap();
+    stash_push(None, false).unwrap();
+    commit_helper(&root.join("a.txt"), "committed\n", "second");
+
+    stash_pop(0).unwrap();
+
+    let contents = fs::read_to_string(root.join("a.txt")).unwrap();
+    assert!(contents.contains("<<<<<<< Updated upstream"), "unexpected contents: {}", contents);
+    assert!(contents.contains(">>>>>>> Stashed changes"), "unexpected contents: {}", contents);
+    let index = read_index().unwrap();
+    assert_eq!(index.iter().find(|entry| entry.path == "a.txt").unwrap().entry_type, EntryType::X);
+    assert_eq!(stash_list(false).unwrap().lines().count(), 1);
+}
+
+#[test]
+fn stash_drop_removes_one_entry() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\n", "first");
+    fs::write(root.join("a.txt"), "older\n").unwrap();
+    stash_push(Some("older".to_string()), false).unwrap();
+    let older = read_ref("refs/stash").unwrap();
+    fs::write(root.join("a.txt"), "newer\n").unwrap();
+    stash_push(Some("newer".to_string()), false).unwrap();
+
+    stash_drop(0).unwrap();
+
+    assert_eq!(stash_list(false).unwrap(), "stash@{0}: On main: older\n");
+    assert_eq!(read_ref("refs/stash").unwrap(), older);
+    assert!(stash_drop(1).is_err());
+}
+
+#[test]
+fn stash_include_untracked_saves_and_restores_new_files() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\n", "first");
+    fs::write(root.join("notes.txt"), "scratch\n").unwrap();
+
+    stash_push(None, true).unwrap();
+    assert!(!root.join("notes.txt").exists());
+    assert_eq!(stash_show(0, false).unwrap(), "?\tnotes.txt\n");
+
+    fs::write(root.join("notes.txt"), "in the way\n").unwrap();
+    assert!(stash_apply(0).is_err());
+    fs::remove_file(root.join("notes.txt")).unwrap();
+
+    stash_pop(0).unwrap();
+    assert_eq!(fs::read_to_string(root.join("notes.txt")).unwrap(), "scratch\n");
+    assert!(!read_index().unwrap().iter().any(|entry| entry.path == "notes.txt"));
+}
+
+#[test]
+fn stash_without_include_untracked_leaves_new_files_alone() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\n", "first");
+    fs::write(root.join("a.txt"), "two\n").unwrap();
+    fs::write(root.join("notes.txt"), "scratch\n").unwrap();
+
+    stash_push(None, false).unwrap();
+
+    assert_eq!(fs::read_to_string(root.join("notes.txt")).unwrap(), "scratch\n");
+    assert_eq!(stash_show(0, false).unwrap(), "M\ta.txt\n");
+}
+
+#[test]
+fn gc_keeps_every_stash_entry() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "one\n", "first");
+    fs::write(root.join("a.txt"), "older\n").unwrap();
+    stash_push(Some("older".to_string()), false).unwrap();
+    fs::write(root.join("a.txt"), "newer\n").unwrap();
+    stash_push(Some("newer".to_string()), false).unwrap();
+
+    gc(0, false).unwrap();
+
+    assert!(stash_apply(1).unwrap());
+    assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "older\n");
+}
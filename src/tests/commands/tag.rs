@@ -7,7 +7,7 @@ use crate::commands::{
     commit::commit,
     tag::{ tag, list_tags, delete_tag },
 };
-use crate::core::io::read_file;
+use crate::core::io::{ read_file, read_object, object_path };
 use crate::core::repo::find_repo_root;
 use crate::core::hash::hash;
 
@@ -77,10 +77,10 @@ fn tag_creates_annotated_tag_with_message() {
     assert!(tag_ref.exists());
 
     let tag_oid = String::from_utf8_lossy(&read_file(&tag_ref.to_string_lossy()).unwrap()).trim().to_string();
-    let tag_object_path = find_repo_root().unwrap().join(".nag/objects").join(&tag_oid);
+    let tag_object_path = object_path(&find_repo_root().unwrap().join(".nag/objects"), &tag_oid);
     assert!(tag_object_path.exists());
 
-    let bytes = read_file(&tag_object_path.to_string_lossy()).unwrap();
+    let bytes = read_object(&tag_oid).unwrap().into_payload("tag").unwrap();
     let contents = String::from_utf8_lossy(&bytes);
     assert!(contents.contains("object "));
     assert!(contents.contains("stable release"));
@@ -0,0 +1,129 @@
+use tempfile::TempDir;
+use std::fs;
+
+use crate::core::ancestry::{ CommitGraph, merge_bases, is_ancestor };
+use crate::core::io::write_object;
+use crate::core::object::Object;
+
+fn init_fake_repo(tmp: &TempDir) -> std::path::PathBuf {
+    let root = tmp.path().to_path_buf();
+    fs::create_dir_all(root.join(".nag/objects")).unwrap();
+    fs::create_dir_all(root.join(".nag/refs/heads")).unwrap();
+    std::env::set_current_dir(&root).unwrap();
+    root
+}
+
+fn fake_commit(parents: &[&str], msg: &str) -> String {
+    let mut contents = String::from("tree empty\n");
+    for parent in parents {
+        contents.push_str(&format!("parent {}\n", parent));
+    }
+    contents.push_str("author a <a@x> 0 +0000\ncommitter a <a@x> 0 +0000\n\n");
+    contents.push_str(msg);
+    write_object(&Object::Commit(contents.into_bytes())).unwrap()
+}
+
+#[test]
+fn merge_base_of_linear_history_is_the_older_commit() {
+    let tmp = TempDir::new().unwrap();
+    init_fake_repo(&tmp);
+
+    let a = fake_commit(&[], "a");
+    let b = fake_commit(&[&a], "b");
+    let c = fake_commit(&[&b], "c");
+
+    assert_eq!(merge_bases(&b, &c).unwrap(), vec![b.clone()]);
+    assert_eq!(merge_bases(&c, &a).unwrap(), vec![a.clone()]);
+    assert_eq!(merge_bases(&c, &c).unwrap(), vec![c.clone()]);
+}
+
+#[test]
+fn merge_base_picks_the_fork_point_not_an_older_ancestor() {
+    let tmp = TempDir::new().unwrap();
+    init_fake_repo(&tmp);
+
+    let root = fake_commit(&[], "root");
+    let fork = fake_commit(&[&root], "fork");
+    let left = fake_commit(&[&fork], "left");
+    let left2 = fake_commit(&[&left], "left2");
+    let right = fake_commit(&[&fork], "right");
+
+    assert_eq!(merge_bases(&left2, &right).unwrap(), vec![fork]);
+}
+
+#[test]
+fn merge_base_reports_both_bases_of_a_criss_cross() {
+    let tmp = TempDir::new().unwrap();
+    init_fake_repo(&tmp);
+
+    let a = fake_commit(&[], "a");
+    let b = fake_commit(&[&a], "b");
+    let c = fake_commit(&[&a], "c");
+    let m1 = fake_commit(&[&b, &c], "m1");
+    let m2 = fake_commit(&[&c, &b], "m2");
+
+    let mut bases = merge_bases(&m1, &m2).unwrap();
+    bases.sort();
+    let mut expected = vec![b, c];
+    expected.sort();
+    assert_eq!(bases, expected);
+}
+
+#[test]
+fn merge_base_is_empty_for_unrelated_histories() {
+    let tmp = TempDir::new().unwrap();
+    init_fake_repo(&tmp);
+
+    let a = fake_commit(&[], "a");
+    let b = fake_commit(&[], "b");
+
+    assert!(merge_bases(&a, &b).unwrap().is_empty());
+}
+
+#[test]
+fn is_ancestor_follows_every_parent() {
+    let tmp = TempDir::new().unwrap();
+    init_fake_repo(&tmp);
+
+    let a = fake_commit(&[], "a");
+    let b = fake_commit(&[&a], "b");
+    let c = fake_commit(&[&a], "c");
+    let m = fake_commit(&[&b, &c], "m");
+
+    assert!(is_ancestor(&c, &m).unwrap());
+    assert!(is_ancestor(&a, &m).unwrap());
+    assert!(is_ancestor(&m, &m).unwrap());
+    assert!(!is_ancestor(&m, &b).unwrap());
+    assert!(!is_ancestor(&b, &c).unwrap());
+}
+
+#[test]
+fn generation_counts_the_longest_path_to_a_root() {
+    let tmp = TempDir::new().unwrap();
+    init_fake_repo(&tmp);
+
+    let a = fake_commit(&[], "a");
+    let b = fake_commit(&[&a], "b");
+    let c = fake_commit(&[&b], "c");
+    let m = fake_commit(&[&a, &c], "m");
+
+    let mut graph = CommitGraph::new();
+    assert_eq!(graph.generation(&a).unwrap(), 1);
+    assert_eq!(graph.generation(&m).unwrap(), 4);
+}
+
+#[test]
+fn generations_are_cached_across_graphs() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_fake_repo(&tmp);
+
+    let a = fake_commit(&[], "a");
+    let b = fake_commit(&[&a], "b");
+    let c = fake_commit(&[&b], "c");
+    assert_eq!(CommitGraph::new().generation(&c).unwrap(), 3);
+
+    // A new commit only needs its own parents read; the older history comes from the cache
+    let d = fake_commit(&[&c], "d");
+    fs::remove_file(crate::core::io::object_path(&root.join(".nag/objects"), &a)).unwrap();
+    assert_eq!(CommitGraph::new().generation(&d).unwrap(), 4);
+}
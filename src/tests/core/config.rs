@@ -0,0 +1,98 @@
+use tempfile::TempDir;
+use std::fs;
+
+use crate::core::config::{ read_config, read_effective_config_with, get_config_value, set_config_value, signature, local_timezone };
+use crate::tests::common::setup_nag_repo;
+
+#[test]
+fn set_then_get_round_trips() {
+    let tmp = TempDir::new().unwrap();
+    setup_nag_repo(&tmp);
+
+    set_config_value("user.name", "Ada Lovelace", false).unwrap();
+    assert_eq!(get_config_value("user.name").unwrap(), Some("Ada Lovelace".to_string()));
+
+    let raw = fs::read_to_string(tmp.path().join(".nag/config")).unwrap();
+    assert!(raw.contains("user.name = Ada Lovelace"));
+}
+
+#[test]
+fn set_overwrites_existing_key_in_place() {
+    let tmp = TempDir::new().unwrap();
+    setup_nag_repo(&tmp);
+
+    set_config_value("user.email", "old@example.com", false).unwrap();
+    set_config_value("core.editor", "vi", false).unwrap();
+    set_config_value("user.email", "new@example.com", false).unwrap();
+
+    let entries = read_config(false).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0], ("user.email".to_string(), "new@example.com".to_string()));
+}
+
+#[test]
+fn set_rejects_keys_without_section() {
+    let tmp = TempDir::new().unwrap();
+    setup_nag_repo(&tmp);
+
+    assert!(set_config_value("name", "x", false).is_err());
+    assert!(set_config_value("user.", "x", false).is_err());
+}
+
+#[test]
+fn read_config_skips_comments_and_blank_lines() {
+    let tmp = TempDir::new().unwrap();
+    setup_nag_repo(&tmp);
+
+    fs::write(tmp.path().join(".nag/config"), "# comment\n\nuser.name=Grace\n").unwrap();
+    let entries = read_config(false).unwrap();
+    assert_eq!(entries, vec![("user.name".to_string(), "Grace".to_string())]);
+}
+
+#[test]
+fn signature_uses_configured_identity() {
+    let tmp = TempDir::new().unwrap();
+    setup_nag_repo(&tmp);
+
+    set_config_value("user.name", "Ada", false).unwrap();
+    set_config_value("user.email", "ada@example.com", false).unwrap();
+    set_config_value("user.timezone", "+0100", false).unwrap();
+
+    let sig = signature().unwrap();
+    let parts: Vec<&str> = sig.rsplitn(3, ' ').collect();
+    assert_eq!(parts[0], "+0100");
+    assert!(parts[1].parse::<u64>().is_ok(), "timestamp must be numeric");
+    assert_eq!(parts[2], "Ada <ada@example.com>");
+}
+
+#[test]
+fn signature_defaults_to_the_system_timezone() {
+    let tmp = TempDir::new().unwrap();
+    setup_nag_repo(&tmp);
+
+    let sig = signature().unwrap();
+    let parts: Vec<&str> = sig.rsplitn(3, ' ').collect();
+    let timestamp: i64 = parts[1].parse().unwrap();
+    assert_eq!(parts[0], local_timezone(timestamp));
+    assert_eq!(parts[0].len(), 5);
+    assert!(parts[0].starts_with('+') || parts[0].starts_with('-'));
+}
+
+#[test]
+fn effective_config_merges_global_and_local_entries() {
+    let tmp = TempDir::new().unwrap();
+    setup_nag_repo(&tmp);
+    let home = TempDir::new().unwrap();
+
+    fs::write(home.path().join(".nagconfig"), "user.name = Global\ncore.editor = vi\n").unwrap();
+    set_config_value("user.name", "Local", false).unwrap();
+    set_config_value("user.email", "local@example.com", false).unwrap();
+
+    let entries = read_effective_config_with(Some(home.path())).unwrap();
+    assert_eq!(entries, vec![
+        ("user.name".to_string(), "Local".to_string()),
+        ("core.editor".to_string(), "vi".to_string()),
+        ("user.email".to_string(), "local@example.com".to_string()),
+    ]);
+    assert_eq!(read_effective_config_with(None).unwrap().len(), 2);
+}
@@ -164,3 +164,96 @@ fn diff_excludes_ignored_files() {
         }
     }
 }
+
+#[test]
+fn diff_lines_finds_minimal_edit_script() {
+    use crate::core::diff::{ diff_lines, LineOp };
+
+    let old = vec!["a", "b", "c", "a", "b", "b", "a"];
+    let new = vec!["c", "b", "a", "b", "a", "c"];
+    let ops = diff_lines(&old, &new);
+
+    let edits = ops.iter().filter(|op| !matches!(op, LineOp::Equal(_, _))).count();
+    assert_eq!(edits, 5, "Myers' classic example needs exactly five edits");
+
+    // Replaying the script must rebuild the new sequence
+    let rebuilt: Vec<&str> = ops.iter().filter_map(|op| match op {
+        LineOp::Equal(x, _) => Some(old[*x]),
+        LineOp::Insert(y) => Some(new[*y]),
+        LineOp::Delete(_) => None,
+    }).collect();
+    assert_eq!(rebuilt, new);
+}
+
+#[test]
+fn diff_lines_handles_empty_inputs() {
+    use crate::core::diff::{ diff_lines, LineOp };
+
+    let empty: Vec<&str> = vec![];
+    assert!(diff_lines(&empty, &empty).is_empty());
+    assert_eq!(diff_lines(&empty, &["x"]), vec![LineOp::Insert(0)]);
+    assert_eq!(diff_lines(&["x"], &empty), vec![LineOp::Delete(0)]);
+}
+
+#[test]
+fn unified_diff_splits_distant_changes_into_hunks() {
+    use crate::core::diff::unified_diff;
+
+    let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+    let new = "1\nTWO\n3\n4\n5\n6\n7\n8\n9\nTEN\n";
+
+    let out = unified_diff(old, new, 1);
+    assert_eq!(out, "@@ -1,3 +1,3 @@\n 1\n-2\n+TWO\n 3\n@@ -9,2 +9,2 @@\n 9\n-10\n+TEN\n");
+
+    let merged = unified_diff(old, new, 4);
+    assert_eq!(merged.matches("@@ -").count(), 1, "overlapping context must merge hunks");
+}
+
+#[test]
+fn unified_diff_marks_missing_trailing_newline() {
+    use crate::core::diff::unified_diff;
+
+    let out = unified_diff("a\n", "a\nb", 3);
+    assert!(out.contains("+b\n\\ No newline at end of file\n"));
+}
+
+#[test]
+fn merge3_combines_non_overlapping_edits() {
+    use crate::core::diff::{ merge3, MergeLabels };
+
+    let labels = MergeLabels { ours: "ours", base: "base", theirs: "theirs" };
+    let base = "a\nb\nc\nd\ne\n";
+    let ours = "A\nb\nc\nd\ne\n";
+    let theirs = "a\nb\nc\nd\nE\n";
+
+    let (merged, conflicted) = merge3(base, ours, theirs, &labels);
+    assert!(!conflicted);
+    assert_eq!(merged, "A\nb\nc\nd\nE\n");
+}
+
+#[test]
+fn merge3_marks_only_the_overlapping_region() {
+    use crate::core::diff::{ merge3, MergeLabels };
+
+    let labels = MergeLabels { ours: "ours", base: "base", theirs: "theirs" };
+    let base = "keep\nmid\nkeep too\n";
+    let ours = "keep\nours mid\nkeep too\n";
+    let theirs = "keep\ntheirs mid\nkeep too\n";
+
+    let (merged, conflicted) = merge3(base, ours, theirs, &labels);
+    assert!(conflicted);
+    assert_eq!(
+        merged,
+        "keep\n<<<<<<< ours\nours mid\n||||||| base\nmid\n=======\ntheirs mid\n>>>>>>> theirs\nkeep too\n"
+    );
+}
+
+#[test]
+fn merge3_accepts_identical_changes_on_both_sides() {
+    use crate::core::diff::{ merge3, MergeLabels };
+
+    let labels = MergeLabels { ours: "ours", base: "base", theirs: "theirs" };
+    let (merged, conflicted) = merge3("x\ny\n", "x\nz\n", "x\nz\n", &labels);
+    assert!(!conflicted);
+    assert_eq!(merged, "x\nz\n");
+}
@@ -5,7 +5,8 @@ use rand::random;
 use std::sync::Arc;
 use std::thread;
 
-use crate::core::io::write_object;
+use crate::core::io::{ write_object, object_path };
+use crate::core::object::Object;
 use crate::core::hash::hash;
 use crate::tests::common::setup_nag_repo;
 
@@ -17,13 +18,13 @@ fn writes_new_blob_once() {
     let data = b"hello".to_vec();
     let hash = hash(&data);
 
-    write_object(&data, &hash).unwrap();
+    write_object(&Object::Blob(data.clone())).unwrap();
 
-    let final_path = objects.join(&hash);
+    let final_path = object_path(&objects, &hash);
     assert!(final_path.exists());
 
     // writing same content twice is safe
-    write_object(&data, &hash).unwrap();
+    write_object(&Object::Blob(data.clone())).unwrap();
     assert!(final_path.exists());
 }
 
@@ -37,12 +38,12 @@ fn different_content_different_hash() {
     let h1 = hash(&d1);
     let h2 = hash(&d2);
 
-    write_object(&d1, &h1).unwrap();
-    write_object(&d2, &h2).unwrap();
+    write_object(&Object::Blob(d1.clone())).unwrap();
+    write_object(&Object::Blob(d2.clone())).unwrap();
 
     assert_ne!(h1, h2);
-    assert!(objects.join(&h1).exists());
-    assert!(objects.join(&h2).exists());
+    assert!(object_path(&objects, &h1).exists());
+    assert!(object_path(&objects, &h2).exists());
 }
 
 #[test]
@@ -53,13 +54,13 @@ fn file_contents_roundtrip() {
     let bytes: Vec<u8> = (0..256).map(|_| random::<u8>()).collect();
     let hash = hash(&bytes);
 
-    write_object(&bytes, &hash).unwrap();
+    write_object(&Object::Blob(bytes.clone())).unwrap();
 
-    let path = tmp.path().join(".nag").join("objects").join(&hash);
+    let path = object_path(&tmp.path().join(".nag").join("objects"), &hash);
     let mut f = fs::File::open(path).unwrap();
     let mut buf = Vec::new();
     f.read_to_end(&mut buf).unwrap();
-    assert_eq!(bytes, buf);
+    assert_eq!(Object::decompress(&buf).unwrap(), Object::Blob(bytes));
 }
 
 #[test]
@@ -75,9 +76,8 @@ fn concurrent_writes_race_safe() {
 
     for _ in 0..10 {
         let data = data.clone();
-        let hash = hash.clone();
         handles.push(thread::spawn(move || {
-            write_object(&data, &hash).unwrap();
+            write_object(&Object::Blob(data.clone())).unwrap();
         }));
     }
 
@@ -85,14 +85,19 @@ fn concurrent_writes_race_safe() {
         h.join().unwrap();
     }
 
-    let final_path = objects_arc.join(&hash);
+    let final_path = object_path(&objects_arc, &hash);
     assert!(final_path.exists());
 
-    // only OID files, no temp leftovers
+    // only fan-out directories holding OID files, no temp leftovers
     for entry in fs::read_dir(objects_arc.as_path()).unwrap() {
         let name = entry.unwrap().file_name();
         let name = name.to_string_lossy();
-        assert_eq!(name.len(), 64, "unexpected leftover: {name}");
+        assert_eq!(name.len(), 2, "unexpected leftover: {name}");
+    }
+    for entry in fs::read_dir(final_path.parent().unwrap()).unwrap() {
+        let name = entry.unwrap().file_name();
+        let name = name.to_string_lossy();
+        assert_eq!(name.len(), 62, "unexpected leftover: {name}");
     }
 }
 
@@ -103,14 +108,14 @@ fn preexisting_object_dedupe() {
 
     let data = b"existing".to_vec();
     let hash = hash(&data);
-    let path = objects.join(&hash);
+    let path = object_path(&objects, &hash);
 
     // manually create object file
     fs::write(&path, &data).unwrap();
     let mtime_before = fs::metadata(&path).unwrap().modified().unwrap();
 
     // call write_object on same content
-    write_object(&data, &hash).unwrap();
+    write_object(&Object::Blob(data.clone())).unwrap();
 
     let mtime_after = fs::metadata(&path).unwrap().modified().unwrap();
     assert_eq!(mtime_before, mtime_after, "object should not be rewritten");
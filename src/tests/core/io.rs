@@ -2,7 +2,8 @@ use std::fs;
 use std::path::PathBuf;
 use tempfile::TempDir;
 
-use crate::core::io::{read_file, write_file, write_object};
+use crate::core::io::{read_file, write_file, write_object, read_object, object_path, remove_empty_parents};
+use crate::core::object::Object;
 use crate::core::hash::hash;
 use crate::tests::common::setup_nag_repo;
 
@@ -32,14 +33,16 @@ fn write_object_creates_blob() {
     let objects = setup_nag_repo(&tmp);
 
     let data = b"some data".to_vec();
-    let oid = hash(&data);
-    write_object(&data, &oid).unwrap();
+    let oid = write_object(&Object::Blob(data.clone())).unwrap();
+    assert_eq!(oid, hash(&data));
 
-    let final_path = objects.join(&oid);
+    let final_path = object_path(&objects, &oid);
     assert!(final_path.exists());
 
+    // Stored compressed with a type header, never as the raw bytes
     let stored = fs::read(&final_path).unwrap();
-    assert_eq!(stored, data);
+    assert_ne!(stored, data);
+    assert_eq!(Object::decompress(&stored).unwrap(), Object::Blob(data));
 }
 
 #[test]
@@ -47,17 +50,15 @@ fn write_object_is_idempotent() {
     let tmp = TempDir::new().unwrap();
     let objects = setup_nag_repo(&tmp);
 
-    let data = b"idempotent".to_vec();
-    let oid = hash(&data);
-    let final_path = objects.join(&oid);
+    let object = Object::Blob(b"idempotent".to_vec());
+    let final_path = object_path(&objects, &object.oid());
 
     // first write
-    write_object(&data, &oid).unwrap();
+    write_object(&object).unwrap();
     let first = fs::read(&final_path).unwrap();
 
-    // second write with bogus content shouldn't overwrite
-    let bogus = b"different".to_vec();
-    write_object(&bogus, &oid).unwrap();
+    // second write of the same object shouldn't touch the file
+    write_object(&object).unwrap();
     let second = fs::read(&final_path).unwrap();
 
     assert_eq!(first, second, "existing blob was overwritten when it shouldn't be");
@@ -93,3 +94,84 @@ fn write_file_leaves_no_temp_files() {
         tmpfiles
     );
 }
+
+#[test]
+fn read_object_returns_typed_object() {
+    let tmp = TempDir::new().unwrap();
+    setup_nag_repo(&tmp);
+
+    let oid = write_object(&Object::Tree(b"C\t100644\ta.txt\tabc\n".to_vec())).unwrap();
+    let object = read_object(&oid).unwrap();
+
+    assert_eq!(object.kind(), "tree");
+    assert!(object.clone().into_payload("blob").is_err());
+    assert_eq!(object.into_payload("tree").unwrap(), b"C\t100644\ta.txt\tabc\n".to_vec());
+}
+
+#[test]
+fn same_bytes_with_different_types_get_different_oids() {
+    let tmp = TempDir::new().unwrap();
+    setup_nag_repo(&tmp);
+
+    let bytes = b"tree abc\n".to_vec();
+    let blob = write_object(&Object::Blob(bytes.clone())).unwrap();
+    let commit = write_object(&Object::Commit(bytes)).unwrap();
+
+    assert_ne!(blob, commit);
+    assert_eq!(read_object(&blob).unwrap().kind(), "blob");
+    assert_eq!(read_object(&commit).unwrap().kind(), "commit");
+}
+
+#[test]
+fn read_object_rejects_untyped_file() {
+    let tmp = TempDir::new().unwrap();
+    let objects = setup_nag_repo(&tmp);
+
+    fs::write(objects.join("deadbeef"), b"raw bytes").unwrap();
+
+    let err = read_object("deadbeef").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn write_object_fans_out_by_first_two_characters() {
+    let tmp = TempDir::new().unwrap();
+    let objects = setup_nag_repo(&tmp);
+
+    let oid = write_object(&Object::Blob(b"fan out".to_vec())).unwrap();
+
+    assert!(objects.join(&oid[..2]).join(&oid[2..]).is_file());
+    assert!(!objects.join(&oid).exists());
+    assert_eq!(object_path(&objects, &oid), objects.join(&oid[..2]).join(&oid[2..]));
+}
+
+#[test]
+fn read_object_falls_back_to_flat_layout() {
+    let tmp = TempDir::new().unwrap();
+    let objects = setup_nag_repo(&tmp);
+
+    let object = Object::Commit(b"tree abc\n\nflat\n".to_vec());
+    let oid = object.oid();
+    fs::write(objects.join(&oid), object.compress().unwrap()).unwrap();
+
+    assert_eq!(object_path(&objects, &oid), objects.join(&oid));
+    assert_eq!(read_object(&oid).unwrap(), object);
+
+    // Rewriting an object that already exists flat doesn't duplicate it
+    write_object(&object).unwrap();
+    assert!(!objects.join(&oid[..2]).exists());
+}
+
+#[test]
+fn remove_empty_parents_stops_at_the_first_non_empty_directory() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("a/b/c")).unwrap();
+    fs::write(root.join("a/keep.txt"), "keep").unwrap();
+
+    remove_empty_parents(root, &root.join("a/b/c/gone.txt"));
+
+    assert!(!root.join("a/b").exists());
+    assert!(root.join("a/keep.txt").exists());
+    assert!(root.exists());
+}
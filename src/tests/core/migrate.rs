@@ -0,0 +1,170 @@
+use tempfile::TempDir;
+use std::fs;
+use std::path::Path;
+use sha2::{ Sha256, Digest };
+
+use crate::core::migrate::{ migrate_if_needed, FORMAT_KEY, FORMAT_VERSION };
+use crate::core::config::read_config;
+use crate::core::commit::read_commit;
+use crate::core::tree::read_tree_to_index;
+use crate::core::index::read_index;
+use crate::core::io::{ read_object, loose_oids };
+use crate::core::object::Object;
+use crate::commands::{ init::init, clone::clone, remote::add_remote };
+use crate::core::refs::{ read_ref, resolve_revision };
+
+fn init_legacy_repo(tmp: &TempDir) -> std::path::PathBuf {
+    let root = tmp.path().to_path_buf();
+    fs::create_dir_all(root.join(".nag/objects")).unwrap();
+    fs::create_dir_all(root.join(".nag/refs/heads")).unwrap();
+    fs::create_dir_all(root.join(".nag/refs/tags")).unwrap();
+    fs::write(root.join(".nag/objects/empty"), b"").unwrap();
+    fs::write(root.join(".nag/HEAD"), b"ref: refs/heads/main\n").unwrap();
+    std::env::set_current_dir(&root).unwrap();
+    root
+}
+
+// Stores bytes the way repositories did before typed objects: raw, named by their own hash
+fn legacy_object(root: &Path, bytes: &str) -> String {
+    let oid = format!("{:x}", Sha256::digest(bytes.as_bytes()));
+    fs::write(root.join(".nag/objects").join(&oid), bytes).unwrap();
+    oid
+}
+
+fn legacy_history(root: &Path) -> (String, String) {
+    let blob = legacy_object(root, "hello\n");
+    let nested = legacy_object(root, "nested\n");
+    let subtree = legacy_object(root, &format!("C\t100644\tinner.txt\t{}\n", nested));
+    let tree = legacy_object(root, &format!("C\t100644\ta.txt\t{}\nC\t040000\tdir\t{}\n", blob, subtree));
+    let first = legacy_object(root, &format!("tree {}\nauthor a <a@x> 0 +0000\ncommitter a <a@x> 0 +0000\n\nfirst\n", tree));
+    let second = legacy_object(root, &format!("tree {}\nparent {}\nauthor a <a@x> 1 +0000\ncommitter a <a@x> 1 +0000\n\nsecond\ntree in message\n", tree, first));
+
+    fs::write(root.join(".nag/refs/heads/main"), &second).unwrap();
+    fs::write(root.join(".nag/refs/tags/light"), &first).unwrap();
+    let tag = legacy_object(root, &format!("object {}\n\nrelease\n", second));
+    fs::write(root.join(".nag/refs/tags/annotated"), &tag).unwrap();
+    fs::write(root.join(".nag/index"), format!("C\t100644\ta.txt\t{}\nC\t100644\tdir/inner.txt\t{}\n", blob, nested)).unwrap();
+
+    (first, second)
+}
+
+#[test]
+fn migration_rewrites_reachable_objects_and_refs() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_legacy_repo(&tmp);
+    let (old_first, old_second) = legacy_history(&root);
+
+    migrate_if_needed().unwrap();
+
+    let head = read_ref("main").unwrap();
+    assert_ne!(head, old_second);
+    let commit = read_commit(&head).unwrap();
+    assert_eq!(commit.message, "second\ntree in message");
+    assert_eq!(commit.parents.len(), 1);
+    assert_eq!(read_commit(&commit.parents[0]).unwrap().message, "first");
+    assert_eq!(read_ref("refs/tags/light").unwrap(), commit.parents[0]);
+    assert_eq!(resolve_revision("annotated").unwrap(), head);
+
+    let entries = read_tree_to_index(&commit.tree).unwrap();
+    let inner = entries.iter().find(|e| e.path == "dir/inner.txt").unwrap();
+    assert_eq!(read_object(&inner.oids[0]).unwrap().into_payload("blob").unwrap(), b"nested\n");
+
+    for entry in read_index().unwrap() {
+        let committed = entries.iter().find(|e| e.path == entry.path).unwrap();
+        assert_eq!(entry.oids, committed.oids);
+    }
+
+    // Legacy files are gone and the placeholder blob is readable
+    assert!(!root.join(".nag/objects").join(&old_first).exists());
+    assert!(!root.join(".nag/objects").join(&old_second).exists());
+    assert_eq!(read_object("empty").unwrap().into_payload("blob").unwrap(), b"");
+}
+
+#[test]
+fn migration_runs_only_once() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_legacy_repo(&tmp);
+    legacy_history(&root);
+
+    migrate_if_needed().unwrap();
+    let config = read_config(false).unwrap();
+    assert!(config.contains(&(FORMAT_KEY.to_string(), FORMAT_VERSION.to_string())));
+
+    let head = read_ref("main").unwrap();
+    migrate_if_needed().unwrap();
+    assert_eq!(read_ref("main").unwrap(), head);
+}
+
+#[test]
+fn migration_keeps_unreachable_legacy_objects() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_legacy_repo(&tmp);
+    let (old_first, _) = legacy_history(&root);
+    let stray = legacy_object(&root, "nobody points here");
+    let lost_blob = legacy_object(&root, "dropped work\n");
+    let lost_tree = legacy_object(&root, &format!("C\t100644\tlost.txt\t{}\n", lost_blob));
+    let lost_commit = legacy_object(&root, &format!("tree {}\nparent {}\nauthor a <a@x> 2 +0000\ncommitter a <a@x> 2 +0000\n\nlost\n", lost_tree, old_first));
+
+    migrate_if_needed().unwrap();
+
+    assert!(!root.join(".nag/objects").join(&stray).exists());
+    assert!(!root.join(".nag/objects").join(&lost_commit).exists());
+    let stray_oid = Object::Blob(b"nobody points here".to_vec()).oid();
+    assert_eq!(read_object(&stray_oid).unwrap().into_payload("blob").unwrap(), b"nobody points here");
+
+    // The dangling commit is still a commit on top of the migrated history
+    let (commit_oid, _) = loose_oids(&root.join(".nag/objects")).unwrap().into_iter()
+        .find(|(oid, _)| read_commit(oid).is_ok_and(|commit| commit.message == "lost"))
+        .expect("the dangling commit was converted");
+    let commit = read_commit(&commit_oid).unwrap();
+    assert_eq!(read_commit(&commit.parents[0]).unwrap().message, "first");
+    let entries = read_tree_to_index(&commit.tree).unwrap();
+    assert_eq!(read_object(&entries[0].oids[0]).unwrap().into_payload("blob").unwrap(), b"dropped work\n");
+
+    for (oid, _) in loose_oids(&root.join(".nag/objects")).unwrap() {
+        assert!(read_object(&oid).is_ok(), "{} was left in the legacy format", oid);
+    }
+}
+
+#[test]
+fn migration_skips_a_missing_historical_tree() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_legacy_repo(&tmp);
+    let blob = legacy_object(&root, "hello\n");
+    let tree = legacy_object(&root, &format!("C\t100644\ta.txt\t{}\n", blob));
+    let old_tree = legacy_object(&root, "C\t100644\told.txt\tempty\n");
+    let first = legacy_object(&root, &format!("tree {}\nauthor a <a@x> 0 +0000\ncommitter a <a@x> 0 +0000\n\nfirst\n", old_tree));
+    let second = legacy_object(&root, &format!("tree {}\nparent {}\nauthor a <a@x> 1 +0000\ncommitter a <a@x> 1 +0000\n\nsecond\n", tree, first));
+    fs::write(root.join(".nag/refs/heads/main"), &second).unwrap();
+    fs::remove_file(root.join(".nag/objects").join(&old_tree)).unwrap();
+
+    migrate_if_needed().unwrap();
+
+    let config = read_config(false).unwrap();
+    assert!(config.contains(&(FORMAT_KEY.to_string(), FORMAT_VERSION.to_string())));
+    let commit = read_commit(&read_ref("main").unwrap()).unwrap();
+    let entries = read_tree_to_index(&commit.tree).unwrap();
+    assert_eq!(read_object(&entries[0].oids[0]).unwrap().into_payload("blob").unwrap(), b"hello\n");
+
+    // The parent is converted but still names the tree that was never there
+    let parent = read_commit(&commit.parents[0]).unwrap();
+    assert_eq!(parent.message, "first");
+    assert_eq!(parent.tree, old_tree);
+}
+
+#[test]
+fn legacy_remotes_are_refused() {
+    let tmp_legacy = TempDir::new().unwrap();
+    let legacy_root = init_legacy_repo(&tmp_legacy);
+    legacy_history(&legacy_root);
+
+    let tmp = TempDir::new().unwrap();
+    std::env::set_current_dir(tmp.path()).unwrap();
+    init(Some(tmp.path().to_string_lossy().to_string()));
+
+    let err = add_remote("origin".to_string(), legacy_root.to_string_lossy().to_string()).unwrap_err();
+    assert!(err.to_string().contains("legacy object format"));
+    let err = clone(legacy_root.to_string_lossy().to_string(), Some(tmp.path().join("copy").to_string_lossy().to_string())).unwrap_err();
+    assert!(err.to_string().contains("legacy object format"));
+    assert!(!tmp.path().join("copy").exists());
+}
@@ -6,3 +6,7 @@ pub mod hash;
 pub mod diff;
 pub mod refs;
 pub mod ignore;
+pub mod config;
+pub mod ancestry;
+pub mod migrate;
+pub mod pack;
@@ -0,0 +1,109 @@
+use tempfile::TempDir;
+use std::fs;
+
+use crate::core::pack::{ create_delta, apply_delta, write_pack, read_packed_object, packed_oids, has_packed_object, PackInput };
+use crate::core::io::{ read_object, write_object, object_path };
+use crate::core::object::Object;
+use crate::tests::common::setup_nag_repo;
+
+fn blob_input(content: &[u8], hint: &str) -> PackInput {
+    PackInput { object: Object::Blob(content.to_vec()), hint: hint.to_string() }
+}
+
+fn numbered_lines(count: usize, changed: Option<usize>) -> Vec<u8> {
+    (0..count)
+        .map(|i| if Some(i) == changed { format!("changed line {}\n", i) } else { format!("line number {}\n", i) })
+        .collect::<String>()
+        .into_bytes()
+}
+
+#[test]
+fn delta_roundtrips_an_edited_file() {
+    let base = numbered_lines(200, None);
+    let target = numbered_lines(200, Some(120));
+
+    let delta = create_delta(&base, &target);
+    assert!(delta.len() < target.len() / 4, "delta of one changed line should be small");
+    assert_eq!(apply_delta(&base, &delta).unwrap(), target);
+}
+
+#[test]
+fn delta_roundtrips_unrelated_and_empty_content() {
+    for (base, target) in [
+        (b"abc".to_vec(), b"something completely different".to_vec()),
+        (Vec::new(), b"from nothing".to_vec()),
+        (b"to nothing".to_vec(), Vec::new()),
+    ] {
+        let delta = create_delta(&base, &target);
+        assert_eq!(apply_delta(&base, &delta).unwrap(), target);
+    }
+}
+
+#[test]
+fn delta_against_the_wrong_base_is_rejected() {
+    let base = numbered_lines(50, None);
+    let delta = create_delta(&base, &numbered_lines(50, Some(3)));
+
+    assert!(apply_delta(b"short", &delta).is_err());
+}
+
+#[test]
+fn packed_objects_read_back_including_deltas() {
+    let tmp = TempDir::new().unwrap();
+    let objects = setup_nag_repo(&tmp);
+
+    let versions: Vec<Vec<u8>> = (0..5).map(|i| numbered_lines(300, Some(i * 10))).collect();
+    let inputs = versions.iter().map(|v| blob_input(v, "file.txt")).collect();
+    let idx_path = write_pack(&objects, inputs).unwrap();
+
+    assert!(idx_path.exists());
+    assert!(idx_path.with_extension("pack").exists());
+    // Four of the five versions are stored as deltas, so the pack is far smaller than the content
+    let pack_len = fs::metadata(idx_path.with_extension("pack")).unwrap().len() as usize;
+    let raw_len: usize = versions.iter().map(|v| v.len()).sum();
+    assert!(pack_len < raw_len / 10, "pack is {} bytes for {} bytes of content", pack_len, raw_len);
+
+    for version in &versions {
+        let oid = Object::Blob(version.clone()).oid();
+        assert!(has_packed_object(&objects, &oid).unwrap());
+        assert_eq!(read_packed_object(&objects, &oid).unwrap(), Some(Object::Blob(version.clone())));
+    }
+    assert_eq!(read_packed_object(&objects, &"0".repeat(64)).unwrap(), None);
+}
+
+#[test]
+fn packed_oids_lists_each_object_once() {
+    let tmp = TempDir::new().unwrap();
+    let objects = setup_nag_repo(&tmp);
+
+    let inputs = vec![
+        blob_input(b"one", "a"),
+        blob_input(b"two", "b"),
+        blob_input(b"one", "c"),
+        PackInput { object: Object::Tree(b"C\t100644\ta\tx\n".to_vec()), hint: String::new() },
+    ];
+    write_pack(&objects, inputs).unwrap();
+
+    let mut oids = packed_oids(&objects).unwrap();
+    oids.sort();
+    let mut expected = vec![
+        Object::Blob(b"one".to_vec()).oid(),
+        Object::Blob(b"two".to_vec()).oid(),
+        Object::Tree(b"C\t100644\ta\tx\n".to_vec()).oid(),
+    ];
+    expected.sort();
+    assert_eq!(oids, expected);
+}
+
+#[test]
+fn read_object_falls_back_to_packs_and_write_skips_packed_objects() {
+    let tmp = TempDir::new().unwrap();
+    let objects = setup_nag_repo(&tmp);
+
+    let object = Object::Blob(b"packed only".to_vec());
+    write_pack(&objects, vec![blob_input(b"packed only", "f")]).unwrap();
+
+    assert_eq!(read_object(&object.oid()).unwrap(), object);
+    write_object(&object).unwrap();
+    assert!(!object_path(&objects, &object.oid()).exists());
+}
@@ -8,10 +8,25 @@ use crate::core::refs::{
     update_ref,
     set_head_ref,
     set_head_detached,
-    list_refs
+    list_refs,
+    resolve_revision,
+    resolve_object,
 };
+use crate::commands::{
+    init::init,
+    add::add,
+    commit::commit,
+    branch::branch,
+    checkout::checkout,
+    merge::merge,
+    tag::tag,
+};
+use crate::core::commit::{ read_commit, parse_date };
+use crate::core::reflog::{ read_reflog, write_reflog };
+use crate::core::config::set_config_value;
+use crate::core::object::Object;
 use crate::core::repo::find_repo_root;
-use crate::core::io::{ read_file, write_file };
+use crate::core::io::{ read_file, write_file, object_path };
 
 fn init_fake_repo(tmp: &TempDir) -> std::path::PathBuf {
     let root = tmp.path().to_path_buf();
@@ -128,7 +143,7 @@ fn update_ref_creates_and_writes_branch() {
     let ref_path = tmp.path().join(".nag/refs/heads/feature/test");
 
     // should create nested dirs and write oid
-    update_ref("feature/test", oid).unwrap();
+    update_ref("feature/test", oid, "").unwrap();
     assert!(ref_path.exists());
 
     let bytes = read_file(&ref_path.to_string_lossy()).unwrap();
@@ -142,8 +157,8 @@ fn update_ref_overwrites_existing_branch() {
     let tmp = TempDir::new().unwrap();
     init_fake_repo(&tmp);
 
-    update_ref("main", "111111").unwrap();
-    update_ref("main", "222222").unwrap();
+    update_ref("main", "111111", "").unwrap();
+    update_ref("main", "222222", "").unwrap();
 
     let ref_path = tmp.path().join(".nag/refs/heads/main");
     let bytes = read_file(&ref_path.to_string_lossy()).unwrap();
@@ -158,9 +173,9 @@ fn set_head_ref_points_to_existing_branch() {
     init_fake_repo(&tmp);
 
     // create a dummy branch first
-    update_ref("dev", "abc123").unwrap();
+    update_ref("dev", "abc123", "").unwrap();
 
-    set_head_ref("dev").unwrap();
+    set_head_ref("dev", "").unwrap();
 
     let head_path = tmp.path().join(".nag/HEAD");
     let bytes = read_file(&head_path.to_string_lossy()).unwrap();
@@ -174,7 +189,7 @@ fn set_head_ref_fails_on_missing_branch() {
     let tmp = TempDir::new().unwrap();
     init_fake_repo(&tmp);
 
-    let result = set_head_ref("nope");
+    let result = set_head_ref("nope", "");
     assert!(result.is_err());
 }
 
@@ -186,10 +201,10 @@ fn set_head_detached_writes_oid_to_head() {
 
     // simulate object file for valid oid
     let oid = "xyz789";
-    let obj_path = tmp.path().join(".nag/objects").join(oid);
+    let obj_path = object_path(&tmp.path().join(".nag/objects"), oid);
     write_file(&b"dummy data".to_vec(), &obj_path).unwrap();
 
-    set_head_detached(oid).unwrap();
+    set_head_detached(oid, "").unwrap();
 
     let head_path = tmp.path().join(".nag/HEAD");
     let bytes = read_file(&head_path.to_string_lossy()).unwrap();
@@ -203,7 +218,7 @@ fn set_head_detached_fails_on_missing_object() {
     let tmp = TempDir::new().unwrap();
     init_fake_repo(&tmp);
 
-    let result = set_head_detached("missingoid");
+    let result = set_head_detached("missingoid", "");
     assert!(result.is_err());
 }
 
@@ -263,3 +278,193 @@ fn list_refs_returns_empty_for_missing_prefix() {
     assert!(refs.is_empty());
 }
 
+
+fn init_test_repo(tmp: &TempDir) -> std::path::PathBuf {
+    std::env::set_current_dir(tmp.path()).unwrap();
+    init(Some(tmp.path().to_string_lossy().to_string()));
+    tmp.path().to_path_buf()
+}
+
+fn commit_helper(path: &Path, content: &str, msg: &str) -> String {
+    write(path, content);
+    add(path).unwrap();
+    commit(msg.to_string()).unwrap();
+    resolve_head().unwrap().1
+}
+
+#[test]
+fn resolve_revision_walks_first_parents_with_tilde() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "1", "first");
+    let second = commit_helper(&root.join("a.txt"), "2", "second");
+    let third = commit_helper(&root.join("a.txt"), "3", "third");
+
+    assert_eq!(resolve_revision("HEAD").unwrap(), third);
+    assert_eq!(resolve_revision("@").unwrap(), third);
+    assert_eq!(resolve_revision("HEAD~").unwrap(), second);
+    assert_eq!(resolve_revision("HEAD~2").unwrap(), first);
+    assert_eq!(resolve_revision("main~1~1").unwrap(), first);
+    assert_eq!(resolve_revision("main^^").unwrap(), first);
+    assert!(resolve_revision("HEAD~3").is_err());
+}
+
+#[test]
+fn resolve_revision_rejects_other_characters_after_an_ancestor_step() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "1", "first");
+    commit_helper(&root.join("a.txt"), "2", "second");
+
+    for spec in ["HEAD~é", "HEAD^é", "HEAD~1é", "main~x", "HEAD~0^ü"] {
+        let err = resolve_revision(spec).unwrap_err();
+        assert_eq!(err.to_string(), format!("Unknown revision '{}'", spec));
+    }
+}
+
+#[test]
+fn resolve_revision_picks_merge_parents_with_caret() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "base", "base");
+    branch("feature".to_string(), None).unwrap();
+    let main_tip = commit_helper(&root.join("a.txt"), "main", "main work");
+    checkout("feature".to_string(), false).unwrap();
+    let feature_tip = commit_helper(&root.join("b.txt"), "feature", "feature work");
+    checkout("main".to_string(), false).unwrap();
+    merge("feature".to_string()).unwrap();
+    let merge_oid = resolve_head().unwrap().1;
+
+    assert_eq!(read_commit(&merge_oid).unwrap().parents.len(), 2);
+    assert_eq!(resolve_revision("main^0").unwrap(), merge_oid);
+    assert_eq!(resolve_revision("main^1").unwrap(), main_tip);
+    assert_eq!(resolve_revision("main^2").unwrap(), feature_tip);
+    assert!(resolve_revision("main^3").is_err());
+}
+
+#[test]
+fn resolve_revision_accepts_unique_short_oids_and_peels_tags() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "1", "first");
+    commit_helper(&root.join("a.txt"), "2", "second");
+    tag(Some("v1".to_string()), Some("HEAD~1".to_string()), Some("release".to_string())).unwrap();
+
+    assert_eq!(resolve_revision(&first[..10]).unwrap(), first);
+    assert_eq!(resolve_revision(&first).unwrap(), first);
+    assert_eq!(resolve_revision("v1").unwrap(), first);
+    assert_eq!(resolve_revision("refs/tags/v1").unwrap(), first);
+    assert!(resolve_revision(&first[..3]).is_err(), "prefixes under four characters are not looked up");
+    assert!(resolve_revision("nonexistent").is_err());
+}
+
+#[test]
+fn resolve_revision_rejects_ambiguous_short_oids() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    commit_helper(&root.join("a.txt"), "1", "first");
+    // Write blobs until two oids share a four-character prefix
+    let mut seen = std::collections::HashMap::new();
+    let mut i = 0;
+    let prefix = loop {
+        let oid = crate::core::io::write_object(&Object::Blob(format!("blob {}", i).into_bytes())).unwrap();
+        if seen.insert(oid[..4].to_string(), oid.clone()).is_some() {
+            break oid[..4].to_string();
+        }
+        i += 1;
+    };
+
+    let err = resolve_object(&prefix).unwrap_err();
+    assert!(err.to_string().contains("ambiguous"), "unexpected error: {}", err);
+}
+
+#[test]
+fn resolve_object_finds_paths_inside_a_commit() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    write(&root.join("dir/nested.txt"), "nested v1");
+    add(&root.join("dir/nested.txt")).unwrap();
+    commit_helper(&root.join("a.txt"), "1", "first");
+    commit_helper(&root.join("dir/nested.txt"), "nested v2", "second");
+
+    assert_eq!(resolve_object("HEAD~1:dir/nested.txt").unwrap(), Object::Blob(b"nested v1".to_vec()).oid());
+    assert_eq!(resolve_object("main:dir/nested.txt").unwrap(), Object::Blob(b"nested v2".to_vec()).oid());
+    assert!(resolve_object("HEAD:dir/missing.txt").is_err());
+    let tree = resolve_object("HEAD:dir").unwrap();
+    assert!(matches!(crate::core::io::read_object(&tree).unwrap(), Object::Tree(_)));
+    assert!(resolve_revision("HEAD:dir").is_err(), "a tree is not a commit");
+}
+
+#[test]
+fn resolve_revision_follows_upstream() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "1", "first");
+    commit_helper(&root.join("a.txt"), "2", "second");
+    update_ref("refs/remotes/origin/main", &first, "").unwrap();
+    update_ref("refs/remotes/upstream/trunk", &first, "").unwrap();
+
+    assert_eq!(resolve_revision("@{upstream}").unwrap(), first);
+    assert_eq!(resolve_revision("main@{u}").unwrap(), first);
+    assert!(resolve_revision("main@{u}~1").is_err());
+
+    branch("topic".to_string(), None).unwrap();
+    assert!(resolve_revision("topic@{upstream}").is_err());
+    set_config_value("branch.topic.remote", "upstream", false).unwrap();
+    set_config_value("branch.topic.merge", "refs/heads/trunk", false).unwrap();
+    assert_eq!(resolve_revision("topic@{upstream}").unwrap(), first);
+}
+
+#[test]
+fn resolve_revision_walks_the_reflog_by_position() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "1", "first");
+    let second = commit_helper(&root.join("a.txt"), "2", "second");
+    branch("topic".to_string(), Some(first.clone())).unwrap();
+    checkout("topic".to_string(), false).unwrap();
+
+    assert_eq!(resolve_revision("HEAD@{0}").unwrap(), first);
+    assert_eq!(resolve_revision("HEAD@{1}").unwrap(), second);
+    assert_eq!(resolve_revision("main@{1}").unwrap(), first);
+    assert_eq!(resolve_revision("main@{0}~1").unwrap(), first);
+    assert_eq!(resolve_revision("@{0}").unwrap(), first, "a bare @{{N}} reads the current branch's log");
+    assert_eq!(resolve_object("main@{1}:a.txt").unwrap(), Object::Blob(b"1".to_vec()).oid());
+    assert!(resolve_revision("main@{2}").is_err());
+    assert!(resolve_revision("nope@{0}").is_err());
+}
+
+#[test]
+fn resolve_revision_walks_the_reflog_by_date() {
+    let tmp = TempDir::new().unwrap();
+    let root = init_test_repo(&tmp);
+    let first = commit_helper(&root.join("a.txt"), "1", "first");
+    let second = commit_helper(&root.join("a.txt"), "2", "second");
+
+    // Backdate the two moves to noon on consecutive days
+    let mut entries = read_reflog("refs/heads/main").unwrap();
+    entries[0].who.timestamp = parse_date("2024-03-01 12:00:00", 0).unwrap();
+    entries[1].who.timestamp = parse_date("2024-03-02 12:00:00", 0).unwrap();
+    write_reflog("refs/heads/main", &entries).unwrap();
+
+    assert_eq!(resolve_revision("main@{2024-03-01 18:00}").unwrap(), first);
+    assert_eq!(resolve_revision("main@{2024-03-02T12:00:00}").unwrap(), second);
+    assert_eq!(resolve_revision("main@{2024-03-02 12:00:00 +0100}").unwrap(), first);
+    assert_eq!(resolve_revision("main@{2024-03-02 12:00:00 -0100}").unwrap(), second);
+    assert_eq!(resolve_revision("main@{1 day ago}").unwrap(), second);
+    assert!(resolve_revision("main@{2024-02-01}").is_err());
+    assert!(resolve_revision("main@{next tuesday}").is_err());
+}
+
+#[test]
+fn parse_date_reads_relative_and_absolute_dates() {
+    let now = 1_700_000_000;
+    assert_eq!(parse_date("now", now), Some(now));
+    assert_eq!(parse_date("yesterday", now), Some(now - 86_400));
+    assert_eq!(parse_date("2.hours.ago", now), Some(now - 7200));
+    assert_eq!(parse_date("1 week ago", now), Some(now - 604_800));
+    assert_eq!(parse_date("1970-01-02", now), Some(86_400));
+    assert_eq!(parse_date("2000-03-01 00:00:01", now), Some(951_868_801));
+    assert_eq!(parse_date("2000-13-01", now), None);
+    assert_eq!(parse_date("soon", now), None);
+}
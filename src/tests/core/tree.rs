@@ -2,12 +2,13 @@ use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use tempfile::TempDir;
 
-use crate::core::tree::{write_tree, write_tree_from_index};
+use crate::core::tree::{write_tree, write_tree_from_index, serialize_tree, is_canonical_tree, TreeEntry};
 use crate::core::index::IndexEntry;
 use crate::core::index::EntryType;
 use crate::core::repo::find_repo_root;
 use crate::core::hash::hash;
-use crate::core::io::{read_file, write_object};
+use crate::core::io::{read_object, write_object, object_path};
+use crate::core::object::Object;
 
 fn init_repo(tmp: &TempDir) {
     let nag_root = tmp.path().join(".nag");
@@ -27,10 +28,10 @@ fn write_tree_with_single_file() {
 
     let tree_hash = write_tree(&tmp.path().to_path_buf()).unwrap();
 
-    let tree_path = tmp.path().join(".nag/objects").join(&tree_hash);
+    let tree_path = object_path(&tmp.path().join(".nag/objects"), &tree_hash);
     assert!(tree_path.exists());
 
-    let tree_content = String::from_utf8(read_file(tree_path.to_str().unwrap()).unwrap()).unwrap();
+    let tree_content = String::from_utf8(read_object(&tree_hash).unwrap().into_payload("tree").unwrap()).unwrap();
     assert!(tree_content.contains("100644\tfile.txt"));
 }
 
@@ -45,8 +46,7 @@ fn write_tree_with_nested_dir() {
     fs::write(&file_path, b"nested").unwrap();
 
     let tree_hash = write_tree(&tmp.path().to_path_buf()).unwrap();
-    let tree_path = tmp.path().join(".nag/objects").join(&tree_hash);
-    let tree_content = String::from_utf8(read_file(tree_path.to_str().unwrap()).unwrap()).unwrap();
+    let tree_content = String::from_utf8(read_object(&tree_hash).unwrap().into_payload("tree").unwrap()).unwrap();
 
     assert!(tree_content.contains("040000\tsubdir"));
 }
@@ -63,8 +63,7 @@ fn write_tree_sets_exec_permission() {
     fs::set_permissions(&file_path, perms).unwrap();
 
     let tree_hash = write_tree(&tmp.path().to_path_buf()).unwrap();
-    let tree_path = tmp.path().join(".nag/objects").join(&tree_hash);
-    let tree_content = String::from_utf8(read_file(tree_path.to_str().unwrap()).unwrap()).unwrap();
+    let tree_content = String::from_utf8(read_object(&tree_hash).unwrap().into_payload("tree").unwrap()).unwrap();
 
     assert!(tree_content.contains("100755\trun.sh"));
 }
@@ -78,8 +77,7 @@ fn write_tree_skips_nag_dir() {
     fs::write(&hidden, b"secret").unwrap();
 
     let tree_hash = write_tree(&tmp.path().to_path_buf()).unwrap();
-    let tree_path = tmp.path().join(".nag/objects").join(&tree_hash);
-    let tree_content = String::from_utf8(read_file(tree_path.to_str().unwrap()).unwrap()).unwrap();
+    let tree_content = String::from_utf8(read_object(&tree_hash).unwrap().into_payload("tree").unwrap()).unwrap();
 
     assert!(!tree_content.contains("hidden.txt"));
 }
@@ -90,8 +88,7 @@ fn write_tree_empty_repo() {
     init_repo(&tmp);
 
     let tree_hash = write_tree(&tmp.path().to_path_buf()).unwrap();
-    let tree_path = tmp.path().join(".nag/objects").join(&tree_hash);
-    let tree_content = String::from_utf8(read_file(tree_path.to_str().unwrap()).unwrap()).unwrap();
+    let tree_content = String::from_utf8(read_object(&tree_hash).unwrap().into_payload("tree").unwrap()).unwrap();
 
     assert!(tree_content.is_empty());
 }
@@ -103,7 +100,7 @@ fn write_tree_from_index_single_file() {
 
     let blob_bytes = b"hello".to_vec();
     let oid = hash(&blob_bytes);
-    write_object(&blob_bytes, &oid).unwrap();
+    write_object(&Object::Blob(blob_bytes.clone())).unwrap();
 
     let entries = vec![
         IndexEntry {
@@ -116,10 +113,10 @@ fn write_tree_from_index_single_file() {
 
     let tree_oid = write_tree_from_index(&entries).unwrap();
 
-    let tree_path = tmp.path().join(".nag/objects").join(&tree_oid);
+    let tree_path = object_path(&tmp.path().join(".nag/objects"), &tree_oid);
     assert!(tree_path.exists());
 
-    let tree_data = fs::read_to_string(tree_path).unwrap();
+    let tree_data = String::from_utf8(read_object(&tree_oid).unwrap().into_payload("tree").unwrap()).unwrap();
     assert!(tree_data.contains("file.txt"));
     assert!(tree_data.contains(&oid));
 }
@@ -135,8 +132,8 @@ fn write_tree_from_index_nested_directories() {
     let oid_a = hash(&blob_a);
     let oid_b = hash(&blob_b);
 
-    write_object(&blob_a, &oid_a).unwrap();
-    write_object(&blob_b, &oid_b).unwrap();
+    write_object(&Object::Blob(blob_a.clone())).unwrap();
+    write_object(&Object::Blob(blob_b.clone())).unwrap();
 
     let entries = vec![
         IndexEntry {
@@ -154,8 +151,7 @@ fn write_tree_from_index_nested_directories() {
     ];
 
     let tree_oid = write_tree_from_index(&entries).unwrap();
-    let tree_path = tmp.path().join(".nag/objects").join(&tree_oid);
-    let tree_data = fs::read_to_string(tree_path).unwrap();
+    let tree_data = String::from_utf8(read_object(&tree_oid).unwrap().into_payload("tree").unwrap()).unwrap();
 
     assert!(tree_data.contains("src"));
     assert!(tree_data.contains("040000"));
@@ -171,8 +167,8 @@ fn write_tree_from_index_multiple_directories() {
     let oid_a = hash(&blob_a);
     let oid_b = hash(&blob_b);
 
-    write_object(&blob_a, &oid_a).unwrap();
-    write_object(&blob_b, &oid_b).unwrap();
+    write_object(&Object::Blob(blob_a.clone())).unwrap();
+    write_object(&Object::Blob(blob_b.clone())).unwrap();
 
     let entries = vec![
         IndexEntry {
@@ -190,7 +186,7 @@ fn write_tree_from_index_multiple_directories() {
     ];
 
     let tree_oid = write_tree_from_index(&entries).unwrap();
-    let tree_data = fs::read_to_string(tmp.path().join(".nag/objects").join(&tree_oid)).unwrap();
+    let tree_data = String::from_utf8(read_object(&tree_oid).unwrap().into_payload("tree").unwrap()).unwrap();
 
     assert!(tree_data.contains("dir1"));
     assert!(tree_data.contains("dir2"));
@@ -204,7 +200,7 @@ fn write_tree_from_index_empty_index_creates_empty_tree() {
     let entries: Vec<IndexEntry> = vec![];
 
     let tree_oid = write_tree_from_index(&entries).unwrap();
-    let tree_data = fs::read_to_string(tmp.path().join(".nag/objects").join(&tree_oid)).unwrap();
+    let tree_data = String::from_utf8(read_object(&tree_oid).unwrap().into_payload("tree").unwrap()).unwrap();
 
     assert!(tree_data.is_empty() || !tree_data.contains('\n'));
 }
@@ -229,3 +225,61 @@ fn write_tree_from_index_missing_blob_errors() {
 
     assert!(result.is_err(), "should error on missing blob object");
 }
+
+fn index_entry(path: &str, oid: &str) -> IndexEntry {
+    IndexEntry {
+        entry_type: EntryType::C,
+        mode: "100644".to_string(),
+        path: path.to_string(),
+        oids: vec![oid.to_string()],
+    }
+}
+
+#[test]
+fn write_tree_from_index_ignores_index_order() {
+    let tmp = TempDir::new().unwrap();
+    init_repo(&tmp);
+    let oid = write_object(&Object::Blob(b"same".to_vec())).unwrap();
+    let paths = ["b.txt", "a.txt", "dir/z.txt", "dir/sub/y.txt", "other/x.txt", "a"];
+
+    let forward: Vec<IndexEntry> = paths.iter().map(|path| index_entry(path, &oid)).collect();
+    let backward: Vec<IndexEntry> = paths.iter().rev().map(|path| index_entry(path, &oid)).collect();
+
+    assert_eq!(write_tree_from_index(&forward).unwrap(), write_tree_from_index(&backward).unwrap());
+}
+
+#[test]
+fn trees_sort_subdirectories_as_if_named_with_a_slash() {
+    let tmp = TempDir::new().unwrap();
+    init_repo(&tmp);
+    let oid = write_object(&Object::Blob(b"same".to_vec())).unwrap();
+    let entries = vec![index_entry("a/inner.txt", &oid), index_entry("a.txt", &oid), index_entry("a-b", &oid)];
+
+    let tree_oid = write_tree_from_index(&entries).unwrap();
+    let tree_data = read_object(&tree_oid).unwrap().into_payload("tree").unwrap();
+    let names: Vec<String> = String::from_utf8(tree_data.clone()).unwrap().lines()
+        .map(|line| line.split('\t').nth(2).unwrap().to_string())
+        .collect();
+
+    assert_eq!(names, vec!["a-b", "a.txt", "a"]);
+    assert!(is_canonical_tree(&tree_data));
+}
+
+#[test]
+fn is_canonical_tree_rejects_unsorted_repeated_and_malformed_entries() {
+    let entry = |name: &str| TreeEntry {
+        entry_type: EntryType::C,
+        mode: "100644".to_string(),
+        name: name.to_string(),
+        oid: "abc".to_string(),
+    };
+    let sorted = serialize_tree(vec![entry("b"), entry("a")]);
+    assert_eq!(String::from_utf8(sorted.clone()).unwrap(), "C\t100644\ta\tabc\nC\t100644\tb\tabc\n");
+    assert!(is_canonical_tree(&sorted));
+    assert!(is_canonical_tree(b""));
+
+    assert!(!is_canonical_tree(b"C\t100644\tb\tabc\nC\t100644\ta\tabc\n"));
+    assert!(!is_canonical_tree(b"C\t100644\ta\tabc\nC\t100644\ta\tabc\n"));
+    assert!(!is_canonical_tree(b"C\t100644\ta\n"));
+    assert!(!is_canonical_tree(b"C\t100644\ta\tabc"));
+}